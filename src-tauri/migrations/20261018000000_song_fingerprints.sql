@@ -0,0 +1,7 @@
+-- Acoustic fingerprints used for duplicate detection
+CREATE TABLE IF NOT EXISTS song_fingerprints (
+    song_id TEXT PRIMARY KEY,
+    fingerprint BLOB NOT NULL, -- little-endian u32 sub-fingerprints
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- Application settings, stored as JSON documents by key
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL, -- JSON object
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,47 @@
+-- Albums, identified by title plus album artist
+CREATE TABLE IF NOT EXISTS albums (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    album_artist TEXT NOT NULL, -- 'Various Artists' for compilations
+    compilation BOOLEAN NOT NULL DEFAULT FALSE,
+    year INTEGER,
+    disc_count INTEGER,
+    track_count INTEGER,
+    image TEXT, -- cover cache id
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(title, album_artist)
+);
+
+ALTER TABLE songs ADD COLUMN album_id TEXT REFERENCES albums(id) ON DELETE SET NULL;
+ALTER TABLE songs ADD COLUMN album_artist TEXT;
+ALTER TABLE songs ADD COLUMN disc INTEGER;
+ALTER TABLE songs ADD COLUMN disc_total INTEGER;
+ALTER TABLE songs ADD COLUMN track_total INTEGER;
+ALTER TABLE songs ADD COLUMN compilation BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Existing songs have no album artist yet, so their first track artist stands in for it.
+INSERT OR IGNORE INTO albums (id, title, album_artist)
+SELECT lower(hex(randomblob(16))), album, COALESCE(json_extract(artists, '$[0]'), 'Unknown Artist')
+FROM songs
+GROUP BY album, COALESCE(json_extract(artists, '$[0]'), 'Unknown Artist');
+
+UPDATE songs SET album_id = (
+    SELECT albums.id FROM albums
+    WHERE albums.title = songs.album
+      AND albums.album_artist = COALESCE(json_extract(songs.artists, '$[0]'), 'Unknown Artist')
+);
+
+UPDATE albums SET
+    year = (SELECT MIN(year) FROM songs WHERE album_id = albums.id),
+    disc_count = (SELECT MAX(disc) FROM songs WHERE album_id = albums.id),
+    track_count = (SELECT COUNT(*) FROM songs WHERE album_id = albums.id),
+    image = (
+        SELECT image FROM songs
+        WHERE album_id = albums.id AND image IS NOT NULL
+        ORDER BY track
+        LIMIT 1
+    );
+
+CREATE INDEX IF NOT EXISTS idx_songs_album_id ON songs(album_id);
+CREATE INDEX IF NOT EXISTS idx_albums_album_artist ON albums(album_artist);
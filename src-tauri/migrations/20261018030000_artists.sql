@@ -0,0 +1,37 @@
+-- Artist registry: canonical names, sort names and aliases
+CREATE TABLE IF NOT EXISTS artists (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    sort_name TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS artist_aliases (
+    alias TEXT PRIMARY KEY COLLATE NOCASE,
+    artist_id TEXT NOT NULL,
+    FOREIGN KEY (artist_id) REFERENCES artists(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_artist_aliases_artist_id ON artist_aliases(artist_id);
+
+-- Register every artist already in the library under its current spelling. Credits with a
+-- default separator between two names ("Beyoncé feat. Jay-Z") are left out, so that
+-- normalize_artists can still split them.
+WITH separators(token) AS (
+    VALUES ('feat.'), ('feat'), ('ft.'), ('featuring'), ('&'), ('x')
+)
+INSERT OR IGNORE INTO artists (id, name, sort_name)
+SELECT
+    lower(hex(randomblob(16))),
+    je.value,
+    CASE WHEN je.value LIKE 'The %' THEN substr(je.value, 5) || ', ' || substr(je.value, 1, 3) ELSE je.value END
+FROM songs, json_each(songs.artists) AS je
+WHERE je.value != ''
+  AND NOT EXISTS (
+      SELECT 1 FROM separators
+      WHERE je.value LIKE '_% ' || token || ' _%'
+         OR je.value LIKE '_% (' || token || ' _%'
+         OR je.value LIKE '_% [' || token || ' _%'
+  )
+GROUP BY je.value;
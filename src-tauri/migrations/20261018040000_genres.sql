@@ -0,0 +1,76 @@
+-- Genre taxonomy: each genre may have a parent (Deep House -> House -> Electronic)
+CREATE TABLE IF NOT EXISTS genres (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    parent_id TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (parent_id) REFERENCES genres(id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS genre_aliases (
+    alias TEXT PRIMARY KEY COLLATE NOCASE,
+    genre_id TEXT NOT NULL,
+    FOREIGN KEY (genre_id) REFERENCES genres(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_genres_parent_id ON genres(parent_id);
+CREATE INDEX IF NOT EXISTS idx_genre_aliases_genre_id ON genre_aliases(genre_id);
+
+-- Starter taxonomy
+CREATE TEMP TABLE genre_seed (name TEXT NOT NULL, parent TEXT);
+INSERT INTO genre_seed (name, parent) VALUES
+    ('Electronic', NULL),
+    ('House', 'Electronic'),
+    ('Deep House', 'House'),
+    ('Tech House', 'House'),
+    ('Progressive House', 'House'),
+    ('Techno', 'Electronic'),
+    ('Trance', 'Electronic'),
+    ('Drum and Bass', 'Electronic'),
+    ('Dubstep', 'Electronic'),
+    ('Ambient', 'Electronic'),
+    ('Downtempo', 'Electronic'),
+    ('Hip Hop', NULL),
+    ('Trap', 'Hip Hop'),
+    ('Rock', NULL),
+    ('Alternative Rock', 'Rock'),
+    ('Indie Rock', 'Rock'),
+    ('Hard Rock', 'Rock'),
+    ('Punk Rock', 'Rock'),
+    ('Metal', NULL),
+    ('Heavy Metal', 'Metal'),
+    ('Pop', NULL),
+    ('Synth-pop', 'Pop'),
+    ('Jazz', NULL),
+    ('Blues', NULL),
+    ('Classical', NULL),
+    ('R&B', NULL),
+    ('Soul', 'R&B'),
+    ('Funk', NULL),
+    ('Reggae', NULL),
+    ('Country', NULL),
+    ('Folk', NULL);
+
+INSERT OR IGNORE INTO genres (id, name)
+SELECT lower(hex(randomblob(16))), name FROM genre_seed;
+
+UPDATE genres SET parent_id = (
+    SELECT parent.id FROM genre_seed
+    JOIN genres AS parent ON parent.name = genre_seed.parent
+    WHERE genre_seed.name = genres.name
+)
+WHERE name IN (SELECT name FROM genre_seed WHERE parent IS NOT NULL);
+
+DROP TABLE genre_seed;
+
+INSERT OR IGNORE INTO genre_aliases (alias, genre_id)
+SELECT alias, genres.id FROM (
+    SELECT 'RnB' AS alias, 'R&B' AS name
+    UNION ALL SELECT 'Rhythm and Blues', 'R&B'
+    UNION ALL SELECT 'DnB', 'Drum and Bass'
+    UNION ALL SELECT 'D&B', 'Drum and Bass'
+    UNION ALL SELECT 'Rap', 'Hip Hop'
+) AS seed
+JOIN genres ON genres.name = seed.name;
+
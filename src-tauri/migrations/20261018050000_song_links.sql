@@ -0,0 +1,44 @@
+-- Indexed copies of the songs' JSON artist, genre and tag arrays, kept in sync by the
+-- database layer. The JSON columns stay the source for reading songs.
+CREATE TABLE IF NOT EXISTS song_artists (
+    song_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    artist TEXT NOT NULL,
+    PRIMARY KEY (song_id, position),
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS song_genres (
+    song_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    genre TEXT NOT NULL,
+    PRIMARY KEY (song_id, position),
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS song_tags (
+    song_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (song_id, position),
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_song_artists_artist ON song_artists(artist, song_id);
+CREATE INDEX IF NOT EXISTS idx_song_genres_genre ON song_genres(genre, song_id);
+CREATE INDEX IF NOT EXISTS idx_song_tags_tag ON song_tags(tag, song_id);
+
+INSERT OR IGNORE INTO song_artists (song_id, position, artist)
+SELECT songs.id, je.key, je.value FROM songs, json_each(songs.artists) AS je
+WHERE je.value != '';
+
+INSERT OR IGNORE INTO song_genres (song_id, position, genre)
+SELECT songs.id, je.key, je.value FROM songs, json_each(songs.genres) AS je
+WHERE je.value != '';
+
+INSERT OR IGNORE INTO song_tags (song_id, position, tag)
+SELECT songs.id, je.key, je.value FROM songs, json_each(songs.tags) AS je
+WHERE je.value != '';
+
+-- Indexed the whole JSON string, which no query could use.
+DROP INDEX IF EXISTS idx_songs_artist;
@@ -0,0 +1,14 @@
+-- Indexed copy of the songs' JSON instruments array, like song_artists/song_genres/song_tags
+CREATE TABLE IF NOT EXISTS song_instruments (
+    song_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    instrument TEXT NOT NULL,
+    PRIMARY KEY (song_id, position),
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_song_instruments_instrument ON song_instruments(instrument, song_id);
+
+INSERT OR IGNORE INTO song_instruments (song_id, position, instrument)
+SELECT songs.id, je.key, je.value FROM songs, json_each(songs.instruments) AS je
+WHERE songs.instruments IS NOT NULL AND je.value != '';
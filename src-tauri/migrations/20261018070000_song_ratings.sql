@@ -0,0 +1,4 @@
+-- Star ratings: 0-5 in half stars, NULL when unrated
+ALTER TABLE songs ADD COLUMN rating REAL;
+
+CREATE INDEX IF NOT EXISTS idx_songs_rating ON songs(rating);
@@ -0,0 +1,31 @@
+-- Lyrics, one set per song. `lines` holds the time-synced lines as JSON when available.
+CREATE TABLE IF NOT EXISTS lyrics (
+    song_id TEXT PRIMARY KEY,
+    text TEXT NOT NULL,
+    lines TEXT,
+    language TEXT,
+    source TEXT NOT NULL, -- 'tag', 'lrc' or 'user'
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
+
+-- Full-text index over the lyrics text, kept in sync by triggers.
+CREATE VIRTUAL TABLE IF NOT EXISTS lyrics_fts USING fts5(
+    text,
+    content = 'lyrics',
+    content_rowid = 'rowid'
+);
+
+CREATE TRIGGER IF NOT EXISTS lyrics_fts_insert AFTER INSERT ON lyrics BEGIN
+    INSERT INTO lyrics_fts (rowid, text) VALUES (new.rowid, new.text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS lyrics_fts_delete AFTER DELETE ON lyrics BEGIN
+    INSERT INTO lyrics_fts (lyrics_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS lyrics_fts_update AFTER UPDATE OF text ON lyrics BEGIN
+    INSERT INTO lyrics_fts (lyrics_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+    INSERT INTO lyrics_fts (rowid, text) VALUES (new.rowid, new.text);
+END;
@@ -0,0 +1,23 @@
+-- Credits, identifiers and sort names read from the file tags.
+ALTER TABLE songs ADD COLUMN composer TEXT;
+ALTER TABLE songs ADD COLUMN conductor TEXT;
+ALTER TABLE songs ADD COLUMN label TEXT;
+ALTER TABLE songs ADD COLUMN isrc TEXT;
+ALTER TABLE songs ADD COLUMN catalog_number TEXT;
+ALTER TABLE songs ADD COLUMN original_year INTEGER;
+ALTER TABLE songs ADD COLUMN grouping TEXT;
+ALTER TABLE songs ADD COLUMN title_sort TEXT;
+ALTER TABLE songs ADD COLUMN artist_sort TEXT;
+ALTER TABLE songs ADD COLUMN album_sort TEXT;
+ALTER TABLE songs ADD COLUMN album_artist_sort TEXT;
+ALTER TABLE songs ADD COLUMN composer_sort TEXT;
+ALTER TABLE songs ADD COLUMN musicbrainz_recording_id TEXT;
+ALTER TABLE songs ADD COLUMN musicbrainz_track_id TEXT;
+ALTER TABLE songs ADD COLUMN musicbrainz_album_id TEXT;
+ALTER TABLE songs ADD COLUMN musicbrainz_artist_id TEXT;
+ALTER TABLE songs ADD COLUMN musicbrainz_album_artist_id TEXT;
+ALTER TABLE songs ADD COLUMN musicbrainz_release_group_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_songs_composer ON songs(composer);
+CREATE INDEX IF NOT EXISTS idx_songs_isrc ON songs(isrc);
+CREATE INDEX IF NOT EXISTS idx_songs_musicbrainz_recording_id ON songs(musicbrainz_recording_id);
@@ -0,0 +1,9 @@
+-- Undo/redo history. Each entry holds the before and after state of everything one edit
+-- changed; undone entries are the redo stack and are dropped by the next new edit.
+CREATE TABLE IF NOT EXISTS edit_journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    operation TEXT NOT NULL,
+    changes TEXT NOT NULL, -- JSON array of JournalChange
+    undone BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,11 @@
+-- The tags each song's file had when it was first imported. `frames` lists every frame or item
+-- as text (JSON); `raw` is the encoded ID3v2 tag of MP3s, so it can be restored exactly.
+CREATE TABLE IF NOT EXISTS original_tags (
+    song_id TEXT PRIMARY KEY,
+    format TEXT NOT NULL,
+    frames TEXT NOT NULL,
+    raw BLOB,
+    metadata TEXT NOT NULL, -- the SongMetadata read from the tags, as JSON
+    captured_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
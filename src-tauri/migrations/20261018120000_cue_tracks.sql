@@ -0,0 +1,6 @@
+-- Virtual tracks from a cue sheet: the section of the song's file they play, in seconds.
+-- track_end is NULL for the last track, which plays to the end of the file.
+ALTER TABLE songs ADD COLUMN track_start REAL;
+ALTER TABLE songs ADD COLUMN track_end REAL;
+
+CREATE INDEX IF NOT EXISTS idx_songs_url ON songs(url);
@@ -0,0 +1,2 @@
+-- The musical key read from the file tags (TKEY, INITIALKEY).
+ALTER TABLE songs ADD COLUMN initial_key TEXT;
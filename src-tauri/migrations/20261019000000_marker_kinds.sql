@@ -0,0 +1,16 @@
+-- Marker kind (cue, loop, section, chapter, note), section label, DJ hot cue slot and order.
+ALTER TABLE markers ADD COLUMN kind TEXT NOT NULL DEFAULT 'cue';
+ALTER TABLE markers ADD COLUMN section TEXT;
+ALTER TABLE markers ADD COLUMN hot_cue INTEGER;
+ALTER TABLE markers ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE markers SET kind = 'loop' WHERE end IS NOT NULL;
+
+-- Existing markers keep their order in time.
+UPDATE markers SET position = (
+    SELECT COUNT(*) FROM markers AS earlier
+    WHERE earlier.song_id = markers.song_id
+      AND (earlier.start < markers.start OR (earlier.start = markers.start AND earlier.id < markers.id))
+);
+
+CREATE INDEX IF NOT EXISTS idx_markers_song_kind ON markers(song_id, kind);
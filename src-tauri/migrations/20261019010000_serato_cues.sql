@@ -0,0 +1,7 @@
+-- Positions (ms) of the Serato cues and loops already imported from each file, so cues removed
+-- as markers aren't imported again while they stay in the file's tags.
+CREATE TABLE IF NOT EXISTS serato_cues (
+    url TEXT NOT NULL,
+    start INTEGER NOT NULL,
+    PRIMARY KEY (url, start)
+);
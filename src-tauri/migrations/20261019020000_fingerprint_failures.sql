@@ -0,0 +1,8 @@
+-- Songs whose audio couldn't be fingerprinted (undecodable, or too short to yield one), so
+-- find_duplicates doesn't decode them again on every run.
+CREATE TABLE IF NOT EXISTS fingerprint_failures (
+    song_id TEXT PRIMARY KEY,
+    error TEXT, -- NULL when the audio was too short
+    attempted_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (song_id) REFERENCES songs(id) ON DELETE CASCADE
+);
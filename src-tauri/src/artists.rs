@@ -0,0 +1,264 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{ArtistMergeSuggestion, ArtistSettings};
+
+/// Default `suggest_merges` threshold (Jaro-Winkler similarity of the folded names).
+pub const DEFAULT_SUGGESTION_THRESHOLD: f32 = 0.92;
+
+/// Turns raw artist credits into canonical artist names.
+///
+/// Credits are split on the configured separators ("feat.", "&", "x", ...) unless the whole
+/// credit, or a run of its parts, is a known artist ("Simon & Garfunkel"). Each resulting name
+/// is then mapped through the registry's aliases to its canonical spelling.
+pub struct ArtistResolver {
+    separators: HashSet<String>,
+    known: HashSet<String>,
+    canonical: HashMap<String, String>,
+}
+
+impl ArtistResolver {
+    /// `registry` holds each artist's canonical name and its aliases.
+    pub fn new(settings: &ArtistSettings, registry: Vec<(String, Vec<String>)>) -> Self {
+        let separators = settings
+            .split_separators
+            .iter()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let mut known: HashSet<String> = settings
+            .keep_together
+            .iter()
+            .map(|s| s.trim().to_lowercase())
+            .collect();
+        let mut canonical = HashMap::new();
+
+        for (name, aliases) in registry {
+            known.insert(name.to_lowercase());
+            canonical.insert(name.to_lowercase(), name.clone());
+            for alias in aliases {
+                known.insert(alias.to_lowercase());
+                canonical.insert(alias.to_lowercase(), name.clone());
+            }
+        }
+
+        Self {
+            separators,
+            known,
+            canonical,
+        }
+    }
+
+    /// Split, alias-map and de-duplicate a song's artist credits, keeping their order.
+    pub fn normalize(&self, credits: &[String]) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for credit in credits {
+            for name in self.split_credit(credit) {
+                let name = self
+                    .canonical
+                    .get(&name.to_lowercase())
+                    .cloned()
+                    .unwrap_or(name);
+                if !out.iter().any(|n| n.to_lowercase() == name.to_lowercase()) {
+                    out.push(name);
+                }
+            }
+        }
+        out
+    }
+
+    fn split_credit(&self, credit: &str) -> Vec<String> {
+        let tokens: Vec<&str> = credit.split_whitespace().collect();
+
+        // Runs of tokens between separators. A separator may carry an opening bracket,
+        // as in "Beyoncé (feat. Jay-Z)".
+        let mut segments: Vec<(usize, usize)> = Vec::new();
+        let mut start = 0;
+        for (i, token) in tokens.iter().enumerate() {
+            // A separator needs a name on both sides, so "Malcolm X" stays whole.
+            let bare = token.trim_start_matches(['(', '[']).to_lowercase();
+            if self.separators.contains(&bare) && i > start && i + 1 < tokens.len() {
+                segments.push((start, i));
+                start = i + 1;
+            }
+        }
+        if start < tokens.len() {
+            segments.push((start, tokens.len()));
+        }
+
+        // Greedily keep the longest run of segments that is a known artist as one name.
+        let mut names = Vec::new();
+        let mut i = 0;
+        while i < segments.len() {
+            let mut end = i;
+            for j in (i + 1..segments.len()).rev() {
+                let joined = tokens[segments[i].0..segments[j].1].join(" ");
+                if self.known.contains(&clean_name(&joined).to_lowercase()) {
+                    end = j;
+                    break;
+                }
+            }
+
+            let name = clean_name(&tokens[segments[i].0..segments[end].1].join(" "));
+            if !name.is_empty() {
+                names.push(name);
+            }
+            i = end + 1;
+        }
+        names
+    }
+}
+
+/// Trim whitespace and brackets left unbalanced by splitting ("Jay-Z)" -> "Jay-Z").
+fn clean_name(name: &str) -> String {
+    let mut name = name.trim().to_string();
+    loop {
+        let opens = name.matches(['(', '[']).count();
+        let closes = name.matches([')', ']']).count();
+        if closes > opens && (name.ends_with(')') || name.ends_with(']')) {
+            name.pop();
+        } else if opens > closes && (name.starts_with('(') || name.starts_with('[')) {
+            name.remove(0);
+        } else {
+            break;
+        }
+        name = name.trim().to_string();
+    }
+    name
+}
+
+/// Default sort name: a leading "The" moves to the end ("The Beatles" -> "Beatles, The").
+pub fn default_sort_name(name: &str) -> String {
+    match name.split_once(' ') {
+        Some((first, rest)) if first.eq_ignore_ascii_case("the") && !rest.is_empty() => {
+            format!("{}, {}", rest, first)
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Lowercased ASCII form with punctuation and a leading "the" dropped, for fuzzy comparison.
+fn fold_name(name: &str) -> String {
+    let ascii = deunicode::deunicode(name).to_lowercase();
+    let words: Vec<&str> = ascii
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    match words.split_first() {
+        Some((&"the", rest)) if !rest.is_empty() => rest.join(" "),
+        _ => words.join(" "),
+    }
+}
+
+/// Pairs of artist names that look like spellings of the same artist.
+///
+/// `artists` holds each name with its song count; the less used name of each pair is suggested
+/// as the merge source. Pairs are ordered by similarity, highest first.
+pub fn suggest_merges(artists: &[(String, i64)], threshold: f32) -> Vec<ArtistMergeSuggestion> {
+    let folded: Vec<String> = artists.iter().map(|(name, _)| fold_name(name)).collect();
+
+    let mut suggestions = Vec::new();
+    for i in 0..artists.len() {
+        for j in i + 1..artists.len() {
+            if folded[i].is_empty() || folded[j].is_empty() {
+                continue;
+            }
+            let similarity = strsim::jaro_winkler(&folded[i], &folded[j]) as f32;
+            if similarity < threshold {
+                continue;
+            }
+
+            let (source, target) = if artists[i].1 > artists[j].1 { (j, i) } else { (i, j) };
+            suggestions.push(ArtistMergeSuggestion {
+                source: artists[source].0.clone(),
+                target: artists[target].0.clone(),
+                similarity,
+            });
+        }
+    }
+
+    suggestions.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(registry: Vec<(&str, Vec<&str>)>) -> ArtistResolver {
+        ArtistResolver::new(
+            &ArtistSettings::default(),
+            registry
+                .into_iter()
+                .map(|(name, aliases)| {
+                    (name.to_string(), aliases.into_iter().map(String::from).collect())
+                })
+                .collect(),
+        )
+    }
+
+    fn names(credits: &[&str]) -> Vec<String> {
+        credits.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_splits_featured_artists() {
+        let r = resolver(vec![]);
+        assert_eq!(
+            r.normalize(&names(&["Beyoncé feat. Jay-Z"])),
+            names(&["Beyoncé", "Jay-Z"])
+        );
+        assert_eq!(
+            r.normalize(&names(&["Beyoncé (feat. Jay-Z)"])),
+            names(&["Beyoncé", "Jay-Z"])
+        );
+        assert_eq!(r.normalize(&names(&["Skrillex x Diplo"])), names(&["Skrillex", "Diplo"]));
+        assert_eq!(r.normalize(&names(&["Xzibit"])), names(&["Xzibit"]));
+        assert_eq!(r.normalize(&names(&["Malcolm X"])), names(&["Malcolm X"]));
+    }
+
+    #[test]
+    fn test_known_artists_are_not_split() {
+        let r = resolver(vec![("Simon & Garfunkel", vec![])]);
+        assert_eq!(
+            r.normalize(&names(&["Simon & Garfunkel feat. Someone"])),
+            names(&["Simon & Garfunkel", "Someone"])
+        );
+    }
+
+    #[test]
+    fn test_aliases_map_to_canonical_name_and_dedupe() {
+        let r = resolver(vec![("Beyoncé", vec!["Beyonce"])]);
+        assert_eq!(
+            r.normalize(&names(&["beyonce", "Beyoncé & Jay-Z"])),
+            names(&["Beyoncé", "Jay-Z"])
+        );
+    }
+
+    #[test]
+    fn test_default_sort_name() {
+        assert_eq!(default_sort_name("The Beatles"), "Beatles, The");
+        assert_eq!(default_sort_name("Theory of a Deadman"), "Theory of a Deadman");
+        assert_eq!(default_sort_name("The"), "The");
+    }
+
+    #[test]
+    fn test_suggest_merges() {
+        let artists = vec![
+            ("Beyoncé".to_string(), 10),
+            ("Beyonce".to_string(), 2),
+            ("The Beatles".to_string(), 5),
+            ("Beatles".to_string(), 1),
+            ("Metallica".to_string(), 4),
+        ];
+
+        let suggestions = suggest_merges(&artists, 0.95);
+        assert_eq!(suggestions.len(), 2);
+        assert!(suggestions
+            .iter()
+            .any(|s| s.source == "Beyonce" && s.target == "Beyoncé"));
+        assert!(suggestions
+            .iter()
+            .any(|s| s.source == "Beatles" && s.target == "The Beatles"));
+    }
+}
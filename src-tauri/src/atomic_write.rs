@@ -0,0 +1,177 @@
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Rewrite a file without ever leaving it half-written: `write` gets a copy of the file in the
+/// same directory, which is fsynced and renamed over the original once `write` succeeds. On
+/// any error the copy is removed and the original is left untouched.
+pub fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&Path) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let temp_path = temp_path_for(path)?;
+    let result = fs::copy(path, &temp_path)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|_| write(&temp_path))
+        .and_then(|_| {
+            // Read/write handle: Windows won't flush a read-only one.
+            OpenOptions::new().write(true).open(&temp_path)?.sync_all()?;
+            fs::rename(&temp_path, path)?;
+            Ok(())
+        });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Make the rename itself durable. Directories can't be opened as files on Windows.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|d| d.sync_all());
+    }
+    Ok(())
+}
+
+/// A hidden sibling of `path` that keeps its extension, so tag libraries detect the same format.
+fn temp_path_for(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file path"))?;
+    Ok(path.with_file_name(format!(".nagan-{}-{}", Uuid::new_v4(), file_name)))
+}
+
+/// Copy the file's tag block into `backup_dir` before it is rewritten, returning the backup's
+/// path. That's the ID3v2 tag (`.id3`) or the FLAC metadata blocks (`.flac-meta`); formats
+/// whose tags aren't one block at the start of the file (MP4, Ogg) are copied whole.
+pub fn backup_tag_block(path: &Path, backup_dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(backup_dir)?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown");
+
+    let mut file = File::open(path)?;
+    let (kind, len) = match leading_tag_block(&mut file)? {
+        Some(block) => block,
+        None => ("file", file.metadata()?.len()),
+    };
+
+    let backup_path = backup_dir.join(format!(
+        "{}-{}.{}",
+        Utc::now().format("%Y%m%dT%H%M%S%3f"),
+        file_name,
+        kind
+    ));
+    file.seek(SeekFrom::Start(0))?;
+    let mut backup = File::create(&backup_path)?;
+    io::copy(&mut file.take(len), &mut backup)?;
+    backup.sync_all()?;
+    Ok(backup_path)
+}
+
+/// The kind and length of the tag block at the start of a file, if it has one.
+fn leading_tag_block(file: &mut File) -> io::Result<Option<(&'static str, u64)>> {
+    let mut header = [0u8; 10];
+    if file.read(&mut header)? < header.len() {
+        return Ok(None);
+    }
+
+    if &header[..3] == b"ID3" {
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |size, byte| (size << 7) | (*byte & 0x7f) as u64);
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        return Ok(Some(("id3", 10 + size + footer)));
+    }
+
+    if &header[..4] == b"fLaC" {
+        // Metadata blocks follow the marker; the last one has the high bit of its type set.
+        let mut pos = 4u64;
+        loop {
+            let mut block_header = [0u8; 4];
+            file.seek(SeekFrom::Start(pos))?;
+            file.read_exact(&mut block_header)?;
+            let len = u32::from_be_bytes([0, block_header[1], block_header[2], block_header[3]]);
+            pos += 4 + len as u64;
+            if block_header[0] & 0x80 != 0 {
+                return Ok(Some(("flac-meta", pos)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nagan-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_replace_file_keeps_original_on_failure() {
+        let dir = temp_dir();
+        let path = dir.join("song.mp3");
+        fs::write(&path, b"original audio").unwrap();
+
+        let result = replace_file(&path, |temp| {
+            fs::write(temp, b"half")?;
+            Err("tag write failed".into())
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original audio");
+
+        replace_file(&path, |temp| {
+            assert_eq!(temp.extension().and_then(|e| e.to_str()), Some("mp3"));
+            fs::write(temp, b"tagged audio")?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"tagged audio");
+
+        // No temp copies are left behind either way.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_backup_tag_block() {
+        let dir = temp_dir();
+
+        // A 20-byte ID3v2 tag (syncsafe size 10) followed by audio
+        let mut mp3 = b"ID3\x04\x00\x00\x00\x00\x00\x0a".to_vec();
+        mp3.extend_from_slice(&[1; 10]);
+        mp3.extend_from_slice(b"audio frames");
+        let mp3_path = dir.join("song.mp3");
+        fs::write(&mp3_path, &mp3).unwrap();
+
+        let backup = backup_tag_block(&mp3_path, &dir.join("backups")).unwrap();
+        assert!(backup.to_str().unwrap().ends_with("-song.mp3.id3"));
+        assert_eq!(fs::read(&backup).unwrap(), &mp3[..20]);
+
+        // fLaC, a STREAMINFO block and a last (0x80) 2-byte block, then audio
+        let mut flac = b"fLaC\x00\x00\x00\x03abc\x84\x00\x00\x02xy".to_vec();
+        flac.extend_from_slice(b"audio frames");
+        let flac_path = dir.join("song.flac");
+        fs::write(&flac_path, &flac).unwrap();
+        let backup = backup_tag_block(&flac_path, &dir.join("backups")).unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), &flac[..17]);
+
+        let ogg_path = dir.join("song.ogg");
+        fs::write(&ogg_path, b"OggS whole file").unwrap();
+        let backup = backup_tag_block(&ogg_path, &dir.join("backups")).unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), b"OggS whole file");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
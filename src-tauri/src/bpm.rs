@@ -1,106 +1,19 @@
-use std::fs::File;
-use std::path::Path;
-
-use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::DecoderOptions;
-use symphonia::core::errors::Error as SymphoniaError;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
+use crate::audio;
+use crate::models::TrackRange;
 
 /// Estimate BPM from the audio signal (not from tags/metadata).
 ///
-/// Returns `Ok(None)` when the file is too short or tempo can't be determined reliably.
-pub fn estimate_bpm_from_file(file_path: &str) -> Result<Option<f32>, String> {
-    let path = Path::new(file_path);
-    let file = File::open(path).map_err(|e| format!("Failed to open file '{}': {}", file_path, e))?;
-
-    let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-    let mut hint = Hint::new();
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        hint.with_extension(ext);
-    }
-
-    let probed = symphonia::default::get_probe()
-        .format(
-            &hint,
-            mss,
-            &FormatOptions::default(),
-            &MetadataOptions::default(),
-        )
-        .map_err(|e| format!("Failed to probe format for '{}': {}", file_path, e))?;
-
-    let mut format = probed.format;
-
-    let track = format
-        .default_track()
-        .ok_or_else(|| format!("No default audio track found in '{}'", file_path))?
-        .clone();
-
-    let sample_rate = track
-        .codec_params
-        .sample_rate
-        .ok_or_else(|| "Missing sample rate in codec params".to_string())? as usize;
-
-    let mut decoder = symphonia::default::get_codecs()
-        .make(&track.codec_params, &DecoderOptions::default())
-        .map_err(|e| format!("Failed to create decoder: {}", e))?;
-
+/// Only `range` is analysed for cue sheet tracks. Returns `Ok(None)` when the audio is too short
+/// or tempo can't be determined reliably.
+pub fn estimate_bpm_from_file(
+    file_path: &str,
+    range: Option<&TrackRange>,
+) -> Result<Option<f32>, String> {
     // Read up to N seconds to keep this reasonably fast.
     const MAX_SECONDS: usize = 90;
-    let max_samples = sample_rate.saturating_mul(MAX_SECONDS);
-
-    let mut mono_samples: Vec<f32> = Vec::with_capacity(sample_rate.saturating_mul(30));
-
-    loop {
-        let packet = match format.next_packet() {
-            Ok(packet) => packet,
-            Err(SymphoniaError::IoError(_)) => break,
-            Err(SymphoniaError::ResetRequired) => {
-                return Err("Decoder reset required; unsupported stream".to_string())
-            }
-            Err(e) => return Err(format!("Failed to read packet: {}", e)),
-        };
-
-        if packet.track_id() != track.id {
-            continue;
-        }
-
-        let decoded = match decoder.decode(&packet) {
-            Ok(decoded) => decoded,
-            Err(SymphoniaError::IoError(_)) => break,
-            Err(SymphoniaError::DecodeError(_)) => continue,
-            Err(e) => return Err(format!("Decode error: {}", e)),
-        };
-
-        let spec = *decoded.spec();
-        let frames = decoded.frames();
-        let channels = spec.channels.count().max(1);
-
-        // Convert to f32 via SampleBuffer.
-        let mut sample_buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
-        sample_buf.copy_interleaved_ref(decoded);
-        let data = sample_buf.samples();
-
-        for frame in 0..frames {
-            let mut sum = 0.0f32;
-            for ch in 0..channels {
-                sum += data[frame * channels + ch];
-            }
-            mono_samples.push(sum / channels as f32);
-            if mono_samples.len() >= max_samples {
-                break;
-            }
-        }
-
-        if mono_samples.len() >= max_samples {
-            break;
-        }
-    }
 
-    estimate_bpm_from_samples(&mono_samples, sample_rate)
+    let audio = audio::decode_mono_samples(file_path, range, MAX_SECONDS)?;
+    estimate_bpm_from_samples(&audio.samples, audio.sample_rate)
 }
 
 fn estimate_bpm_from_samples(samples: &[f32], sample_rate: usize) -> Result<Option<f32>, String> {
@@ -0,0 +1,313 @@
+use crate::dj_library::DjCue;
+
+/// Atoms on the way to the chunk offset tables of an MP4 file.
+const TRACK_CONTAINERS: [&[u8; 4]; 4] = [b"trak", b"mdia", b"minf", b"stbl"];
+/// Nero chapter times are in units of 100ns.
+const CHPL_UNITS: f64 = 10_000_000.0;
+
+/// Chapters covering the given cue points: each runs to its own end, else to the next one's
+/// start, else to `duration`. Untitled ones are named by number.
+pub fn sections(cues: &[DjCue], duration: f64) -> Vec<DjCue> {
+    let mut sorted = cues.to_vec();
+    sorted.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let starts: Vec<f64> = sorted.iter().map(|c| c.start).skip(1).collect();
+    sorted
+        .into_iter()
+        .enumerate()
+        .map(|(i, cue)| DjCue {
+            end: Some(
+                cue.end
+                    .or(starts.get(i).copied())
+                    .unwrap_or(duration)
+                    .max(cue.start),
+            ),
+            name: cue.name.or_else(|| Some(format!("Chapter {}", i + 1))),
+            ..cue
+        })
+        .collect()
+}
+
+/// An atom of an MP4 file: its type, where it starts, where its content starts and where it ends.
+#[derive(Debug, Clone, Copy)]
+struct Atom {
+    kind: [u8; 4],
+    start: usize,
+    body: usize,
+    end: usize,
+}
+
+/// The atoms in `data[from..to]`.
+fn atoms(data: &[u8], from: usize, to: usize) -> Result<Vec<Atom>, String> {
+    let mut atoms = Vec::new();
+    let mut pos = from;
+    while pos + 8 <= to {
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+        let (body, end) = match size {
+            0 => (pos + 8, Some(to)),
+            1 => {
+                let large = data
+                    .get(pos + 8..pos + 16)
+                    .ok_or("truncated MP4 atom")?;
+                let large = u64::from_be_bytes(large.try_into().unwrap());
+                (pos + 16, usize::try_from(large).ok().and_then(|size| pos.checked_add(size)))
+            }
+            size => (pos + 8, pos.checked_add(size)),
+        };
+        let Some(end) = end.filter(|end| *end >= body && *end <= to) else {
+            return Err(format!(
+                "malformed MP4 atom '{}'",
+                String::from_utf8_lossy(&kind)
+            ));
+        };
+        atoms.push(Atom {
+            kind,
+            start: pos,
+            body,
+            end,
+        });
+        pos = end;
+    }
+    Ok(atoms)
+}
+
+fn find(atoms: &[Atom], kind: &[u8; 4]) -> Option<Atom> {
+    atoms.iter().find(|a| &a.kind == kind).copied()
+}
+
+/// The Nero chapters (`moov/udta/chpl`) of an MP4 file. They have no end, so each runs to the
+/// next one; the last has none.
+pub fn read_mp4(data: &[u8]) -> Result<Vec<DjCue>, String> {
+    let Some(moov) = find(&atoms(data, 0, data.len())?, b"moov") else {
+        return Err("not an MP4 file".to_string());
+    };
+    let Some(udta) = find(&atoms(data, moov.body, moov.end)?, b"udta") else {
+        return Ok(Vec::new());
+    };
+    let Some(chpl) = find(&atoms(data, udta.body, udta.end)?, b"chpl") else {
+        return Ok(Vec::new());
+    };
+
+    // version, flags, (version 1: reserved u32), count, then start (u64) + Pascal string each
+    let body = &data[chpl.body..chpl.end];
+    let mut pos = if body.first() == Some(&1) { 8 } else { 4 };
+    let count = *body.get(pos).ok_or("truncated MP4 chapter list")? as usize;
+    pos += 1;
+
+    let mut chapters: Vec<DjCue> = Vec::with_capacity(count);
+    for _ in 0..count {
+        let start = body
+            .get(pos..pos + 8)
+            .map(|b| u64::from_be_bytes(b.try_into().unwrap()))
+            .ok_or("truncated MP4 chapter list")?;
+        let length = *body.get(pos + 8).ok_or("truncated MP4 chapter list")? as usize;
+        let title = body
+            .get(pos + 9..pos + 9 + length)
+            .ok_or("truncated MP4 chapter list")?;
+        pos += 9 + length;
+
+        let start = start as f64 / CHPL_UNITS;
+        if let Some(previous) = chapters.last_mut() {
+            previous.end = Some(start);
+        }
+        chapters.push(DjCue {
+            start,
+            end: None,
+            name: Some(String::from_utf8_lossy(title).trim().to_string()).filter(|t| !t.is_empty()),
+            color: None,
+            hot_cue: None,
+        });
+    }
+    Ok(chapters)
+}
+
+/// `data` with its Nero chapter list replaced by `chapters`; none removes it. Chunk offsets are
+/// moved along when the grown or shrunk `moov` atom comes before the media data.
+pub fn write_mp4(data: &[u8], chapters: &[DjCue]) -> Result<Vec<u8>, String> {
+    let top = atoms(data, 0, data.len())?;
+    let moov = find(&top, b"moov").ok_or("not an MP4 file")?;
+
+    let chpl = (!chapters.is_empty()).then(|| chpl_atom(chapters));
+    let mut moov_body = Vec::new();
+    let mut has_udta = false;
+    for child in atoms(data, moov.body, moov.end)? {
+        if &child.kind != b"udta" {
+            moov_body.extend_from_slice(&data[child.start..child.end]);
+            continue;
+        }
+        has_udta = true;
+        let mut udta_body = Vec::new();
+        for item in atoms(data, child.body, child.end)? {
+            if &item.kind != b"chpl" {
+                udta_body.extend_from_slice(&data[item.start..item.end]);
+            }
+        }
+        udta_body.extend(chpl.iter().flatten());
+        moov_body.extend(atom(b"udta", &udta_body)?);
+    }
+    if let (false, Some(chpl)) = (has_udta, &chpl) {
+        moov_body.extend(atom(b"udta", chpl)?);
+    }
+    let mut new_moov = atom(b"moov", &moov_body)?;
+
+    let delta = new_moov.len() as i64 - (moov.end - moov.start) as i64;
+    if delta != 0 {
+        patch_chunk_offsets(&mut new_moov, moov.end as u64, delta)?;
+    }
+
+    let mut out = Vec::with_capacity(data.len() + new_moov.len());
+    out.extend_from_slice(&data[..moov.start]);
+    out.extend_from_slice(&new_moov);
+    out.extend_from_slice(&data[moov.end..]);
+    Ok(out)
+}
+
+fn chpl_atom(chapters: &[DjCue]) -> Vec<u8> {
+    let mut body = vec![1, 0, 0, 0, 0, 0, 0, 0];
+    let chapters = &chapters[..chapters.len().min(255)];
+    body.push(chapters.len() as u8);
+    for chapter in chapters {
+        body.extend_from_slice(&((chapter.start.max(0.0) * CHPL_UNITS).round() as u64).to_be_bytes());
+        let title = chapter.name.as_deref().unwrap_or("");
+        let mut length = title.len().min(255);
+        while !title.is_char_boundary(length) {
+            length -= 1;
+        }
+        body.push(length as u8);
+        body.extend_from_slice(&title.as_bytes()[..length]);
+    }
+    let mut chpl = (body.len() as u32 + 8).to_be_bytes().to_vec();
+    chpl.extend_from_slice(b"chpl");
+    chpl.extend(body);
+    chpl
+}
+
+fn atom(kind: &[u8; 4], body: &[u8]) -> Result<Vec<u8>, String> {
+    let size = u32::try_from(body.len() + 8).map_err(|_| "MP4 atom too large")?;
+    let mut out = size.to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    Ok(out)
+}
+
+/// Shift the `stco`/`co64` chunk offsets of every track in `moov` that point at or past `after`.
+fn patch_chunk_offsets(moov: &mut [u8], after: u64, delta: i64) -> Result<(), String> {
+    let len = moov.len();
+    let root = atoms(moov, 0, len)?;
+    let mut pending: Vec<Atom> = atoms(moov, root[0].body, len)?;
+    while let Some(atom) = pending.pop() {
+        if TRACK_CONTAINERS.contains(&&atom.kind) {
+            pending.extend(atoms(moov, atom.body, atom.end)?);
+            continue;
+        }
+        let width = match &atom.kind {
+            b"stco" => 4,
+            b"co64" => 8,
+            _ => continue,
+        };
+        let count = moov
+            .get(atom.body + 4..atom.body + 8)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()) as usize)
+            .ok_or("truncated chunk offset table")?;
+        for i in 0..count {
+            let pos = atom.body + 8 + i * width;
+            let entry = moov.get_mut(pos..pos + width).ok_or("truncated chunk offset table")?;
+            let offset = match width {
+                4 => u32::from_be_bytes((&*entry).try_into().unwrap()) as u64,
+                _ => u64::from_be_bytes((&*entry).try_into().unwrap()),
+            };
+            if offset < after {
+                continue;
+            }
+            let moved = offset as i64 + delta;
+            match width {
+                4 => entry.copy_from_slice(
+                    &u32::try_from(moved)
+                        .map_err(|_| "chunk offset out of range")?
+                        .to_be_bytes(),
+                ),
+                _ => entry.copy_from_slice(&(moved as u64).to_be_bytes()),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(start: f64, name: &str) -> DjCue {
+        DjCue {
+            start,
+            end: None,
+            name: Some(name.to_string()),
+            color: None,
+            hot_cue: None,
+        }
+    }
+
+    /// ftyp, a moov with one track whose chunk offset points into the mdat after it, and the mdat.
+    fn sample_mp4() -> Vec<u8> {
+        let mdat_start = 16 + 72;
+        let mut stco = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stco.extend_from_slice(&(mdat_start as u32 + 8).to_be_bytes());
+        let stbl = atom(b"stbl", &atom(b"stco", &stco).unwrap()).unwrap();
+        let minf = atom(b"minf", &stbl).unwrap();
+        let mdia = atom(b"mdia", &minf).unwrap();
+        let trak = atom(b"trak", &mdia).unwrap();
+        let mut moov_body = atom(b"mvhd", &[0; 4]).unwrap();
+        moov_body.extend(trak);
+        let mut data = atom(b"ftyp", b"M4A \0\0\0\0").unwrap();
+        data.extend(atom(b"moov", &moov_body).unwrap());
+        assert_eq!(data.len(), mdat_start);
+        data.extend(atom(b"mdat", b"audio").unwrap());
+        data
+    }
+
+    fn chunk_offset(data: &[u8]) -> usize {
+        let stco = data.windows(4).position(|w| w == b"stco").unwrap();
+        u32::from_be_bytes(data[stco + 12..stco + 16].try_into().unwrap()) as usize
+    }
+
+    #[test]
+    fn test_sections_fill_in_ends() {
+        let mut looped = chapter(30.0, "Loop");
+        looped.end = Some(34.0);
+        let cues = vec![chapter(10.0, "B"), looped, chapter(0.0, "A")];
+        let sections = sections(&cues, 60.0);
+        let spans: Vec<(f64, Option<f64>)> = sections.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(spans, [(0.0, Some(10.0)), (10.0, Some(30.0)), (30.0, Some(34.0))]);
+    }
+
+    #[test]
+    fn test_mp4_chapters_round_trip() {
+        let data = sample_mp4();
+        assert!(read_mp4(&data).unwrap().is_empty());
+
+        let written = write_mp4(&data, &[chapter(0.0, "Intro"), chapter(92.5, "Verse")]).unwrap();
+        let chapters = read_mp4(&written).unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!((chapters[0].start, chapters[0].end), (0.0, Some(92.5)));
+        assert_eq!(chapters[1].name.as_deref(), Some("Verse"));
+
+        // The audio moved with the grown moov, and the chunk offset with it.
+        let offset = chunk_offset(&written);
+        assert_eq!(&written[offset..offset + 5], b"audio");
+
+        let cleared = write_mp4(&written, &[]).unwrap();
+        assert!(read_mp4(&cleared).unwrap().is_empty());
+        let offset = chunk_offset(&cleared);
+        assert_eq!(&cleared[offset..offset + 5], b"audio");
+    }
+
+    #[test]
+    fn test_oversized_atoms_are_rejected() {
+        // A 64-bit size that would overflow the offset past it.
+        let mut data = sample_mp4();
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(b"free");
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(read_mp4(&data).unwrap_err(), "malformed MP4 atom 'free'");
+    }
+}
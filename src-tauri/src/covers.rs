@@ -0,0 +1,341 @@
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::ImageFormat;
+use sha2::{Digest, Sha256};
+
+use crate::database::Database;
+use crate::models::SongMetadata;
+
+/// Content-addressed store for cover art.
+///
+/// Each image is saved once under the hex SHA-256 of its bytes (its "cover id"), so every track
+/// of an album that embeds the same picture shares one file. Songs only keep the cover id.
+pub struct CoverCache {
+    dir: PathBuf,
+}
+
+impl CoverCache {
+    pub fn new(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create cover cache '{}': {}", dir.display(), e))?;
+        Ok(Self { dir })
+    }
+
+    /// Store image bytes and return their cover id.
+    pub fn store(&self, data: &[u8]) -> Result<String, String> {
+        let id = format!("{:x}", Sha256::digest(data));
+        let path = self.original_path(&id);
+
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            // Write to a temp file first so a crash never leaves a truncated cover behind.
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, data).map_err(|e| e.to_string())?;
+            std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+        }
+
+        Ok(id)
+    }
+
+    pub fn load(&self, id: &str) -> Option<Vec<u8>> {
+        if !is_cover_id(id) {
+            return None;
+        }
+        std::fs::read(self.original_path(id)).ok()
+    }
+
+    /// Return the cover as a data URL, scaled to fit within `size`x`size` pixels if given.
+    ///
+    /// Thumbnails are generated on first request and cached next to the originals.
+    pub fn get_data_url(&self, id: &str, size: Option<u32>) -> Result<Option<String>, String> {
+        let Some(original) = self.load(id) else {
+            return Ok(None);
+        };
+
+        let Some(size) = size.filter(|s| *s > 0) else {
+            return Ok(Some(to_data_url(&original)));
+        };
+
+        let thumb_path = self
+            .dir
+            .join("thumbs")
+            .join(size.to_string())
+            .join(format!("{}.jpg", id));
+
+        if let Ok(bytes) = std::fs::read(&thumb_path) {
+            return Ok(Some(to_data_url(&bytes)));
+        }
+
+        let img = image::load_from_memory(&original)
+            .map_err(|e| format!("Failed to decode cover {}: {}", id, e))?;
+        let thumb = image::DynamicImage::ImageRgb8(img.thumbnail(size, size).to_rgb8());
+
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        thumb
+            .write_to(&mut bytes, ImageFormat::Jpeg)
+            .map_err(|e| format!("Failed to encode thumbnail for {}: {}", id, e))?;
+        let bytes = bytes.into_inner();
+
+        if let Some(parent) = thumb_path.parent() {
+            if std::fs::create_dir_all(parent).is_ok() {
+                if let Err(e) = std::fs::write(&thumb_path, &bytes) {
+                    log::warn!("Failed to cache thumbnail '{}': {}", thumb_path.display(), e);
+                }
+            }
+        }
+
+        Ok(Some(to_data_url(&bytes)))
+    }
+
+    /// Replace an inline data URL in `metadata.image` with a cover id, storing the image.
+    pub fn intern_image(&self, metadata: &mut SongMetadata) -> Result<(), String> {
+        let Some(image) = metadata.image.as_deref() else {
+            return Ok(());
+        };
+        if !image.starts_with("data:") {
+            return Ok(());
+        }
+
+        metadata.image = Some(self.store_data_url(image)?);
+        Ok(())
+    }
+
+    /// Store the image in a base64 data URL and return its cover id.
+    pub fn store_data_url(&self, data_url: &str) -> Result<String, String> {
+        self.store(&decode_data_url(data_url)?)
+    }
+
+    /// Replace a cover id in `metadata.image` with an inline data URL, as the tag writer expects.
+    pub fn expand_image(&self, metadata: &mut SongMetadata) -> Result<(), String> {
+        let Some(id) = metadata.image.as_deref().filter(|i| is_cover_id(i)) else {
+            return Ok(());
+        };
+
+        let data = self
+            .load(id)
+            .ok_or_else(|| format!("Cover {} is missing from the cache", id))?;
+        metadata.image = Some(to_data_url(&data));
+        Ok(())
+    }
+
+    fn original_path(&self, id: &str) -> PathBuf {
+        self.dir.join(&id[..2]).join(id)
+    }
+}
+
+/// Move covers still stored inline as data URLs in `songs.image` into the cache.
+///
+/// Returns the number of songs converted.
+pub async fn migrate_inline_images(db: &Database, cache: &CoverCache) -> Result<usize, String> {
+    const BATCH_SIZE: i64 = 100;
+    let mut converted = 0;
+
+    loop {
+        let rows = db
+            .get_songs_with_inline_image(BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        if rows.is_empty() {
+            return Ok(converted);
+        }
+
+        for (song_id, data_url) in rows {
+            // Undecodable images are dropped rather than retried forever.
+            let cover_id = match cache.store_data_url(&data_url) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    log::warn!("Dropping unreadable cover of song {}: {}", song_id, e);
+                    None
+                }
+            };
+            db.set_song_image(&song_id, cover_id.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+            converted += 1;
+        }
+    }
+}
+
+const SIDECAR_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif", "bmp"];
+
+/// Find an artwork file next to `file_path` matching the first pattern that matches anything.
+pub fn find_sidecar_cover(file_path: &str, patterns: &[String]) -> Option<PathBuf> {
+    let dir = Path::new(file_path).parent()?;
+
+    let mut images: Vec<(String, PathBuf)> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| SIDECAR_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        })
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_lowercase();
+            Some((name, path))
+        })
+        .collect();
+    images.sort();
+
+    patterns.iter().find_map(|pattern| {
+        let pattern = pattern.to_lowercase();
+        images
+            .iter()
+            .find(|(name, _)| wildcard_match(&pattern, name))
+            .map(|(_, path)| path.clone())
+    })
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and `?` any single one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub fn is_cover_id(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn to_data_url(data: &[u8]) -> String {
+    let mime = image::guess_format(data)
+        .map(|f| f.to_mime_type())
+        .unwrap_or("image/jpeg");
+    format!("data:{};base64,{}", mime, BASE64.encode(data))
+}
+
+fn decode_data_url(data_url: &str) -> Result<Vec<u8>, String> {
+    let (meta, data) = data_url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+        .ok_or_else(|| "Malformed data URL".to_string())?;
+
+    if !meta.split(';').any(|part| part == "base64") {
+        return Err("Only base64 data URLs are supported".to_string());
+    }
+
+    BASE64.decode(data.trim()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache() -> CoverCache {
+        let dir = std::env::temp_dir().join(format!("nagan-covers-{}", uuid::Uuid::new_v4()));
+        CoverCache::new(dir).unwrap()
+    }
+
+    fn png_bytes() -> Vec<u8> {
+        let img = image::DynamicImage::new_rgb8(64, 32);
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_store_is_content_addressed() {
+        let cache = temp_cache();
+        let a = cache.store(&png_bytes()).unwrap();
+        let b = cache.store(&png_bytes()).unwrap();
+
+        assert_eq!(a, b);
+        assert!(is_cover_id(&a));
+        assert_eq!(cache.load(&a).unwrap(), png_bytes());
+
+        std::fs::remove_dir_all(&cache.dir).ok();
+    }
+
+    #[test]
+    fn test_intern_and_expand_image() {
+        let cache = temp_cache();
+        let data_url = format!("data:image/png;base64,{}", BASE64.encode(png_bytes()));
+
+        let mut metadata: SongMetadata = serde_json::from_value(serde_json::json!({
+            "title": "T", "album": "A", "year": null, "track": null, "image": data_url,
+            "duration": 1.0, "artists": [], "instruments": null, "bpm": null, "genres": [],
+            "comment": null, "tags": [], "file_exists": true, "times_played": 0
+        }))
+        .unwrap();
+
+        cache.intern_image(&mut metadata).unwrap();
+        assert!(is_cover_id(metadata.image.as_deref().unwrap()));
+
+        cache.expand_image(&mut metadata).unwrap();
+        assert_eq!(metadata.image.as_deref(), Some(data_url.as_str()));
+
+        std::fs::remove_dir_all(&cache.dir).ok();
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("cover.*", "cover.jpg"));
+        assert!(wildcard_match("albumart*.*", "albumart_large.png"));
+        assert!(wildcard_match("front.???", "front.png"));
+        assert!(!wildcard_match("front.???", "front.jpeg"));
+        assert!(!wildcard_match("cover.*", "backcover.jpg"));
+    }
+
+    #[test]
+    fn test_find_sidecar_cover_respects_pattern_order() {
+        let dir = std::env::temp_dir().join(format!("nagan-sidecar-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["song.mp3", "Folder.JPG", "cover.png", "notes.txt"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        let song = dir.join("song.mp3").to_string_lossy().to_string();
+
+        let patterns = vec!["cover.*".to_string(), "folder.*".to_string()];
+        assert_eq!(find_sidecar_cover(&song, &patterns), Some(dir.join("cover.png")));
+
+        let patterns = vec!["folder.*".to_string(), "cover.*".to_string()];
+        assert_eq!(find_sidecar_cover(&song, &patterns), Some(dir.join("Folder.JPG")));
+
+        let patterns = vec!["notes.*".to_string()];
+        assert_eq!(find_sidecar_cover(&song, &patterns), None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_thumbnail_is_generated_and_cached() {
+        let cache = temp_cache();
+        let id = cache.store(&png_bytes()).unwrap();
+
+        let thumb = cache.get_data_url(&id, Some(16)).unwrap().unwrap();
+        assert!(thumb.starts_with("data:image/jpeg;base64,"));
+
+        let decoded = image::load_from_memory(&decode_data_url(&thumb).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (16, 8));
+        assert!(cache.dir.join("thumbs").join("16").join(format!("{}.jpg", id)).exists());
+
+        assert!(cache.get_data_url("missing", Some(16)).unwrap().is_none());
+
+        std::fs::remove_dir_all(&cache.dir).ok();
+    }
+}
@@ -0,0 +1,411 @@
+use std::path::{Path, PathBuf};
+
+use crate::models::{ExtendedTags, Song, SongMetadata, TrackRange};
+
+/// CD frames per second, the unit of cue sheet times.
+const FRAMES_PER_SECOND: f64 = 75.0;
+
+/// The audio tracks of a cue sheet and its album-level fields.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueTrack {
+    /// The `FILE` the track is in, as written in the sheet.
+    pub file: String,
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub songwriter: Option<String>,
+    pub isrc: Option<String>,
+    /// Where `INDEX 01` puts the track in its file, in seconds.
+    pub start: f64,
+}
+
+/// Parse a cue sheet. Data tracks and tracks without an `INDEX 01` are left out.
+pub fn parse(content: &str) -> CueSheet {
+    let mut sheet = CueSheet::default();
+    let mut file = String::new();
+    let mut track: Option<CueTrack> = None;
+    let mut in_track = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                sheet.tracks.extend(track.take().filter(|t| t.start >= 0.0));
+                in_track = false;
+                file = file_name(rest);
+            }
+            "TRACK" => {
+                sheet.tracks.extend(track.take().filter(|t| t.start >= 0.0));
+                in_track = true;
+                let mut parts = rest.split_whitespace();
+                let number = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                let audio = parts.next().is_some_and(|kind| kind.eq_ignore_ascii_case("AUDIO"));
+                track = audio.then(|| CueTrack {
+                    file: file.clone(),
+                    number,
+                    start: -1.0,
+                    ..Default::default()
+                });
+            }
+            "INDEX" => {
+                let mut parts = rest.split_whitespace();
+                if let (Some("01"), Some(time), Some(track)) = (parts.next(), parts.next(), &mut track) {
+                    if let Some(start) = parse_time(time) {
+                        track.start = start;
+                    }
+                }
+            }
+            "TITLE" | "PERFORMER" | "SONGWRITER" | "ISRC" => {
+                let value = Some(unquote(rest)).filter(|v| !v.is_empty());
+                match (command.to_ascii_uppercase().as_str(), in_track, &mut track) {
+                    ("TITLE", false, _) => sheet.title = value,
+                    ("PERFORMER", false, _) => sheet.performer = value,
+                    ("TITLE", true, Some(track)) => track.title = value,
+                    ("PERFORMER", true, Some(track)) => track.performer = value,
+                    ("SONGWRITER", true, Some(track)) => track.songwriter = value,
+                    ("ISRC", true, Some(track)) => track.isrc = value,
+                    _ => {}
+                }
+            }
+            "REM" if !in_track => {
+                let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                let value = Some(unquote(value)).filter(|v| !v.is_empty());
+                match key.to_ascii_uppercase().as_str() {
+                    "GENRE" => sheet.genre = value,
+                    "DATE" => sheet.date = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    sheet.tracks.extend(track.filter(|t| t.start >= 0.0));
+    sheet
+}
+
+/// Read a cue sheet, which may be UTF-8 (with or without a BOM) or Latin-1.
+pub fn read(path: &Path) -> Result<CueSheet, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read cue sheet '{}': {}", path.display(), e))?;
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    };
+    Ok(parse(content.trim_start_matches('\u{feff}')))
+}
+
+/// The cue sheet next to `file_path` that has tracks in it, reduced to those tracks. A sheet
+/// named after the file is tried first; tracks match on file name, or on its stem when the
+/// sheet was written for another encoding of the rip.
+pub fn find_for(file_path: &str) -> Option<(PathBuf, CueSheet)> {
+    let path = Path::new(file_path);
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let stem = path.file_stem()?.to_str()?.to_lowercase();
+
+    let mut sheets: Vec<PathBuf> = std::fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| e.eq_ignore_ascii_case("cue"))
+        })
+        .collect();
+    let named_otherwise = |p: &PathBuf| {
+        let sheet_stem = p.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+        sheet_stem != stem && sheet_stem != name
+    };
+    sheets.sort_by_key(|p| (named_otherwise(p), p.clone()));
+
+    sheets.into_iter().find_map(|sheet_path| {
+        let mut sheet = read(&sheet_path)
+            .map_err(|e| log::warn!("{}", e))
+            .ok()?;
+        sheet.tracks.retain(|track| {
+            let referenced = Path::new(&track.file);
+            let matches = |part: Option<&std::ffi::OsStr>, expected: &str| {
+                part.and_then(|p| p.to_str())
+                    .is_some_and(|p| p.to_lowercase() == expected)
+            };
+            matches(referenced.file_name(), &name) || matches(referenced.file_stem(), &stem)
+        });
+        (!sheet.tracks.is_empty()).then_some((sheet_path, sheet))
+    })
+}
+
+/// Metadata and file section of each track of `sheet`, whose tracks are all in one file with
+/// the tags `file`. Album-level tags of the file are kept; track-level ones come from the sheet.
+pub fn tracks(sheet: &CueSheet, file: &SongMetadata) -> Vec<(SongMetadata, TrackRange)> {
+    let year = sheet
+        .date
+        .as_deref()
+        .and_then(|date| date.get(..4))
+        .and_then(|year| year.parse().ok());
+
+    sheet
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(index, track)| {
+            let end = sheet.tracks.get(index + 1).map(|next| next.start);
+            let mut metadata = file.clone();
+            metadata.title = track
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("Track {:02}", track.number));
+            if let Some(performer) = track.performer.as_ref().or(sheet.performer.as_ref()) {
+                metadata.artists = vec![performer.clone()];
+            }
+            if let Some(title) = &sheet.title {
+                metadata.album = title.clone();
+            }
+            if sheet.performer.is_some() {
+                metadata.album_artist = sheet.performer.clone();
+            }
+            if let Some(genre) = &sheet.genre {
+                metadata.genres = vec![genre.clone()];
+            }
+            metadata.year = year.or(file.year);
+            metadata.track = i32::try_from(track.number).ok();
+            metadata.track_total = i32::try_from(sheet.tracks.len()).ok();
+            metadata.duration = (end.unwrap_or(file.duration) - track.start).max(0.0);
+            metadata.bpm = None;
+            metadata.extended = ExtendedTags {
+                composer: track.songwriter.clone(),
+                isrc: track.isrc.clone(),
+                title_sort: None,
+                artist_sort: None,
+                composer_sort: None,
+                musicbrainz_recording_id: None,
+                musicbrainz_track_id: None,
+                musicbrainz_artist_id: None,
+                ..file.extended.clone()
+            };
+
+            let range = TrackRange {
+                start: track.start,
+                end,
+            };
+            (metadata, range)
+        })
+        .collect()
+}
+
+/// A cue sheet for the tracks of one file, ordered by where they start.
+pub fn sheet_for(songs: &[Song]) -> CueSheet {
+    let Some(first) = songs.first() else {
+        return CueSheet::default();
+    };
+    let album = &first.metadata;
+
+    CueSheet {
+        title: Some(album.album.clone()).filter(|t| !t.is_empty()),
+        performer: album
+            .album_artist
+            .clone()
+            .or_else(|| album.artists.first().cloned()),
+        genre: album.genres.first().cloned(),
+        date: album.year.map(|year| year.to_string()),
+        tracks: songs
+            .iter()
+            .enumerate()
+            .map(|(index, song)| CueTrack {
+                file: song.filename.clone(),
+                number: song
+                    .metadata
+                    .track
+                    .and_then(|n| u32::try_from(n).ok())
+                    .unwrap_or(index as u32 + 1),
+                title: Some(song.metadata.title.clone()),
+                performer: Some(song.metadata.artists.join(", ")).filter(|p| !p.is_empty()),
+                songwriter: song.metadata.extended.composer.clone(),
+                isrc: song.metadata.extended.isrc.clone(),
+                start: song.range.as_ref().map_or(0.0, |r| r.start),
+            })
+            .collect(),
+    }
+}
+
+/// Write a cue sheet.
+pub fn render(sheet: &CueSheet) -> String {
+    let mut out = String::new();
+    if let Some(genre) = &sheet.genre {
+        out.push_str(&format!("REM GENRE \"{}\"\n", quoted(genre)));
+    }
+    if let Some(date) = &sheet.date {
+        out.push_str(&format!("REM DATE {}\n", date));
+    }
+    if let Some(performer) = &sheet.performer {
+        out.push_str(&format!("PERFORMER \"{}\"\n", quoted(performer)));
+    }
+    if let Some(title) = &sheet.title {
+        out.push_str(&format!("TITLE \"{}\"\n", quoted(title)));
+    }
+
+    let mut file: Option<&str> = None;
+    for track in &sheet.tracks {
+        if file != Some(&track.file) {
+            out.push_str(&format!("FILE \"{}\" {}\n", quoted(&track.file), file_type(&track.file)));
+            file = Some(&track.file);
+        }
+        out.push_str(&format!("  TRACK {:02} AUDIO\n", track.number));
+        if let Some(title) = &track.title {
+            out.push_str(&format!("    TITLE \"{}\"\n", quoted(title)));
+        }
+        if let Some(performer) = &track.performer {
+            out.push_str(&format!("    PERFORMER \"{}\"\n", quoted(performer)));
+        }
+        if let Some(songwriter) = &track.songwriter {
+            out.push_str(&format!("    SONGWRITER \"{}\"\n", quoted(songwriter)));
+        }
+        if let Some(isrc) = &track.isrc {
+            out.push_str(&format!("    ISRC {}\n", isrc));
+        }
+        out.push_str(&format!("    INDEX 01 {}\n", format_time(track.start)));
+    }
+    out
+}
+
+/// `mm:ss:ff` in seconds.
+fn parse_time(time: &str) -> Option<f64> {
+    let mut parts = time.split(':').map(|p| p.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(minutes as f64 * 60.0 + seconds as f64 + frames as f64 / FRAMES_PER_SECOND)
+}
+
+fn format_time(seconds: f64) -> String {
+    let frames = (seconds.max(0.0) * FRAMES_PER_SECOND).round() as u64;
+    let per_minute = FRAMES_PER_SECOND as u64 * 60;
+    format!(
+        "{:02}:{:02}:{:02}",
+        frames / per_minute,
+        frames % per_minute / FRAMES_PER_SECOND as u64,
+        frames % FRAMES_PER_SECOND as u64
+    )
+}
+
+/// The file name of a `FILE` line: quoted, or everything before the file type.
+fn file_name(rest: &str) -> String {
+    if let Some(quoted) = rest.strip_prefix('"') {
+        return quoted.split('"').next().unwrap_or("").to_string();
+    }
+    match rest.rsplit_once(char::is_whitespace) {
+        Some((name, _)) => name.trim().to_string(),
+        None => rest.to_string(),
+    }
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .map(|v| v.strip_suffix('"').unwrap_or(v))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Cue sheets have no escaping, so double quotes in values become single ones.
+fn quoted(value: &str) -> String {
+    value.replace('"', "'")
+}
+
+fn file_type(file: &str) -> &'static str {
+    let extension = Path::new(file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match extension.as_str() {
+        "mp3" => "MP3",
+        "aif" | "aiff" => "AIFF",
+        _ => "WAVE",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "REM GENRE Jazz\r
+REM DATE 1959\r
+PERFORMER \"The Quintet\"\r
+TITLE \"Live Set\"\r
+FILE \"Live Set.wav\" WAVE\r
+  TRACK 01 AUDIO\r
+    TITLE \"Opener\"\r
+    INDEX 01 00:00:00\r
+  TRACK 02 AUDIO\r
+    TITLE \"Ballad\"\r
+    PERFORMER \"The Quintet & Guest\"\r
+    ISRC USABC5900002\r
+    INDEX 00 04:10:00\r
+    INDEX 01 04:12:37\r
+  TRACK 03 MODE1/2352\r
+    INDEX 01 09:00:00\r
+";
+
+    #[test]
+    fn test_parse_and_render() {
+        let sheet = parse(SHEET);
+        assert_eq!(sheet.title.as_deref(), Some("Live Set"));
+        assert_eq!(sheet.genre.as_deref(), Some("Jazz"));
+        assert_eq!(sheet.tracks.len(), 2);
+        assert_eq!(sheet.tracks[0].file, "Live Set.wav");
+        assert_eq!(sheet.tracks[1].performer.as_deref(), Some("The Quintet & Guest"));
+        assert!((sheet.tracks[1].start - (252.0 + 37.0 / 75.0)).abs() < 1e-9);
+
+        assert_eq!(parse(&render(&sheet)), sheet);
+    }
+
+    #[test]
+    fn test_tracks_take_titles_and_ranges_from_the_sheet() {
+        let file = SongMetadata {
+            title: "Live Set".to_string(),
+            album: "Untitled".to_string(),
+            duration: 600.0,
+            artists: vec!["Unknown".to_string()],
+            bpm: Some(120.0),
+            file_exists: true,
+            extended: ExtendedTags {
+                label: Some("Label".to_string()),
+                isrc: Some("WHOLEFILE".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let tracks = tracks(&parse(SHEET), &file);
+        assert_eq!(tracks.len(), 2);
+        let (first, first_range) = &tracks[0];
+        assert_eq!(first.title, "Opener");
+        assert_eq!(first.artists, ["The Quintet"]);
+        assert_eq!(first.album, "Live Set");
+        assert_eq!(first.year, Some(1959));
+        assert_eq!(first.track_total, Some(2));
+        assert_eq!(first.bpm, None);
+        assert_eq!(first.extended.isrc, None);
+        assert_eq!(first.extended.label.as_deref(), Some("Label"));
+        assert_eq!(first_range.start, 0.0);
+        assert!((first.duration - tracks[1].1.start).abs() < 1e-9);
+
+        let (second, second_range) = &tracks[1];
+        assert_eq!(second.artists, ["The Quintet & Guest"]);
+        assert_eq!(second.extended.isrc.as_deref(), Some("USABC5900002"));
+        assert_eq!(second_range.end, None);
+        assert!((second.duration - (600.0 - second_range.start)).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,11 @@
+use crate::artists::{self, ArtistResolver};
+use crate::genres::{self, GenreResolver};
 use crate::models::*;
+use crate::ratings;
 use chrono::Utc;
-use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
 use sqlx::Row;
-use sqlx::SqlitePool;
+use sqlx::{Sqlite, SqlitePool, Transaction};
 use std::collections::{HashMap, VecDeque};
 use std::str::FromStr;
 
@@ -41,11 +44,28 @@ impl Database {
                 "filename" => Some("filename"),
                 "title" => Some("title"),
                 "album" => Some("album"),
+                "album_id" | "albumId" => Some("album_id"),
                 "year" | "years" => Some("year"),
                 "bpm" => Some("bpm"),
                 "available" => Some("available"),
                 "artist" | "artists" => Some("artist"),
                 "genre" | "genres" => Some("genre"),
+                "instrument" | "instruments" => Some("instrument"),
+                "tag" | "tags" => Some("tag"),
+                "rating" => Some("rating"),
+                "min_rating" | "minRating" => Some("min_rating"),
+                "composer" => Some("composer"),
+                "conductor" => Some("conductor"),
+                "label" => Some("label"),
+                "grouping" => Some("grouping"),
+                "initial_key" | "initialKey" | "key" => Some("initial_key"),
+                "isrc" => Some("isrc"),
+                "catalog_number" | "catalogNumber" => Some("catalog_number"),
+                "original_year" | "originalYear" => Some("original_year"),
+                "musicbrainz_recording_id" | "musicbrainzRecordingId" => Some("musicbrainz_recording_id"),
+                "musicbrainz_album_id" | "musicbrainzAlbumId" => Some("musicbrainz_album_id"),
+                "marker" | "markers" => Some("marker"),
+                "no_marker" | "noMarker" => Some("no_marker"),
                 _ => None,
             }
         }
@@ -79,7 +99,10 @@ impl Database {
             };
 
             match key {
-                "id" | "url" | "filename" | "title" | "album" => {
+                "id" | "url" | "filename" | "title" | "album" | "album_id" | "composer" | "conductor"
+                | "label" | "grouping" | "initial_key" | "isrc" | "catalog_number"
+                | "musicbrainz_recording_id"
+                | "musicbrainz_album_id" => {
                     if value.is_null() {
                         where_clauses.push(format!("{key} IS NULL"));
                     } else if let Some(s) = value.as_str() {
@@ -102,6 +125,14 @@ impl Database {
                         binds.push(BindValue::Int(n));
                     }
                 }
+                "original_year" => {
+                    if value.is_null() {
+                        where_clauses.push("original_year IS NULL".to_string());
+                    } else if let Some(n) = value.as_i64() {
+                        where_clauses.push("original_year = ?".to_string());
+                        binds.push(BindValue::Int(n));
+                    }
+                }
                 "available" => {
                     if let Some(b) = value.as_bool() {
                         where_clauses.push("available = ?".to_string());
@@ -111,21 +142,69 @@ impl Database {
                 "artist" => {
                     if let Some(s) = value.as_str() {
                         where_clauses.push(
-                            "EXISTS (SELECT 1 FROM json_each(songs.artists) WHERE value = ?)"
+                            "songs.id IN (SELECT song_id FROM song_artists WHERE artist = ?)"
                                 .to_string(),
                         );
                         binds.push(BindValue::Text(s.to_string()));
                     }
                 }
                 "genre" => {
+                    if let Some(s) = value.as_str() {
+                        where_clauses.push(GENRE_OR_DESCENDANT_CLAUSE.to_string());
+                        binds.push(BindValue::Text(s.to_string()));
+                        binds.push(BindValue::Text(s.to_string()));
+                    }
+                }
+                "instrument" => {
                     if let Some(s) = value.as_str() {
                         where_clauses.push(
-                            "EXISTS (SELECT 1 FROM json_each(songs.genres) WHERE value = ?)"
+                            "songs.id IN (SELECT song_id FROM song_instruments WHERE instrument = ?)"
                                 .to_string(),
                         );
                         binds.push(BindValue::Text(s.to_string()));
                     }
                 }
+                "tag" => {
+                    if let Some(s) = value.as_str() {
+                        where_clauses
+                            .push("songs.id IN (SELECT song_id FROM song_tags WHERE tag = ?)".to_string());
+                        binds.push(BindValue::Text(s.to_string()));
+                    }
+                }
+                "rating" => {
+                    if value.is_null() || value.as_str() == Some("Unrated") {
+                        where_clauses.push("rating IS NULL".to_string());
+                    } else if let Some(n) = value.as_f64() {
+                        where_clauses.push("rating = ?".to_string());
+                        binds.push(BindValue::Float(n));
+                    }
+                }
+                "min_rating" => {
+                    if let Some(n) = value.as_f64() {
+                        where_clauses.push("rating >= ?".to_string());
+                        binds.push(BindValue::Float(n));
+                    }
+                }
+                "marker" => {
+                    if let Some(b) = value.as_bool() {
+                        let negate = if b { "" } else { "NOT " };
+                        where_clauses
+                            .push(format!("songs.id {negate}IN (SELECT song_id FROM markers)"));
+                    } else if let Some(kind) = value.as_str().and_then(MarkerKind::from_name) {
+                        where_clauses.push(
+                            "songs.id IN (SELECT song_id FROM markers WHERE kind = ?)".to_string(),
+                        );
+                        binds.push(BindValue::Text(kind.as_str().to_string()));
+                    }
+                }
+                "no_marker" => {
+                    if let Some(kind) = value.as_str().and_then(MarkerKind::from_name) {
+                        where_clauses.push(
+                            "songs.id NOT IN (SELECT song_id FROM markers WHERE kind = ?)".to_string(),
+                        );
+                        binds.push(BindValue::Text(kind.as_str().to_string()));
+                    }
+                }
                 "bpm" => {
                     if value.is_null() {
                         where_clauses.push("bpm IS NULL".to_string());
@@ -280,6 +359,9 @@ impl Database {
                 "year" | "years" => Some("year"),
                 "bpm" => Some("bpm"),
                 "genre" | "genres" => Some("genre"),
+                "instrument" | "instruments" => Some("instrument"),
+                "tag" | "tags" => Some("tag"),
+                "rating" | "ratings" => Some("rating"),
                 _ => None,
             }
         }
@@ -318,22 +400,24 @@ impl Database {
 
             match group_name {
                 "album" => {
+                    // Album ids are preferred; a plain title still selects every album of that name.
                     if selected_value.is_null() {
-                        where_clauses.push("album IS NULL".to_string());
+                        where_clauses.push("songs.album IS NULL".to_string());
                     } else if let Some(s) = selected_value.as_str() {
-                        where_clauses.push("album = ?".to_string());
+                        where_clauses.push("(songs.album_id = ? OR songs.album = ?)".to_string());
+                        binds.push(BindValue::Text(s.to_string()));
                         binds.push(BindValue::Text(s.to_string()));
                     }
                 }
                 "year" => {
                     if selected_value.is_null() {
-                        where_clauses.push("year IS NULL".to_string());
+                        where_clauses.push("songs.year IS NULL".to_string());
                     } else if let Some(n) = selected_value.as_i64() {
-                        where_clauses.push("year = ?".to_string());
+                        where_clauses.push("songs.year = ?".to_string());
                         binds.push(BindValue::Int(n));
                     } else if let Some(s) = selected_value.as_str() {
                         if let Ok(n) = s.parse::<i64>() {
-                            where_clauses.push("year = ?".to_string());
+                            where_clauses.push("songs.year = ?".to_string());
                             binds.push(BindValue::Int(n));
                         }
                     }
@@ -341,21 +425,46 @@ impl Database {
                 "artist" => {
                     if let Some(s) = selected_value.as_str() {
                         where_clauses.push(
-                            "EXISTS (SELECT 1 FROM json_each(songs.artists) WHERE value = ?)"
+                            "songs.id IN (SELECT song_id FROM song_artists WHERE artist = ?)"
                                 .to_string(),
                         );
                         binds.push(BindValue::Text(s.to_string()));
                     }
                 }
                 "genre" => {
+                    if let Some(s) = selected_value.as_str() {
+                        where_clauses.push(GENRE_OR_DESCENDANT_CLAUSE.to_string());
+                        binds.push(BindValue::Text(s.to_string()));
+                        binds.push(BindValue::Text(s.to_string()));
+                    }
+                }
+                "instrument" => {
                     if let Some(s) = selected_value.as_str() {
                         where_clauses.push(
-                            "EXISTS (SELECT 1 FROM json_each(songs.genres) WHERE value = ?)"
+                            "songs.id IN (SELECT song_id FROM song_instruments WHERE instrument = ?)"
                                 .to_string(),
                         );
                         binds.push(BindValue::Text(s.to_string()));
                     }
                 }
+                "tag" => {
+                    if let Some(s) = selected_value.as_str() {
+                        where_clauses
+                            .push("songs.id IN (SELECT song_id FROM song_tags WHERE tag = ?)".to_string());
+                        binds.push(BindValue::Text(s.to_string()));
+                    }
+                }
+                "rating" => {
+                    let rating = selected_value
+                        .as_f64()
+                        .or_else(|| selected_value.as_str().and_then(|s| s.parse().ok()));
+                    if let Some(n) = rating {
+                        where_clauses.push("songs.rating = ?".to_string());
+                        binds.push(BindValue::Float(n));
+                    } else if selected_value.is_null() || selected_value.as_str() == Some("Unrated") {
+                        where_clauses.push("songs.rating IS NULL".to_string());
+                    }
+                }
                 "bpm" => {
                     if selected_value.is_null() {
                         where_clauses.push("bpm IS NULL".to_string());
@@ -440,7 +549,14 @@ impl Database {
             for row in rows {
                 let name: String = row.try_get("name")?;
                 let count: i64 = row.try_get("count")?;
-                out.push(SongGroupItemCount { name, count });
+                let id: Option<String> = row.try_get("id").ok().flatten();
+                let detail: Option<String> = row.try_get("detail").ok().flatten();
+                out.push(SongGroupItemCount {
+                    name,
+                    count,
+                    id,
+                    detail,
+                });
             }
             Ok(out)
         }
@@ -466,7 +582,7 @@ impl Database {
             };
             let mut sql = match normalize_group_name(group_name) {
                 Some("album") => {
-                    "SELECT album as name, COUNT(*) as count FROM songs".to_string()
+                    "SELECT albums.title as name, albums.id as id, albums.album_artist as detail, COUNT(*) as count FROM songs JOIN albums ON albums.id = songs.album_id".to_string()
                 }
                 Some("year") => {
                     "SELECT COALESCE(CAST(year AS TEXT), 'Unknown') as name, COUNT(*) as count FROM songs".to_string()
@@ -478,10 +594,32 @@ impl Database {
                     )
                 }
                 Some("artist") => {
-                    "SELECT je.value as name, COUNT(DISTINCT songs.id) as count FROM songs JOIN json_each(songs.artists) as je".to_string()
+                    "SELECT links.artist as name, COUNT(DISTINCT songs.id) as count FROM songs JOIN song_artists as links ON links.song_id = songs.id".to_string()
+                }
+                Some("genre") => match group.level {
+                    // Each genre counts towards its ancestor at `level`; genres outside the
+                    // taxonomy stay as they are.
+                    Some(level) => format!(
+                        r#"WITH RECURSIVE tree(genre_id, genre_name, ancestor, depth) AS (
+                            SELECT id, name, name, 0 FROM genres WHERE parent_id IS NULL
+                            UNION
+                            SELECT genres.id, genres.name, CASE WHEN tree.depth + 1 <= {level} THEN genres.name ELSE tree.ancestor END, tree.depth + 1
+                            FROM genres JOIN tree ON genres.parent_id = tree.genre_id
+                        )
+                        SELECT COALESCE(tree.ancestor, links.genre) as name, COUNT(DISTINCT songs.id) as count
+                        FROM songs JOIN song_genres as links ON links.song_id = songs.id
+                        LEFT JOIN tree ON tree.genre_name = links.genre"#
+                    ),
+                    None => "SELECT links.genre as name, COUNT(DISTINCT songs.id) as count FROM songs JOIN song_genres as links ON links.song_id = songs.id".to_string(),
+                },
+                Some("instrument") => {
+                    "SELECT links.instrument as name, COUNT(DISTINCT songs.id) as count FROM songs JOIN song_instruments as links ON links.song_id = songs.id".to_string()
+                }
+                Some("tag") => {
+                    "SELECT links.tag as name, COUNT(DISTINCT songs.id) as count FROM songs JOIN song_tags as links ON links.song_id = songs.id".to_string()
                 }
-                Some("genre") => {
-                    "SELECT je.value as name, COUNT(DISTINCT songs.id) as count FROM songs JOIN json_each(songs.genres) as je".to_string()
+                Some("rating") => {
+                    "SELECT CASE WHEN rating IS NULL THEN 'Unrated' ELSE printf('%.1f', rating) END as name, COUNT(*) as count FROM songs".to_string()
                 }
                 _ => {
                     // Unknown group name - return empty group items (validation is expected in command layer)
@@ -495,16 +633,19 @@ impl Database {
                 }
             };
 
-            if matches!(normalize_group_name(group_name), Some("artist") | Some("genre")) {
-                where_clauses.push("je.value IS NOT NULL AND je.value != ''".to_string());
-            }
 
             if !where_clauses.is_empty() {
                 sql.push_str(" WHERE ");
                 sql.push_str(&where_clauses.join(" AND "));
             }
 
-            sql.push_str(&format!(" GROUP BY name ORDER BY {}", order_by));
+            // Albums with the same title but different album artists are separate groups.
+            let group_by = if normalize_group_name(group_name) == Some("album") {
+                "albums.id"
+            } else {
+                "name"
+            };
+            sql.push_str(&format!(" GROUP BY {} ORDER BY {}", group_by, order_by));
 
             let items = run_group_items_query(&self.pool, sql, binds).await?;
             out_groups.push(SongGroupResponseItem {
@@ -519,12 +660,7 @@ impl Database {
     }
 
     pub async fn get_song_by_id(&self, id: &str) -> Result<Option<Song>, sqlx::Error> {
-        let db_song: Option<DbSong> = sqlx::query_as("SELECT * FROM songs WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await?;
-
-        Ok(db_song.map(|s| s.into()))
+        fetch_song(&mut *self.pool.acquire().await?, id).await
     }
 
     pub async fn get_song_by_url(&self, url: &str) -> Result<Option<Song>, sqlx::Error> {
@@ -537,6 +673,12 @@ impl Database {
     }
 
     pub async fn create_song(&self, song: Song) -> Result<Song, sqlx::Error> {
+        let mut song = song;
+        song.metadata.artists = self.artist_resolver().await?.normalize(&song.metadata.artists);
+        self.register_artists(&song.metadata.artists).await?;
+        song.metadata.genres = self.genre_resolver().await?.normalize(&song.metadata.genres);
+        self.register_genres(&song.metadata.genres).await?;
+
         let db_song = DbSong {
             id: song.id.clone(),
             url: song.url.clone(),
@@ -562,15 +704,29 @@ impl Database {
             available: song.available,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            album_id: None,
+            album_artist: song.metadata.album_artist.clone(),
+            disc: song.metadata.disc,
+            disc_total: song.metadata.disc_total,
+            track_total: song.metadata.track_total,
+            compilation: song.metadata.compilation,
+            rating: song.metadata.rating,
+            track_start: song.range.as_ref().map(|r| r.start),
+            track_end: song.range.as_ref().and_then(|r| r.end),
+            extended: song.metadata.extended.clone(),
         };
 
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query(
             r#"
             INSERT INTO songs (
                 id, url, filename, title, album, year, track, image, duration,
                 artists, instruments, bpm, genres, comment, tags, file_exists,
-                times_played, available, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                times_played, available, created_at, updated_at,
+                album_artist, disc, disc_total, track_total, compilation, rating,
+                track_start, track_end
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&db_song.id)
@@ -593,9 +749,31 @@ impl Database {
         .bind(db_song.available)
         .bind(db_song.created_at)
         .bind(db_song.updated_at)
-        .execute(&self.pool)
+        .bind(&db_song.album_artist)
+        .bind(db_song.disc)
+        .bind(db_song.disc_total)
+        .bind(db_song.track_total)
+        .bind(db_song.compilation)
+        .bind(db_song.rating)
+        .bind(db_song.track_start)
+        .bind(db_song.track_end)
+        .execute(&mut *tx)
+        .await?;
+
+        set_song_links(&mut tx, SongLink::Artist, &song.id, &song.metadata.artists).await?;
+        set_song_links(&mut tx, SongLink::Genre, &song.id, &song.metadata.genres).await?;
+        set_song_links(
+            &mut tx,
+            SongLink::Instrument,
+            &song.id,
+            song.metadata.instruments.as_deref().unwrap_or_default(),
+        )
         .await?;
+        set_song_links(&mut tx, SongLink::Tag, &song.id, &song.metadata.tags).await?;
+        set_extended_tags(&mut tx, &song.id, &song.metadata.extended).await?;
+        tx.commit().await?;
 
+        song.metadata.album_id = self.assign_album(&song.id).await?;
         Ok(song)
     }
 
@@ -604,1168 +782,4128 @@ impl Database {
         id: &str,
         updates: UpdateSongPayload,
     ) -> Result<Option<Song>, sqlx::Error> {
-        // Fast existence check so we can return Ok(None) for unknown ids
-        if self.get_song_by_id(id).await?.is_none() {
+        let artists = self.artist_resolver().await?;
+        let genres = self.genre_resolver().await?;
+
+        let tags_written = updates.update_id3.unwrap_or(false);
+
+        let mut tx = self.pool.begin().await?;
+        let before = fetch_song(&mut tx, id).await?;
+        let song = update_song_row(&mut tx, &artists, &genres, id, updates).await?;
+        if let (Some(before), Some(after)) = (before, &song) {
+            let change = JournalChange::Song {
+                id: id.to_string(),
+                before: Box::new(before.metadata),
+                after: Box::new(after.metadata.clone()),
+                tags_written,
+            };
+            record_edit_in(&mut tx, "update_song", &[change]).await?;
+        }
+        tx.commit().await?;
+        Ok(song)
+    }
+
+    /// Overwrite a song's tag-backed metadata with `metadata`, journaled as one edit under
+    /// `operation`. Returns `Ok(None)` if the song doesn't exist.
+    pub async fn set_song_metadata(
+        &self,
+        id: &str,
+        metadata: &SongMetadata,
+        operation: &str,
+        tags_written: bool,
+    ) -> Result<Option<Song>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(before) = fetch_song(&mut tx, id).await? else {
             return Ok(None);
+        };
+
+        restore_song_metadata(&mut tx, id, metadata).await?;
+        let after = fetch_song(&mut tx, id).await?;
+        if let Some(after) = &after {
+            let change = JournalChange::Song {
+                id: id.to_string(),
+                before: Box::new(before.metadata),
+                after: Box::new(after.metadata.clone()),
+                tags_written,
+            };
+            record_edit_in(&mut tx, operation, &[change]).await?;
         }
+        tx.commit().await?;
+        Ok(after)
+    }
 
-        let mut new_title: Option<String> = None;
-        let mut new_album: Option<String> = None;
-        let mut new_artists_json: Option<String> = None;
-        let mut new_year: Option<i32> = None;
-        let mut new_bpm: Option<f32> = None;
-        let mut new_genres_json: Option<String> = None;
-        let mut new_comment: Option<String> = None;
-        let mut new_image: Option<String> = None;
-        let mut clear_image: bool = false;
+    /// Start a batch of song updates that are committed together and journaled as one edit
+    /// under `operation`. See [`SongUpdateBatch`].
+    pub async fn begin_song_updates(&self, operation: &str) -> Result<SongUpdateBatch, sqlx::Error> {
+        Ok(SongUpdateBatch {
+            artists: self.artist_resolver().await?,
+            genres: self.genre_resolver().await?,
+            tx: self.pool.begin().await?,
+            operation: operation.to_string(),
+            changes: Vec::new(),
+            pending: None,
+        })
+    }
 
-        if let Some(obj) = updates.metadata.as_object() {
-            if let Some(title) = obj.get("title").and_then(|v| v.as_str()) {
-                new_title = Some(title.to_string());
-            }
-            if let Some(album) = obj.get("album").and_then(|v| v.as_str()) {
-                new_album = Some(album.to_string());
-            }
+    pub async fn get_songs_with_inline_image(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<(String, String)>, sqlx::Error> {
+        sqlx::query_as("SELECT id, image FROM songs WHERE image LIKE 'data:%' LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
 
-            if let Some(year) = obj.get("year").and_then(|v| v.as_i64()) {
-                if let Ok(year) = i32::try_from(year) {
-                    new_year = Some(year);
-                }
-            }
+    pub async fn set_song_image(&self, id: &str, image: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE songs SET image = ?, updated_at = ? WHERE id = ?")
+            .bind(image)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
 
-            if let Some(bpm) = obj.get("bpm").and_then(|v| v.as_f64()) {
-                if bpm.is_finite() {
-                    new_bpm = Some(bpm as f32);
-                }
-            }
+        Ok(())
+    }
 
-            // Preferred: { artists: ["a", "b"] }
-            if let Some(artists) = obj.get("artists").and_then(|v| v.as_array()) {
-                let artists_vec: Vec<String> = artists
-                    .iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|s| s.to_string())
-                    .collect();
-                new_artists_json = Some(serde_json::to_string(&artists_vec).unwrap_or("[]".into()));
-            } else if let Some(artist) = obj.get("artist").and_then(|v| v.as_str()) {
-                // Fallback: { artist: "single" }
-                new_artists_json =
-                    Some(serde_json::to_string(&vec![artist.to_string()]).unwrap_or("[]".into()));
-            }
+    pub async fn delete_song(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM songs WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
 
-            // Preferred: { genres: ["a", "b"] }, but also accept { genre: "single" }
-            if let Some(genres) = obj.get("genres") {
-                if let Some(arr) = genres.as_array() {
-                    let genres_vec: Vec<String> = arr
-                        .iter()
-                        .filter_map(|v| v.as_str())
-                        .map(|s| s.to_string())
-                        .collect();
-                    new_genres_json =
-                        Some(serde_json::to_string(&genres_vec).unwrap_or("[]".into()));
-                } else if let Some(genre) = genres.as_str() {
-                    new_genres_json =
-                        Some(serde_json::to_string(&vec![genre.to_string()]).unwrap_or("[]".into()));
-                }
-            } else if let Some(genre) = obj.get("genre") {
-                if let Some(arr) = genre.as_array() {
-                    let genres_vec: Vec<String> = arr
-                        .iter()
-                        .filter_map(|v| v.as_str())
-                        .map(|s| s.to_string())
-                        .collect();
-                    new_genres_json =
-                        Some(serde_json::to_string(&genres_vec).unwrap_or("[]".into()));
-                } else if let Some(genre) = genre.as_str() {
-                    new_genres_json =
-                        Some(serde_json::to_string(&vec![genre.to_string()]).unwrap_or("[]".into()));
-                }
-            }
+        self.delete_empty_albums().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Fold `remove_ids` into `keep_id`: playlist entries and markers are repointed to the kept
+    /// song, play counts are summed, tags and genres are unioned, and the removed rows deleted.
+    ///
+    /// Everything happens in one transaction. Returns `Ok(None)` if `keep_id` doesn't exist.
+    pub async fn merge_songs(
+        &self,
+        keep_id: &str,
+        remove_ids: &[String],
+    ) -> Result<Option<Song>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(keep) = fetch_song(&mut tx, keep_id).await? else {
+            return Ok(None);
+        };
 
-            if let Some(comment) = obj.get("comment").and_then(|v| v.as_str()) {
-                new_comment = Some(comment.to_string());
+        let mut removed: Vec<Song> = Vec::new();
+        for id in remove_ids {
+            if id == keep_id || removed.iter().any(|s| &s.id == id) {
+                continue;
             }
+            if let Some(song) = fetch_song(&mut tx, id).await? {
+                removed.push(song);
+            }
+        }
 
-            if let Some(image_value) = obj.get("image") {
-                if image_value.is_null() {
-                    clear_image = true;
-                    new_image = None;
-                } else if let Some(image) = image_value.as_str() {
-                    clear_image = false;
-                    new_image = Some(image.to_string());
+        let mut tags = keep.metadata.tags.clone();
+        let mut genres = keep.metadata.genres.clone();
+        let mut times_played = keep.metadata.times_played;
+        for song in &removed {
+            for tag in &song.metadata.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
                 }
             }
+            for genre in &song.metadata.genres {
+                if !genres.contains(genre) {
+                    genres.push(genre.clone());
+                }
+            }
+            times_played += song.metadata.times_played;
+        }
+
+        for song in &removed {
+            sqlx::query("UPDATE playlist_songs SET song_id = ? WHERE song_id = ?")
+                .bind(keep_id)
+                .bind(&song.id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query("UPDATE markers SET song_id = ?, updated_at = ? WHERE song_id = ?")
+                .bind(keep_id)
+                .bind(Utc::now())
+                .bind(&song.id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query("DELETE FROM songs WHERE id = ?")
+                .bind(&song.id)
+                .execute(&mut *tx)
+                .await?;
         }
 
         sqlx::query(
-            r#"
-            UPDATE songs
-            SET
-              title = COALESCE(?, title),
-              album = COALESCE(?, album),
-              artists = COALESCE(?, artists),
-                            year = COALESCE(?, year),
-                            bpm = COALESCE(?, bpm),
-                            genres = COALESCE(?, genres),
-                            comment = COALESCE(?, comment),
-                            image = CASE WHEN ? = 1 THEN NULL ELSE COALESCE(?, image) END,
-              filename = COALESCE(?, filename),
-              updated_at = ?
-            WHERE id = ?
-            "#,
+            "UPDATE songs SET times_played = ?, tags = ?, genres = ?, updated_at = ? WHERE id = ?",
         )
-        .bind(new_title)
-        .bind(new_album)
-        .bind(new_artists_json)
-                .bind(new_year)
-                .bind(new_bpm)
-                .bind(new_genres_json)
-                .bind(new_comment)
-            .bind(if clear_image { 1 } else { 0 })
-            .bind(new_image)
-        .bind(updates.filename)
+        .bind(times_played)
+        .bind(serde_json::to_string(&tags).unwrap_or("[]".into()))
+        .bind(serde_json::to_string(&genres).unwrap_or("[]".into()))
         .bind(Utc::now())
-        .bind(id)
-        .execute(&self.pool)
+        .bind(keep_id)
+        .execute(&mut *tx)
         .await?;
 
-        self.get_song_by_id(id).await
-    }
+        set_song_links(&mut tx, SongLink::Genre, keep_id, &genres).await?;
+        set_song_links(&mut tx, SongLink::Tag, keep_id, &tags).await?;
 
-    pub async fn delete_song(&self, id: &str) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM songs WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+        for song in &removed {
+            if let Some(album_id) = &song.metadata.album_id {
+                refresh_album_in(&mut tx, album_id).await?;
+            }
+        }
+        delete_empty_albums_in(&mut tx).await?;
 
-        Ok(result.rows_affected() > 0)
+        let song = fetch_song(&mut tx, keep_id).await?;
+        tx.commit().await?;
+        Ok(song)
     }
 
-    // Playlist Management
-
-    pub async fn get_playlists(
+    /// Add and remove user tags across songs in one transaction. Tags compare
+    /// case-insensitively; added tags keep their spelling.
+    ///
+    /// Returns the songs whose tags changed.
+    pub async fn edit_song_tags(
         &self,
-        query: GetPlaylistsQuery,
-    ) -> Result<Vec<Playlist>, sqlx::Error> {
-        let mut sql = "SELECT * FROM playlists".to_string();
+        ids: &[String],
+        add: &[String],
+        remove: &[String],
+    ) -> Result<Vec<Song>, sqlx::Error> {
+        let add = tidy_values(add.iter().map(|t| t.as_str()));
+        let remove = tidy_values(remove.iter().map(|t| t.as_str()));
 
-        // Add filters if provided
-        if let Some(filters) = query.filters {
-            if let Some(filters_obj) = filters.as_object() {
-                let mut where_clauses = Vec::new();
-                for (key, value) in filters_obj {
-                    where_clauses.push(format!("{} = '{}'", key, value));
-                }
-                if !where_clauses.is_empty() {
-                    sql.push_str(&format!(" WHERE {}", where_clauses.join(" AND ")));
+        let mut tx = self.pool.begin().await?;
+        let mut updated_ids: Vec<String> = Vec::new();
+
+        for id in ids {
+            let tags_json: Option<String> = sqlx::query_scalar("SELECT tags FROM songs WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+            let Some(tags_json) = tags_json else {
+                continue;
+            };
+
+            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+            let mut edited: Vec<String> = tags
+                .iter()
+                .filter(|t| !remove.iter().any(|r| r.eq_ignore_ascii_case(t)))
+                .cloned()
+                .collect();
+            for tag in &add {
+                if !edited.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    edited.push(tag.clone());
                 }
             }
-        }
+            if edited == tags {
+                continue;
+            }
 
-        // Add sorting
-        if let Some(sort) = query.sort {
-            sql.push_str(&format!(" ORDER BY {}", sort));
+            sqlx::query("UPDATE songs SET tags = ?, updated_at = ? WHERE id = ?")
+                .bind(serde_json::to_string(&edited).unwrap_or("[]".into()))
+                .bind(Utc::now())
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            set_song_links(&mut tx, SongLink::Tag, id, &edited).await?;
+
+            if !updated_ids.contains(id) {
+                updated_ids.push(id.clone());
+            }
         }
 
-        let db_playlists: Vec<DbPlaylist> = sqlx::query_as(&sql).fetch_all(&self.pool).await?;
+        tx.commit().await?;
 
-        Ok(db_playlists.into_iter().map(|p| p.into()).collect())
+        let mut songs = Vec::with_capacity(updated_ids.len());
+        for id in &updated_ids {
+            if let Some(song) = self.get_song_by_id(id).await? {
+                songs.push(song);
+            }
+        }
+        Ok(songs)
     }
 
-    pub async fn create_playlist(&self, playlist: Playlist) -> Result<Playlist, sqlx::Error> {
-        let db_playlist = DbPlaylist {
-            id: playlist.id.clone(),
-            name: playlist.name.clone(),
-            tags: serde_json::to_string(&playlist.tags).unwrap_or_default(),
-            total_duration: playlist.total_duration,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+    // Albums
 
-        sqlx::query(
-            r#"
-            INSERT INTO playlists (id, name, tags, total_duration, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&db_playlist.id)
-        .bind(&db_playlist.name)
-        .bind(&db_playlist.tags)
-        .bind(db_playlist.total_duration)
-        .bind(db_playlist.created_at)
-        .bind(db_playlist.updated_at)
-        .execute(&self.pool)
-        .await?;
+    /// Attach the song to the album matching its title and album artist, creating the album
+    /// if needed. Returns the album id.
+    pub async fn assign_album(&self, song_id: &str) -> Result<Option<String>, sqlx::Error> {
+        assign_album_in(&mut *self.pool.acquire().await?, song_id).await
+    }
 
-        Ok(playlist)
+    /// Recompute an album's year, disc and track counts, cover and compilation flag from its songs.
+    async fn delete_empty_albums(&self) -> Result<(), sqlx::Error> {
+        delete_empty_albums_in(&mut *self.pool.acquire().await?).await
     }
 
-    pub async fn delete_playlist(&self, id: &str) -> Result<bool, sqlx::Error> {
-        // First, delete all playlist_songs entries for this playlist
-        sqlx::query("DELETE FROM playlist_songs WHERE playlist_id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+    pub async fn get_albums(&self) -> Result<Vec<Album>, sqlx::Error> {
+        let db_albums: Vec<DbAlbum> =
+            sqlx::query_as("SELECT * FROM albums ORDER BY album_artist COLLATE NOCASE, title COLLATE NOCASE")
+                .fetch_all(&self.pool)
+                .await?;
 
-        // Then delete the playlist itself
-        let result = sqlx::query("DELETE FROM playlists WHERE id = ?")
+        Ok(db_albums.into_iter().map(|a| a.into()).collect())
+    }
+
+    pub async fn get_album_by_id(&self, id: &str) -> Result<Option<Album>, sqlx::Error> {
+        let db_album: Option<DbAlbum> = sqlx::query_as("SELECT * FROM albums WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(db_album.map(|a| a.into()))
     }
 
-    pub async fn get_max_playlist_position(&self, playlist_id: &str) -> Result<i32, sqlx::Error> {
-        let result: Option<(i32,)> = sqlx::query_as(
-            "SELECT COALESCE(MAX(position), -1) FROM playlist_songs WHERE playlist_id = ?"
+    // Artists
+
+    /// Build a resolver from the artist settings and the registry's names and aliases.
+    pub async fn artist_resolver(&self) -> Result<ArtistResolver, sqlx::Error> {
+        let settings = self.get_settings().await?;
+
+        let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT artists.id, artists.name, artist_aliases.alias
+            FROM artists LEFT JOIN artist_aliases ON artist_aliases.artist_id = artists.id
+            ORDER BY artists.id
+            "#,
         )
-        .bind(playlist_id)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(result.map(|(pos,)| pos).unwrap_or(-1))
+        let mut registry: Vec<(String, Vec<String>)> = Vec::new();
+        let mut last_id: Option<String> = None;
+        for (id, name, alias) in rows {
+            if last_id.as_deref() != Some(id.as_str()) {
+                registry.push((name, Vec::new()));
+                last_id = Some(id);
+            }
+            if let (Some(alias), Some(entry)) = (alias, registry.last_mut()) {
+                entry.1.push(alias);
+            }
+        }
+
+        Ok(ArtistResolver::new(&settings.artists, registry))
     }
 
-    pub async fn shift_playlist_positions(
+    /// Add any artists not yet in the registry.
+    pub async fn register_artists(&self, names: &[String]) -> Result<(), sqlx::Error> {
+        insert_artists(&mut *self.pool.acquire().await?, names).await
+    }
+
+    pub async fn get_artists(&self) -> Result<Vec<Artist>, sqlx::Error> {
+        let db_artists: Vec<DbArtist> =
+            sqlx::query_as("SELECT * FROM artists ORDER BY sort_name COLLATE NOCASE")
+                .fetch_all(&self.pool)
+                .await?;
+        self.with_aliases_and_counts(db_artists).await
+    }
+
+    pub async fn get_artist_by_id(&self, id: &str) -> Result<Option<Artist>, sqlx::Error> {
+        let db_artists: Vec<DbArtist> = sqlx::query_as("SELECT * FROM artists WHERE id = ?")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(self.with_aliases_and_counts(db_artists).await?.pop())
+    }
+
+    async fn with_aliases_and_counts(
         &self,
-        playlist_id: &str,
-        from_position: i32,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "UPDATE playlist_songs SET position = position + 1 WHERE playlist_id = ? AND position >= ?"
+        db_artists: Vec<DbArtist>,
+    ) -> Result<Vec<Artist>, sqlx::Error> {
+        let aliases: Vec<(String, String)> =
+            sqlx::query_as("SELECT artist_id, alias FROM artist_aliases ORDER BY alias")
+                .fetch_all(&self.pool)
+                .await?;
+        let mut aliases_by_artist: HashMap<String, Vec<String>> = HashMap::new();
+        for (artist_id, alias) in aliases {
+            aliases_by_artist.entry(artist_id).or_default().push(alias);
+        }
+
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT artist, COUNT(DISTINCT song_id) FROM song_artists GROUP BY artist",
         )
-        .bind(playlist_id)
-        .bind(from_position)
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
+        let mut counts_by_name: HashMap<String, i64> = HashMap::new();
+        for (name, count) in counts {
+            *counts_by_name.entry(name.to_lowercase()).or_default() += count;
+        }
 
-        Ok(())
+        Ok(db_artists
+            .into_iter()
+            .map(|a| Artist {
+                aliases: aliases_by_artist.remove(&a.id).unwrap_or_default(),
+                song_count: counts_by_name.get(&a.name.to_lowercase()).copied().unwrap_or(0),
+                id: a.id,
+                name: a.name,
+                sort_name: a.sort_name,
+            })
+            .collect())
     }
 
-    pub async fn add_song_to_playlist(
+    pub async fn update_artist(
         &self,
-        id: &str,
-        playlist_id: &str,
-        song_id: &str,
-        position: i32,
-    ) -> Result<bool, sqlx::Error> {
-        sqlx::query(
-            r#"
-            INSERT INTO playlist_songs (id, playlist_id, song_id, position, added_at)
-            VALUES (?, ?, ?, ?, CURRENT_TIMESTAMP)
-            "#,
-        )
-        .bind(id)
-        .bind(playlist_id)
-        .bind(song_id)
-        .bind(position)
-        .execute(&self.pool)
-        .await?;
+        payload: UpdateArtistPayload,
+    ) -> Result<Option<Artist>, sqlx::Error> {
+        let Some(artist) = self.get_artist_by_id(&payload.id).await? else {
+            return Ok(None);
+        };
 
-        Ok(true)
-    }
+        let mut tx = self.pool.begin().await?;
 
-    pub async fn get_playlist_songs(
-        &self,
-        playlist_id: &str,
-        sort: Option<&str>,
-    ) -> Result<Vec<Song>, sqlx::Error> {
-        let mut sql = r#"
-            SELECT s.* FROM songs s
-            INNER JOIN playlist_songs ps ON s.id = ps.song_id
-            WHERE ps.playlist_id = ?
-        "#.to_string();
+        if let Some(sort_name) = payload.sort_name.filter(|s| !s.trim().is_empty()) {
+            sqlx::query("UPDATE artists SET sort_name = ?, updated_at = ? WHERE id = ?")
+                .bind(sort_name.trim())
+                .bind(Utc::now())
+                .bind(&artist.id)
+                .execute(&mut *tx)
+                .await?;
+        }
 
-        // Add sorting - default to position order
-        if let Some(sort_field) = sort {
-            sql.push_str(&format!(" ORDER BY s.{}", sort_field));
-        } else {
-            sql.push_str(" ORDER BY ps.position");
+        if let Some(aliases) = payload.aliases {
+            sqlx::query("DELETE FROM artist_aliases WHERE artist_id = ?")
+                .bind(&artist.id)
+                .execute(&mut *tx)
+                .await?;
+
+            // An alias can only point at one artist; claiming it here takes it from any other.
+            for alias in aliases.iter().map(|a| a.trim()) {
+                if alias.is_empty() || alias.eq_ignore_ascii_case(&artist.name) {
+                    continue;
+                }
+                sqlx::query(
+                    r#"
+                    INSERT INTO artist_aliases (alias, artist_id) VALUES (?, ?)
+                    ON CONFLICT(alias) DO UPDATE SET artist_id = excluded.artist_id
+                    "#,
+                )
+                .bind(alias)
+                .bind(&artist.id)
+                .execute(&mut *tx)
+                .await?;
+            }
         }
 
-        let db_songs: Vec<DbSong> = sqlx::query_as(&sql)
-            .bind(playlist_id)
-            .fetch_all(&self.pool)
-            .await?;
+        tx.commit().await?;
 
-        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+        self.get_artist_by_id(&artist.id).await
     }
 
-    pub async fn remove_song_from_playlist_by_song_id(
+    /// Fold the `sources` artists into `target`: every song crediting a source is rewritten to
+    /// credit the target instead, and the source names become aliases of the target.
+    ///
+    /// Returns the target artist and the songs that were rewritten.
+    pub async fn merge_artists(
         &self,
-        playlist_id: &str,
-        song_id: &str,
-    ) -> Result<bool, sqlx::Error> {
-        // Get all positions of the song being removed (in descending order to avoid shifting issues)
-        let positions: Vec<(i32,)> = sqlx::query_as(
-            "SELECT position FROM playlist_songs WHERE playlist_id = ? AND song_id = ? ORDER BY position DESC"
-        )
-        .bind(playlist_id)
-        .bind(song_id)
-        .fetch_all(&self.pool)
-        .await?;
+        target: &str,
+        sources: &[String],
+    ) -> Result<(Artist, Vec<Song>), sqlx::Error> {
+        self.register_artists(&[target.to_string()]).await?;
 
-        if positions.is_empty() {
-            return Ok(false);
-        }
+        let mut tx = self.pool.begin().await?;
 
-        // Delete all instances of the song from the playlist
-        sqlx::query(
-            "DELETE FROM playlist_songs WHERE playlist_id = ? AND song_id = ?"
-        )
-        .bind(playlist_id)
-        .bind(song_id)
-        .execute(&self.pool)
-        .await?;
+        let (target_id, target_name): (String, String) =
+            sqlx::query_as("SELECT id, name FROM artists WHERE name = ?")
+                .bind(target)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let mut updated_ids: Vec<String> = Vec::new();
+
+        for source in sources
+            .iter()
+            .filter(|s| s.to_lowercase() != target_name.to_lowercase())
+        {
+            let songs: Vec<(String, String)> = sqlx::query_as(
+                "SELECT id, artists FROM songs WHERE id IN (SELECT song_id FROM song_artists WHERE artist = ? COLLATE NOCASE)",
+            )
+            .bind(source)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for (song_id, artists_json) in songs {
+                let artists: Vec<String> = serde_json::from_str(&artists_json).unwrap_or_default();
+                let mut merged: Vec<String> = Vec::new();
+                for artist in artists {
+                    let artist = if artist.to_lowercase() == source.to_lowercase() {
+                        target_name.clone()
+                    } else {
+                        artist
+                    };
+                    if !merged.contains(&artist) {
+                        merged.push(artist);
+                    }
+                }
+
+                sqlx::query("UPDATE songs SET artists = ?, updated_at = ? WHERE id = ?")
+                    .bind(serde_json::to_string(&merged).unwrap_or("[]".into()))
+                    .bind(Utc::now())
+                    .bind(&song_id)
+                    .execute(&mut *tx)
+                    .await?;
+                set_song_links(&mut tx, SongLink::Artist, &song_id, &merged).await?;
+
+                if !updated_ids.contains(&song_id) {
+                    updated_ids.push(song_id);
+                }
+            }
+
+            let source_id: Option<String> =
+                sqlx::query_scalar("SELECT id FROM artists WHERE name = ?")
+                    .bind(source)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if let Some(source_id) = source_id {
+                sqlx::query("UPDATE artist_aliases SET artist_id = ? WHERE artist_id = ?")
+                    .bind(&target_id)
+                    .bind(&source_id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("DELETE FROM artists WHERE id = ?")
+                    .bind(&source_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
 
-        // Shift positions for each removed song (process from highest to lowest position)
-        for (removed_position,) in positions {
             sqlx::query(
-                "UPDATE playlist_songs SET position = position - 1 WHERE playlist_id = ? AND position > ?"
+                r#"
+                INSERT INTO artist_aliases (alias, artist_id) VALUES (?, ?)
+                ON CONFLICT(alias) DO UPDATE SET artist_id = excluded.artist_id
+                "#,
             )
-            .bind(playlist_id)
-            .bind(removed_position)
-            .execute(&self.pool)
+            .bind(source)
+            .bind(&target_id)
+            .execute(&mut *tx)
             .await?;
         }
 
-        Ok(true)
+        tx.commit().await?;
+
+        let mut songs = Vec::with_capacity(updated_ids.len());
+        for id in &updated_ids {
+            if let Some(song) = self.get_song_by_id(id).await? {
+                songs.push(song);
+            }
+        }
+
+        let artist = self
+            .get_artist_by_id(&target_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        Ok((artist, songs))
     }
 
-    pub async fn remove_song_from_playlist_by_position(
-        &self,
-        playlist_id: &str,
-        position: i32,
-    ) -> Result<bool, sqlx::Error> {
-        // Delete the song at the specified position
-        let result = sqlx::query(
-            "DELETE FROM playlist_songs WHERE playlist_id = ? AND position = ?"
-        )
-        .bind(playlist_id)
-        .bind(position)
-        .execute(&self.pool)
-        .await?;
+    /// Re-run artist splitting and alias mapping over every song, e.g. after the rules changed.
+    ///
+    /// Returns the songs whose artists changed.
+    pub async fn normalize_song_artists(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let resolver = self.artist_resolver().await?;
 
-        if result.rows_affected() == 0 {
-            return Ok(false);
-        }
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, artists FROM songs")
+            .fetch_all(&self.pool)
+            .await?;
 
-        // Shift positions of songs after the removed position
-        sqlx::query(
-            "UPDATE playlist_songs SET position = position - 1 WHERE playlist_id = ? AND position > ?"
-        )
-        .bind(playlist_id)
-        .bind(position)
-        .execute(&self.pool)
-        .await?;
+        let mut changed = Vec::new();
+        for (song_id, artists_json) in rows {
+            let artists: Vec<String> = serde_json::from_str(&artists_json).unwrap_or_default();
+            let normalized = resolver.normalize(&artists);
+            if normalized == artists {
+                continue;
+            }
 
-        Ok(true)
+            self.register_artists(&normalized).await?;
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("UPDATE songs SET artists = ?, updated_at = ? WHERE id = ?")
+                .bind(serde_json::to_string(&normalized).unwrap_or("[]".into()))
+                .bind(Utc::now())
+                .bind(&song_id)
+                .execute(&mut *tx)
+                .await?;
+            set_song_links(&mut tx, SongLink::Artist, &song_id, &normalized).await?;
+            tx.commit().await?;
+
+            if let Some(song) = self.get_song_by_id(&song_id).await? {
+                changed.push(song);
+            }
+        }
+
+        Ok(changed)
     }
 
-    pub async fn reorder_playlist_songs(
-        &self,
-        playlist_id: &str,
-        song_ids: &[String],
-    ) -> Result<bool, sqlx::Error> {
-        // IMPORTANT: playlists can contain the same song multiple times. Updating by song_id would
-        // affect multiple rows and break ordering. We rebuild the playlist_songs rows in the
-        // requested order, preserving each original row's `added_at` (stable for duplicates).
+    // Genres
 
-        let current_rows: Vec<DbPlaylistSong> = sqlx::query_as(
-            "SELECT id, playlist_id, song_id, position, added_at FROM playlist_songs WHERE playlist_id = ? ORDER BY position",
+    pub async fn genre_resolver(&self) -> Result<GenreResolver, sqlx::Error> {
+        let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT genres.id, genres.name, genre_aliases.alias
+            FROM genres LEFT JOIN genre_aliases ON genre_aliases.genre_id = genres.id
+            ORDER BY genres.created_at, genres.id
+            "#,
         )
-        .bind(playlist_id)
         .fetch_all(&self.pool)
         .await?;
 
-        if current_rows.len() != song_ids.len() {
-            return Ok(false);
+        let mut registry: Vec<(String, Vec<String>)> = Vec::new();
+        let mut last_id: Option<String> = None;
+        for (id, name, alias) in rows {
+            if last_id.as_deref() != Some(id.as_str()) {
+                registry.push((name, Vec::new()));
+                last_id = Some(id);
+            }
+            if let (Some(alias), Some(entry)) = (alias, registry.last_mut()) {
+                entry.1.push(alias);
+            }
         }
 
-        let mut rows_by_song: HashMap<String, VecDeque<DbPlaylistSong>> = HashMap::new();
-        for row in current_rows {
-            rows_by_song.entry(row.song_id.clone()).or_default().push_back(row);
-        }
+        Ok(GenreResolver::new(registry))
+    }
 
-        let mut ordered_rows: Vec<DbPlaylistSong> = Vec::with_capacity(song_ids.len());
-        for song_id in song_ids {
-            let Some(queue) = rows_by_song.get_mut(song_id) else {
-                return Ok(false);
-            };
-            let Some(row) = queue.pop_front() else {
-                return Ok(false);
-            };
-            ordered_rows.push(row);
-        }
+    /// Add any genres not yet in the taxonomy, as top-level genres.
+    pub async fn register_genres(&self, names: &[String]) -> Result<(), sqlx::Error> {
+        insert_genres(&mut *self.pool.acquire().await?, names).await
+    }
 
-        let mut tx = self.pool.begin().await?;
+    pub async fn get_genres(&self) -> Result<Vec<Genre>, sqlx::Error> {
+        let db_genres: Vec<DbGenre> =
+            sqlx::query_as("SELECT * FROM genres ORDER BY name COLLATE NOCASE")
+                .fetch_all(&self.pool)
+                .await?;
+        self.genres_with_aliases_and_counts(db_genres).await
+    }
 
-        // Clear all entries for this playlist, then re-insert in the new order.
-        sqlx::query("DELETE FROM playlist_songs WHERE playlist_id = ?")
-            .bind(playlist_id)
-            .execute(&mut *tx)
+    pub async fn get_genre_by_id(&self, id: &str) -> Result<Option<Genre>, sqlx::Error> {
+        let db_genres: Vec<DbGenre> = sqlx::query_as("SELECT * FROM genres WHERE id = ?")
+            .bind(id)
+            .fetch_all(&self.pool)
             .await?;
+        Ok(self.genres_with_aliases_and_counts(db_genres).await?.pop())
+    }
 
-        for (index, row) in ordered_rows.into_iter().enumerate() {
-            sqlx::query(
-                "INSERT INTO playlist_songs (id, playlist_id, song_id, position, added_at) VALUES (?, ?, ?, ?, ?)",
-            )
-            .bind(row.id)
-            .bind(row.playlist_id)
-            .bind(row.song_id)
-            .bind(index as i32)
-            .bind(row.added_at)
-            .execute(&mut *tx)
-            .await?;
+    async fn genres_with_aliases_and_counts(
+        &self,
+        db_genres: Vec<DbGenre>,
+    ) -> Result<Vec<Genre>, sqlx::Error> {
+        let aliases: Vec<(String, String)> =
+            sqlx::query_as("SELECT genre_id, alias FROM genre_aliases ORDER BY alias")
+                .fetch_all(&self.pool)
+                .await?;
+        let mut aliases_by_genre: HashMap<String, Vec<String>> = HashMap::new();
+        for (genre_id, alias) in aliases {
+            aliases_by_genre.entry(genre_id).or_default().push(alias);
         }
 
-        tx.commit().await?;
-        Ok(true)
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT genre, COUNT(DISTINCT song_id) FROM song_genres GROUP BY genre",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut counts_by_name: HashMap<String, i64> = HashMap::new();
+        for (name, count) in counts {
+            *counts_by_name.entry(name.to_lowercase()).or_default() += count;
+        }
+
+        Ok(db_genres
+            .into_iter()
+            .map(|g| Genre {
+                aliases: aliases_by_genre.remove(&g.id).unwrap_or_default(),
+                song_count: counts_by_name.get(&g.name.to_lowercase()).copied().unwrap_or(0),
+                id: g.id,
+                name: g.name,
+                parent_id: g.parent_id,
+            })
+            .collect())
     }
 
-    pub async fn shuffle_playlist_songs(
+    pub async fn create_genre(
         &self,
-        playlist_id: &str,
-    ) -> Result<Vec<String>, sqlx::Error> {
-        use rand::seq::SliceRandom;
+        name: &str,
+        parent_id: Option<&str>,
+    ) -> Result<Genre, sqlx::Error> {
+        self.register_genres(&[name.to_string()]).await?;
+        let id: String = sqlx::query_scalar("SELECT id FROM genres WHERE name = ?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?;
 
-        // Get all song IDs in the playlist ordered by position
-        let song_ids: Vec<(String,)> = sqlx::query_as(
-            "SELECT song_id FROM playlist_songs WHERE playlist_id = ? ORDER BY position"
+        let genre = match parent_id {
+            Some(_) => self.set_genre_parent(&id, parent_id).await?,
+            None => self.get_genre_by_id(&id).await?,
+        };
+        genre.ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Move a genre under `parent_id`, or to the top level for `None`.
+    pub async fn set_genre_parent(
+        &self,
+        id: &str,
+        parent_id: Option<&str>,
+    ) -> Result<Option<Genre>, sqlx::Error> {
+        sqlx::query("UPDATE genres SET parent_id = ?, updated_at = ? WHERE id = ?")
+            .bind(parent_id)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.get_genre_by_id(id).await
+    }
+
+    /// The genre's id followed by the ids of its ancestors; empty if the genre doesn't exist.
+    pub async fn get_genre_lineage(&self, id: &str) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            WITH RECURSIVE up(id, parent_id, depth) AS (
+                SELECT id, parent_id, 0 FROM genres WHERE id = ?
+                UNION
+                SELECT genres.id, genres.parent_id, up.depth + 1
+                FROM genres JOIN up ON genres.id = up.parent_id
+                WHERE up.depth < 100
+            )
+            SELECT id FROM up ORDER BY depth
+            "#,
         )
-        .bind(playlist_id)
+        .bind(id)
         .fetch_all(&self.pool)
-        .await?;
+        .await
+    }
 
-        let mut song_ids: Vec<String> = song_ids.into_iter().map(|(id,)| id).collect();
+    /// Replace a genre's aliases. A registered genre that an alias now names is folded into
+    /// this one: its children move here and it is removed (run `normalize_song_genres` to
+    /// rewrite its songs).
+    pub async fn update_genre(
+        &self,
+        payload: UpdateGenrePayload,
+    ) -> Result<Option<Genre>, sqlx::Error> {
+        let Some(genre) = self.get_genre_by_id(&payload.id).await? else {
+            return Ok(None);
+        };
 
-        if song_ids.is_empty() {
-            return Ok(song_ids);
+        if let Some(aliases) = payload.aliases {
+            let all_genres: Vec<(String, String)> = sqlx::query_as("SELECT id, name FROM genres")
+                .fetch_all(&self.pool)
+                .await?;
+
+            let mut tx = self.pool.begin().await?;
+
+            sqlx::query("DELETE FROM genre_aliases WHERE genre_id = ?")
+                .bind(&genre.id)
+                .execute(&mut *tx)
+                .await?;
+
+            for alias in aliases.iter().map(|a| a.trim()) {
+                let key = genres::fold_genre(alias);
+                if key.is_empty() || key == genres::fold_genre(&genre.name) {
+                    continue;
+                }
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO genre_aliases (alias, genre_id) VALUES (?, ?)
+                    ON CONFLICT(alias) DO UPDATE SET genre_id = excluded.genre_id
+                    "#,
+                )
+                .bind(alias)
+                .bind(&genre.id)
+                .execute(&mut *tx)
+                .await?;
+
+                for (other_id, _) in all_genres
+                    .iter()
+                    .filter(|(other_id, name)| *other_id != genre.id && genres::fold_genre(name) == key)
+                {
+                    sqlx::query("UPDATE genres SET parent_id = ? WHERE parent_id = ?")
+                        .bind(&genre.id)
+                        .bind(other_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    sqlx::query("UPDATE genre_aliases SET genre_id = ? WHERE genre_id = ?")
+                        .bind(&genre.id)
+                        .bind(other_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    sqlx::query("DELETE FROM genres WHERE id = ?")
+                        .bind(other_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+
+            tx.commit().await?;
         }
 
-        // Shuffle the song IDs using a thread-safe RNG
-        song_ids.shuffle(&mut rand::rng());
+        self.get_genre_by_id(&genre.id).await
+    }
 
-        // Reorder using the existing method
-        self.reorder_playlist_songs(playlist_id, &song_ids).await?;
+    /// Map every song's genres onto the taxonomy's canonical names.
+    ///
+    /// Returns the songs whose genres changed.
+    pub async fn normalize_song_genres(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let resolver = self.genre_resolver().await?;
 
-        Ok(song_ids)
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, genres FROM songs")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut changed = Vec::new();
+        for (song_id, genres_json) in rows {
+            let genres: Vec<String> = serde_json::from_str(&genres_json).unwrap_or_default();
+            let normalized = resolver.normalize(&genres);
+            self.register_genres(&normalized).await?;
+            if normalized == genres {
+                continue;
+            }
+
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("UPDATE songs SET genres = ?, updated_at = ? WHERE id = ?")
+                .bind(serde_json::to_string(&normalized).unwrap_or("[]".into()))
+                .bind(Utc::now())
+                .bind(&song_id)
+                .execute(&mut *tx)
+                .await?;
+            set_song_links(&mut tx, SongLink::Genre, &song_id, &normalized).await?;
+            tx.commit().await?;
+
+            if let Some(song) = self.get_song_by_id(&song_id).await? {
+                changed.push(song);
+            }
+        }
+
+        Ok(changed)
     }
 
-    // Marker Management
+    // Lyrics
 
-    pub async fn get_markers(&self, song_id: &str) -> Result<Vec<Marker>, sqlx::Error> {
-        let db_markers: Vec<DbMarker> = sqlx::query_as("SELECT * FROM markers WHERE song_id = ?")
+    pub async fn get_lyrics(&self, song_id: &str) -> Result<Option<Lyrics>, sqlx::Error> {
+        let db_lyrics: Option<DbLyrics> = sqlx::query_as("SELECT * FROM lyrics WHERE song_id = ?")
             .bind(song_id)
-            .fetch_all(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
 
-        Ok(db_markers.into_iter().map(|m| m.into()).collect())
+        Ok(db_lyrics.map(|l| l.into()))
     }
 
-    pub async fn create_marker(&self, marker: Marker) -> Result<Marker, sqlx::Error> {
-        let db_marker = DbMarker {
-            id: marker.id.clone(),
-            song_id: marker.song.clone(),
-            start: marker.start,
-            end: marker.end.clone(),
-            comment: marker.comment.clone(),
-            color: marker.color.clone(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+    /// Insert or replace a song's lyrics.
+    pub async fn save_lyrics(&self, lyrics: &Lyrics) -> Result<(), sqlx::Error> {
+        let lines = if lyrics.lines.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&lyrics.lines).unwrap_or("[]".into()))
         };
 
         sqlx::query(
             r#"
-            INSERT INTO markers (id, song_id, start, end, comment, color, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO lyrics (song_id, text, lines, language, source, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(song_id) DO UPDATE SET
+                text = excluded.text,
+                lines = excluded.lines,
+                language = excluded.language,
+                source = excluded.source,
+                updated_at = excluded.updated_at
             "#,
         )
-        .bind(&db_marker.id)
-        .bind(&db_marker.song_id)
-        .bind(db_marker.start)
-        .bind(db_marker.end)
-        .bind(&db_marker.comment)
-        .bind(&db_marker.color)
-        .bind(db_marker.created_at)
-        .bind(db_marker.updated_at)
+        .bind(&lyrics.song_id)
+        .bind(&lyrics.text)
+        .bind(lines)
+        .bind(&lyrics.language)
+        .bind(&lyrics.source)
+        .bind(Utc::now())
+        .bind(Utc::now())
         .execute(&self.pool)
         .await?;
 
-        Ok(marker)
+        Ok(())
     }
-}
+
+    pub async fn delete_lyrics(&self, song_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM lyrics WHERE song_id = ?")
+            .bind(song_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Songs whose lyrics contain every word of `query` (as a prefix), best matches first,
+    /// with a snippet of the matching lyrics.
+    pub async fn search_lyrics(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<LyricsSearchResult>, sqlx::Error> {
+        // Quote each word so FTS syntax in the query is taken literally.
+        let match_expr = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if match_expr.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT lyrics.song_id, snippet(lyrics_fts, 0, '[', ']', '...', 12)
+            FROM lyrics_fts
+            JOIN lyrics ON lyrics.rowid = lyrics_fts.rowid
+            WHERE lyrics_fts MATCH ?
+            ORDER BY rank
+            LIMIT ?
+            "#,
+        )
+        .bind(match_expr)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut results = Vec::with_capacity(rows.len());
+        for (song_id, snippet) in rows {
+            if let Some(song) = self.get_song_by_id(&song_id).await? {
+                results.push(LyricsSearchResult { song, snippet });
+            }
+        }
+        Ok(results)
+    }
+
+    // Fingerprints
+
+    pub async fn save_song_fingerprint(
+        &self,
+        song_id: &str,
+        fingerprint: &[u32],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO song_fingerprints (song_id, fingerprint, created_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(song_id) DO UPDATE SET fingerprint = excluded.fingerprint, created_at = excluded.created_at
+            "#,
+        )
+        .bind(song_id)
+        .bind(crate::fingerprint::to_bytes(fingerprint))
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("DELETE FROM fingerprint_failures WHERE song_id = ?")
+            .bind(song_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record that a song's audio gave no fingerprint (`error` is None when it was too short),
+    /// so get_songs_without_fingerprint leaves it out until one is saved.
+    pub async fn save_fingerprint_failure(
+        &self,
+        song_id: &str,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO fingerprint_failures (song_id, error, attempted_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(song_id) DO UPDATE SET error = excluded.error, attempted_at = excluded.attempted_at
+            "#,
+        )
+        .bind(song_id)
+        .bind(error)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every song of a file: the file itself, or its cue sheet tracks in the order they play.
+    pub async fn get_songs_by_url(&self, url: &str) -> Result<Vec<Song>, sqlx::Error> {
+        let db_songs: Vec<DbSong> =
+            sqlx::query_as("SELECT * FROM songs WHERE url = ? ORDER BY track_start")
+                .bind(url)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+    }
+
+    /// Songs with the given title, ignoring case.
+    pub async fn get_songs_by_title(&self, title: &str) -> Result<Vec<Song>, sqlx::Error> {
+        let db_songs: Vec<DbSong> =
+            sqlx::query_as("SELECT * FROM songs WHERE title = ? COLLATE NOCASE ORDER BY url")
+                .bind(title)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+    }
+
+    /// The cue sheet tracks of a file, in the order they play.
+    pub async fn get_cue_tracks(&self, url: &str) -> Result<Vec<Song>, sqlx::Error> {
+        let db_songs: Vec<DbSong> = sqlx::query_as(
+            "SELECT * FROM songs WHERE url = ? AND track_start IS NOT NULL ORDER BY track_start",
+        )
+        .bind(url)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+    }
+
+    pub async fn get_available_songs(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let db_songs: Vec<DbSong> = sqlx::query_as("SELECT * FROM songs WHERE available = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+    }
+
+    /// Available songs with no fingerprint that haven't failed to give one.
+    pub async fn get_songs_without_fingerprint(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let db_songs: Vec<DbSong> = sqlx::query_as(
+            r#"
+            SELECT * FROM songs
+            WHERE available = 1
+              AND id NOT IN (SELECT song_id FROM song_fingerprints)
+              AND id NOT IN (SELECT song_id FROM fingerprint_failures)
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+    }
+
+    pub async fn get_fingerprinted_songs(&self) -> Result<Vec<(Song, Vec<u32>)>, sqlx::Error> {
+        let rows: Vec<(String, Vec<u8>)> =
+            sqlx::query_as("SELECT song_id, fingerprint FROM song_fingerprints")
+                .fetch_all(&self.pool)
+                .await?;
+        let mut fingerprints: HashMap<String, Vec<u32>> = rows
+            .into_iter()
+            .map(|(id, bytes)| (id, crate::fingerprint::from_bytes(&bytes)))
+            .collect();
+
+        let db_songs: Vec<DbSong> = sqlx::query_as(
+            "SELECT * FROM songs WHERE id IN (SELECT song_id FROM song_fingerprints)",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(db_songs
+            .into_iter()
+            .filter_map(|s| {
+                let fingerprint = fingerprints.remove(&s.id)?;
+                Some((s.into(), fingerprint))
+            })
+            .collect())
+    }
+
+    // Settings
+
+    pub async fn get_settings(&self) -> Result<AppSettings, sqlx::Error> {
+        let value: Option<String> = sqlx::query_scalar("SELECT value FROM settings WHERE key = 'app'")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(value
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn save_settings(&self, settings: &AppSettings) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO settings (key, value, updated_at) VALUES ('app', ?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(serde_json::to_string(settings).unwrap_or("{}".into()))
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Playlist Management
+
+    pub async fn get_playlists(
+        &self,
+        query: GetPlaylistsQuery,
+    ) -> Result<Vec<Playlist>, sqlx::Error> {
+        let mut sql = "SELECT * FROM playlists".to_string();
+
+        // Add filters if provided
+        if let Some(filters) = query.filters {
+            if let Some(filters_obj) = filters.as_object() {
+                let mut where_clauses = Vec::new();
+                for (key, value) in filters_obj {
+                    where_clauses.push(format!("{} = '{}'", key, value));
+                }
+                if !where_clauses.is_empty() {
+                    sql.push_str(&format!(" WHERE {}", where_clauses.join(" AND ")));
+                }
+            }
+        }
+
+        // Add sorting
+        if let Some(sort) = query.sort {
+            sql.push_str(&format!(" ORDER BY {}", sort));
+        }
+
+        let db_playlists: Vec<DbPlaylist> = sqlx::query_as(&sql).fetch_all(&self.pool).await?;
+
+        Ok(db_playlists.into_iter().map(|p| p.into()).collect())
+    }
+
+    pub async fn create_playlist(&self, playlist: Playlist) -> Result<Playlist, sqlx::Error> {
+        let db_playlist = DbPlaylist {
+            id: playlist.id.clone(),
+            name: playlist.name.clone(),
+            tags: serde_json::to_string(&playlist.tags).unwrap_or_default(),
+            total_duration: playlist.total_duration,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO playlists (id, name, tags, total_duration, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&db_playlist.id)
+        .bind(&db_playlist.name)
+        .bind(&db_playlist.tags)
+        .bind(db_playlist.total_duration)
+        .bind(db_playlist.created_at)
+        .bind(db_playlist.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(playlist)
+    }
+
+    /// Create a playlist with its songs in order and journal it as `operation`, in one
+    /// transaction.
+    pub async fn create_playlist_with_songs(
+        &self,
+        snapshot: &PlaylistSnapshot,
+        operation: &str,
+    ) -> Result<(), sqlx::Error> {
+        let id = &snapshot.playlist.id;
+        let mut tx = self.pool.begin().await?;
+        restore_playlist(&mut tx, id, Some(snapshot)).await?;
+        let change = JournalChange::Playlist {
+            id: id.clone(),
+            before: None,
+            after: Some(snapshot.clone()),
+        };
+        record_edit_in(&mut tx, operation, &[change]).await?;
+        tx.commit().await
+    }
+
+    pub async fn delete_playlist(&self, id: &str) -> Result<bool, sqlx::Error> {
+        // First, delete all playlist_songs entries for this playlist
+        sqlx::query("DELETE FROM playlist_songs WHERE playlist_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        // Then delete the playlist itself
+        let result = sqlx::query("DELETE FROM playlists WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_max_playlist_position(&self, playlist_id: &str) -> Result<i32, sqlx::Error> {
+        let result: Option<(i32,)> = sqlx::query_as(
+            "SELECT COALESCE(MAX(position), -1) FROM playlist_songs WHERE playlist_id = ?"
+        )
+        .bind(playlist_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|(pos,)| pos).unwrap_or(-1))
+    }
+
+    pub async fn shift_playlist_positions(
+        &self,
+        playlist_id: &str,
+        from_position: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE playlist_songs SET position = position + 1 WHERE playlist_id = ? AND position >= ?"
+        )
+        .bind(playlist_id)
+        .bind(from_position)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_song_to_playlist(
+        &self,
+        id: &str,
+        playlist_id: &str,
+        song_id: &str,
+        position: i32,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO playlist_songs (id, playlist_id, song_id, position, added_at)
+            VALUES (?, ?, ?, ?, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(id)
+        .bind(playlist_id)
+        .bind(song_id)
+        .bind(position)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(true)
+    }
+
+    pub async fn get_playlist_songs(
+        &self,
+        playlist_id: &str,
+        sort: Option<&str>,
+    ) -> Result<Vec<Song>, sqlx::Error> {
+        let mut sql = r#"
+            SELECT s.* FROM songs s
+            INNER JOIN playlist_songs ps ON s.id = ps.song_id
+            WHERE ps.playlist_id = ?
+        "#.to_string();
+
+        // Add sorting - default to position order
+        if let Some(sort_field) = sort {
+            sql.push_str(&format!(" ORDER BY s.{}", sort_field));
+        } else {
+            sql.push_str(" ORDER BY ps.position");
+        }
+
+        let db_songs: Vec<DbSong> = sqlx::query_as(&sql)
+            .bind(playlist_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(db_songs.into_iter().map(|s| s.into()).collect())
+    }
+
+    pub async fn remove_song_from_playlist_by_song_id(
+        &self,
+        playlist_id: &str,
+        song_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        // Get all positions of the song being removed (in descending order to avoid shifting issues)
+        let positions: Vec<(i32,)> = sqlx::query_as(
+            "SELECT position FROM playlist_songs WHERE playlist_id = ? AND song_id = ? ORDER BY position DESC"
+        )
+        .bind(playlist_id)
+        .bind(song_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if positions.is_empty() {
+            return Ok(false);
+        }
+
+        // Delete all instances of the song from the playlist
+        sqlx::query(
+            "DELETE FROM playlist_songs WHERE playlist_id = ? AND song_id = ?"
+        )
+        .bind(playlist_id)
+        .bind(song_id)
+        .execute(&self.pool)
+        .await?;
+
+        // Shift positions for each removed song (process from highest to lowest position)
+        for (removed_position,) in positions {
+            sqlx::query(
+                "UPDATE playlist_songs SET position = position - 1 WHERE playlist_id = ? AND position > ?"
+            )
+            .bind(playlist_id)
+            .bind(removed_position)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(true)
+    }
+
+    pub async fn remove_song_from_playlist_by_position(
+        &self,
+        playlist_id: &str,
+        position: i32,
+    ) -> Result<bool, sqlx::Error> {
+        // Delete the song at the specified position
+        let result = sqlx::query(
+            "DELETE FROM playlist_songs WHERE playlist_id = ? AND position = ?"
+        )
+        .bind(playlist_id)
+        .bind(position)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // Shift positions of songs after the removed position
+        sqlx::query(
+            "UPDATE playlist_songs SET position = position - 1 WHERE playlist_id = ? AND position > ?"
+        )
+        .bind(playlist_id)
+        .bind(position)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(true)
+    }
+
+    pub async fn reorder_playlist_songs(
+        &self,
+        playlist_id: &str,
+        song_ids: &[String],
+    ) -> Result<bool, sqlx::Error> {
+        // IMPORTANT: playlists can contain the same song multiple times. Updating by song_id would
+        // affect multiple rows and break ordering. We rebuild the playlist_songs rows in the
+        // requested order, preserving each original row's `added_at` (stable for duplicates).
+
+        let current_rows: Vec<DbPlaylistSong> = sqlx::query_as(
+            "SELECT id, playlist_id, song_id, position, added_at FROM playlist_songs WHERE playlist_id = ? ORDER BY position",
+        )
+        .bind(playlist_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if current_rows.len() != song_ids.len() {
+            return Ok(false);
+        }
+
+        let mut rows_by_song: HashMap<String, VecDeque<DbPlaylistSong>> = HashMap::new();
+        for row in current_rows {
+            rows_by_song.entry(row.song_id.clone()).or_default().push_back(row);
+        }
+
+        let mut ordered_rows: Vec<DbPlaylistSong> = Vec::with_capacity(song_ids.len());
+        for song_id in song_ids {
+            let Some(queue) = rows_by_song.get_mut(song_id) else {
+                return Ok(false);
+            };
+            let Some(row) = queue.pop_front() else {
+                return Ok(false);
+            };
+            ordered_rows.push(row);
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Clear all entries for this playlist, then re-insert in the new order.
+        sqlx::query("DELETE FROM playlist_songs WHERE playlist_id = ?")
+            .bind(playlist_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (index, row) in ordered_rows.into_iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO playlist_songs (id, playlist_id, song_id, position, added_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(row.id)
+            .bind(row.playlist_id)
+            .bind(row.song_id)
+            .bind(index as i32)
+            .bind(row.added_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn shuffle_playlist_songs(
+        &self,
+        playlist_id: &str,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        use rand::seq::SliceRandom;
+
+        // Get all song IDs in the playlist ordered by position
+        let song_ids: Vec<(String,)> = sqlx::query_as(
+            "SELECT song_id FROM playlist_songs WHERE playlist_id = ? ORDER BY position"
+        )
+        .bind(playlist_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut song_ids: Vec<String> = song_ids.into_iter().map(|(id,)| id).collect();
+
+        if song_ids.is_empty() {
+            return Ok(song_ids);
+        }
+
+        // Shuffle the song IDs using a thread-safe RNG
+        song_ids.shuffle(&mut rand::rng());
+
+        // Reorder using the existing method
+        self.reorder_playlist_songs(playlist_id, &song_ids).await?;
+
+        Ok(song_ids)
+    }
+
+    // Marker Management
+
+    /// A song's markers, in their order.
+    pub async fn get_markers(&self, song_id: &str) -> Result<Vec<Marker>, sqlx::Error> {
+        let db_markers: Vec<DbMarker> = sqlx::query_as(
+            "SELECT * FROM markers WHERE song_id = ? ORDER BY position, start",
+        )
+        .bind(song_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(db_markers.into_iter().map(|m| m.into()).collect())
+    }
+
+    pub async fn get_marker(&self, id: &str) -> Result<Option<Marker>, sqlx::Error> {
+        let db_marker: Option<DbMarker> = sqlx::query_as("SELECT * FROM markers WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(db_marker.map(|m| m.into()))
+    }
+
+    /// Add a marker after the song's other markers. Returns it with its position.
+    pub async fn create_marker(&self, mut marker: Marker) -> Result<Marker, sqlx::Error> {
+        let (position,): (i64,) = sqlx::query_as(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM markers WHERE song_id = ?",
+        )
+        .bind(&marker.song)
+        .fetch_one(&self.pool)
+        .await?;
+        marker.position = position as i32;
+
+        insert_marker(&mut *self.pool.acquire().await?, &marker).await?;
+        Ok(marker)
+    }
+
+    pub async fn update_marker(&self, marker: &Marker) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE markers
+            SET start = ?, end = ?, comment = ?, color = ?, kind = ?, section = ?, hot_cue = ?,
+                position = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(marker.start)
+        .bind(marker.end)
+        .bind(&marker.comment)
+        .bind(&marker.color)
+        .bind(marker.kind.as_str())
+        .bind(marker.section.map(|s| s.as_str()))
+        .bind(marker.hot_cue.map(i64::from))
+        .bind(marker.position)
+        .bind(Utc::now())
+        .bind(&marker.id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_marker(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM markers WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Put a song's markers in the given order; markers left out follow in their old order.
+    /// Returns the markers in their new order.
+    pub async fn reorder_markers(
+        &self,
+        song_id: &str,
+        marker_ids: &[String],
+    ) -> Result<Vec<Marker>, sqlx::Error> {
+        let mut markers = self.get_markers(song_id).await?;
+        markers.sort_by_key(|m| {
+            marker_ids
+                .iter()
+                .position(|id| *id == m.id)
+                .unwrap_or(marker_ids.len())
+        });
+
+        let mut tx = self.pool.begin().await?;
+        for (position, marker) in markers.iter_mut().enumerate() {
+            marker.position = position as i32;
+            sqlx::query("UPDATE markers SET position = ?, updated_at = ? WHERE id = ?")
+                .bind(marker.position)
+                .bind(Utc::now())
+                .bind(&marker.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(markers)
+    }
+
+    // Serato Cues
+
+    /// Positions (ms) of the Serato cues already imported from a file.
+    pub async fn get_seen_serato_cues(&self, url: &str) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT start FROM serato_cues WHERE url = ? ORDER BY start")
+            .bind(url)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Remember Serato cue positions (ms) of a file as imported.
+    pub async fn mark_serato_cues_seen(&self, url: &str, starts: &[i64]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for start in starts {
+            sqlx::query("INSERT OR IGNORE INTO serato_cues (url, start) VALUES (?, ?)")
+                .bind(url)
+                .bind(start)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    // Original Tags
+
+    /// Store the tags a song had on import. Songs keep the first snapshot taken.
+    pub async fn save_original_tags(
+        &self,
+        song_id: &str,
+        tags: &TagSnapshot,
+        metadata: &SongMetadata,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO original_tags (song_id, format, frames, raw, metadata, captured_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(song_id) DO NOTHING
+            "#,
+        )
+        .bind(song_id)
+        .bind(&tags.format)
+        .bind(serde_json::to_string(&tags.frames).unwrap_or("[]".into()))
+        .bind(&tags.raw)
+        .bind(serde_json::to_string(metadata).unwrap_or_default())
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_original_tags(&self, song_id: &str) -> Result<Option<OriginalTags>, sqlx::Error> {
+        let row: Option<DbOriginalTags> =
+            sqlx::query_as("SELECT * FROM original_tags WHERE song_id = ?")
+                .bind(song_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let metadata = serde_json::from_str(&row.metadata)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        Ok(Some(OriginalTags {
+            song_id: row.song_id,
+            tags: TagSnapshot {
+                format: row.format,
+                frames: serde_json::from_str(&row.frames).unwrap_or_default(),
+                raw: row.raw,
+            },
+            metadata,
+            captured_at: row.captured_at,
+        }))
+    }
+
+    // Edit Journal
+
+    /// Journal an edit for undo. This drops the redo history.
+    pub async fn record_edit(
+        &self,
+        operation: &str,
+        changes: &[JournalChange],
+    ) -> Result<(), sqlx::Error> {
+        record_edit_in(&mut *self.pool.acquire().await?, operation, changes).await
+    }
+
+    /// The latest journaled edits first, undone ones included.
+    pub async fn get_edit_journal(&self, limit: i64) -> Result<Vec<JournalEntry>, sqlx::Error> {
+        let entries: Vec<DbJournalEntry> =
+            sqlx::query_as("SELECT * FROM edit_journal ORDER BY id DESC LIMIT ?")
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(entries.into_iter().map(|e| e.into()).collect())
+    }
+
+    /// Put everything the latest edit changed back the way it was. Returns the undone entry,
+    /// or `None` if there is nothing to undo.
+    pub async fn undo_edit(&self) -> Result<Option<JournalEntry>, sqlx::Error> {
+        self.replay_edit(true).await
+    }
+
+    /// Re-apply the most recently undone edit.
+    pub async fn redo_edit(&self) -> Result<Option<JournalEntry>, sqlx::Error> {
+        self.replay_edit(false).await
+    }
+
+    async fn replay_edit(&self, undo: bool) -> Result<Option<JournalEntry>, sqlx::Error> {
+        let sql = if undo {
+            "SELECT * FROM edit_journal WHERE undone = 0 ORDER BY id DESC LIMIT 1"
+        } else {
+            "SELECT * FROM edit_journal WHERE undone = 1 ORDER BY id LIMIT 1"
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let entry: Option<DbJournalEntry> = sqlx::query_as(sql).fetch_optional(&mut *tx).await?;
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        let mut entry = JournalEntry::from(entry);
+
+        if undo {
+            for change in entry.changes.iter().rev() {
+                restore_journal_change(&mut tx, change, false).await?;
+            }
+        } else {
+            for change in &entry.changes {
+                restore_journal_change(&mut tx, change, true).await?;
+            }
+        }
+
+        entry.undone = undo;
+        sqlx::query("UPDATE edit_journal SET undone = ? WHERE id = ?")
+            .bind(entry.undone)
+            .bind(entry.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(Some(entry))
+    }
+
+    /// A playlist and its song order, for journaling playlist edits.
+    pub async fn get_playlist_snapshot(
+        &self,
+        id: &str,
+    ) -> Result<Option<PlaylistSnapshot>, sqlx::Error> {
+        let playlist: Option<DbPlaylist> = sqlx::query_as("SELECT * FROM playlists WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(playlist) = playlist else {
+            return Ok(None);
+        };
+
+        let song_ids: Vec<String> = sqlx::query_scalar(
+            "SELECT song_id FROM playlist_songs WHERE playlist_id = ? ORDER BY position",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(PlaylistSnapshot {
+            playlist: playlist.into(),
+            song_ids,
+        }))
+    }
+}
+
+/// Song updates applied in one transaction, from [`Database::begin_song_updates`].
+///
+/// Each song's update sits in a savepoint until [`keep_song`](Self::keep_song) or
+/// [`undo_song`](Self::undo_song), so one song can be rolled back without losing the others.
+/// Nothing is visible to other connections until [`commit`](Self::commit); dropping the batch
+/// rolls everything back.
+pub struct SongUpdateBatch {
+    tx: Transaction<'static, Sqlite>,
+    artists: ArtistResolver,
+    genres: GenreResolver,
+    operation: String,
+    changes: Vec<JournalChange>,
+    pending: Option<JournalChange>, // the open savepoint's change
+}
+
+impl SongUpdateBatch {
+    pub async fn get_song(&mut self, id: &str) -> Result<Option<Song>, sqlx::Error> {
+        fetch_song(&mut self.tx, id).await
+    }
+
+    /// Update a song in a new savepoint, which must be closed with `keep_song` or `undo_song`
+    /// when this returns a song. Unknown ids and failed updates leave no savepoint open.
+    pub async fn update_song(
+        &mut self,
+        id: &str,
+        updates: UpdateSongPayload,
+    ) -> Result<Option<Song>, sqlx::Error> {
+        let Some(before) = fetch_song(&mut self.tx, id).await? else {
+            return Ok(None);
+        };
+
+        sqlx::query("SAVEPOINT song_update").execute(&mut *self.tx).await?;
+        let updated = update_song_row(&mut self.tx, &self.artists, &self.genres, id, updates).await;
+        self.open_change(before, updated).await
+    }
+
+    /// Overwrite a song's metadata in a new savepoint, like `update_song`. Fields missing from
+    /// `metadata` are cleared and artists and genres are stored as given.
+    pub async fn set_song(
+        &mut self,
+        id: &str,
+        metadata: &SongMetadata,
+    ) -> Result<Option<Song>, sqlx::Error> {
+        let Some(before) = fetch_song(&mut self.tx, id).await? else {
+            return Ok(None);
+        };
+
+        sqlx::query("SAVEPOINT song_update").execute(&mut *self.tx).await?;
+        let updated = match restore_song_metadata(&mut self.tx, id, metadata).await {
+            Ok(()) => fetch_song(&mut self.tx, id).await,
+            Err(e) => Err(e),
+        };
+        self.open_change(before, updated).await
+    }
+
+    /// Hold the journal change of an update made in the open savepoint, or close the savepoint
+    /// when the update didn't happen.
+    async fn open_change(
+        &mut self,
+        before: Song,
+        updated: Result<Option<Song>, sqlx::Error>,
+    ) -> Result<Option<Song>, sqlx::Error> {
+        match updated {
+            Ok(Some(song)) => {
+                self.pending = Some(JournalChange::Song {
+                    id: before.id,
+                    before: Box::new(before.metadata),
+                    after: Box::new(song.metadata.clone()),
+                    tags_written: false,
+                });
+                Ok(Some(song))
+            }
+            Ok(None) => {
+                self.release().await?;
+                Ok(None)
+            }
+            Err(e) => {
+                self.undo_song().await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Keep the last updated song's changes; `tags_written` says whether its file was retagged.
+    pub async fn keep_song(&mut self, tags_written: bool) -> Result<(), sqlx::Error> {
+        self.release().await?;
+        if let Some(mut change) = self.pending.take() {
+            if let JournalChange::Song { tags_written: written, .. } = &mut change {
+                *written = tags_written;
+            }
+            self.changes.push(change);
+        }
+        Ok(())
+    }
+
+    /// Roll back the last updated song's changes.
+    pub async fn undo_song(&mut self) -> Result<(), sqlx::Error> {
+        sqlx::query("ROLLBACK TO song_update").execute(&mut *self.tx).await?;
+        self.pending = None;
+        self.release().await
+    }
+
+    async fn release(&mut self) -> Result<(), sqlx::Error> {
+        sqlx::query("RELEASE song_update").execute(&mut *self.tx).await?;
+        Ok(())
+    }
+
+    /// Commit the kept songs and journal them as one edit.
+    pub async fn commit(mut self) -> Result<(), sqlx::Error> {
+        record_edit_in(&mut self.tx, &self.operation, &self.changes).await?;
+        self.tx.commit().await
+    }
+}
+
+const VARIOUS_ARTISTS: &str = "Various Artists";
+
+/// Trim values and drop empty ones and case-insensitive repeats, keeping the first spelling.
+fn tidy_values<'a>(values: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for value in values.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        if !out.iter().any(|o| o.eq_ignore_ascii_case(value)) {
+            out.push(value.to_string());
+        }
+    }
+    out
+}
+
+/// The join tables that index the songs' JSON artist, genre, instrument and tag arrays.
+#[derive(Debug, Clone, Copy)]
+enum SongLink {
+    Artist,
+    Genre,
+    Instrument,
+    Tag,
+}
+
+impl SongLink {
+    fn table_and_column(self) -> (&'static str, &'static str) {
+        match self {
+            SongLink::Artist => ("song_artists", "artist"),
+            SongLink::Genre => ("song_genres", "genre"),
+            SongLink::Instrument => ("song_instruments", "instrument"),
+            SongLink::Tag => ("song_tags", "tag"),
+        }
+    }
+}
+
+/// Replace a song's rows in a join table. Call this wherever the matching JSON column is
+/// written, in the same transaction.
+async fn set_song_links(
+    conn: &mut SqliteConnection,
+    link: SongLink,
+    song_id: &str,
+    values: &[String],
+) -> Result<(), sqlx::Error> {
+    let (table, column) = link.table_and_column();
+
+    sqlx::query(&format!("DELETE FROM {table} WHERE song_id = ?"))
+        .bind(song_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (position, value) in values.iter().enumerate() {
+        if value.is_empty() {
+            continue;
+        }
+        sqlx::query(&format!(
+            "INSERT INTO {table} (song_id, position, {column}) VALUES (?, ?, ?)"
+        ))
+        .bind(song_id)
+        .bind(position as i64)
+        .bind(value)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+// Connection-level halves of the song, album and registry helpers above, so they can run
+// inside a caller's transaction.
+
+async fn fetch_song(conn: &mut SqliteConnection, id: &str) -> Result<Option<Song>, sqlx::Error> {
+    let db_song: Option<DbSong> = sqlx::query_as("SELECT * FROM songs WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(db_song.map(|s| s.into()))
+}
+
+async fn assign_album_in(
+    conn: &mut SqliteConnection,
+    song_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let Some(song) = fetch_song(conn, song_id).await? else {
+        return Ok(None);
+    };
+
+    let (album_artist, compilation) = resolve_album_artist(&song.metadata);
+
+    sqlx::query(
+        r#"
+        INSERT INTO albums (id, title, album_artist, compilation, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(title, album_artist) DO NOTHING
+        "#,
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&song.metadata.album)
+    .bind(&album_artist)
+    .bind(compilation)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    let album_id: String =
+        sqlx::query_scalar("SELECT id FROM albums WHERE title = ? AND album_artist = ?")
+            .bind(&song.metadata.album)
+            .bind(&album_artist)
+            .fetch_one(&mut *conn)
+            .await?;
+
+    if song.metadata.album_id.as_deref() != Some(album_id.as_str()) {
+        sqlx::query("UPDATE songs SET album_id = ? WHERE id = ?")
+            .bind(&album_id)
+            .bind(song_id)
+            .execute(&mut *conn)
+            .await?;
+
+        if let Some(previous) = &song.metadata.album_id {
+            refresh_album_in(conn, previous).await?;
+            delete_empty_albums_in(conn).await?;
+        }
+    }
+
+    refresh_album_in(conn, &album_id).await?;
+    Ok(Some(album_id))
+}
+
+async fn refresh_album_in(conn: &mut SqliteConnection, album_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE albums SET
+            year = (SELECT MIN(year) FROM songs WHERE album_id = ?1),
+            disc_count = (SELECT COALESCE(MAX(disc_total), MAX(disc)) FROM songs WHERE album_id = ?1),
+            track_count = (SELECT MAX(COALESCE(MAX(track_total), 0), COUNT(*)) FROM songs WHERE album_id = ?1),
+            image = (
+                SELECT image FROM songs
+                WHERE album_id = ?1 AND image IS NOT NULL
+                ORDER BY disc, track
+                LIMIT 1
+            ),
+            compilation = album_artist = ?3
+                OR EXISTS (SELECT 1 FROM songs WHERE album_id = ?1 AND compilation = 1),
+            updated_at = ?2
+        WHERE id = ?1
+        "#,
+    )
+    .bind(album_id)
+    .bind(Utc::now())
+    .bind(VARIOUS_ARTISTS)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn delete_empty_albums_in(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM albums WHERE id NOT IN (SELECT album_id FROM songs WHERE album_id IS NOT NULL)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_artists(conn: &mut SqliteConnection, names: &[String]) -> Result<(), sqlx::Error> {
+    for name in names.iter().filter(|n| !n.is_empty()) {
+        sqlx::query(
+            r#"
+            INSERT INTO artists (id, name, sort_name, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO NOTHING
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(artists::default_sort_name(name))
+        .bind(Utc::now())
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_genres(conn: &mut SqliteConnection, names: &[String]) -> Result<(), sqlx::Error> {
+    for name in names.iter().filter(|n| !n.is_empty()) {
+        sqlx::query(
+            r#"
+            INSERT INTO genres (id, name, created_at, updated_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(name) DO NOTHING
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(Utc::now())
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Apply an update payload to a song's row, its join tables and album. Returns `Ok(None)` if
+/// the song doesn't exist.
+async fn update_song_row(
+    conn: &mut SqliteConnection,
+    artist_resolver: &ArtistResolver,
+    genre_resolver: &GenreResolver,
+    id: &str,
+    updates: UpdateSongPayload,
+) -> Result<Option<Song>, sqlx::Error> {
+    // Fast existence check so we can return Ok(None) for unknown ids
+    let Some(existing) = fetch_song(conn, id).await? else {
+        return Ok(None);
+    };
+    let mut extended = existing.metadata.extended;
+    let mut extended_changed = false;
+
+    let mut new_title: Option<String> = None;
+    let mut new_album: Option<String> = None;
+    let mut new_artists: Option<Vec<String>> = None;
+    let mut new_year: Option<i32> = None;
+    let mut new_bpm: Option<f32> = None;
+    let mut clear_bpm: bool = false;
+    let mut new_genres: Option<Vec<String>> = None;
+    let mut new_comment: Option<String> = None;
+    let mut new_image: Option<String> = None;
+    let mut clear_image: bool = false;
+    let mut new_track: Option<i32> = None;
+    let mut new_album_artist: Option<String> = None;
+    let mut clear_album_artist: bool = false;
+    let mut new_disc: Option<i32> = None;
+    let mut new_disc_total: Option<i32> = None;
+    let mut new_track_total: Option<i32> = None;
+    let mut new_compilation: Option<bool> = None;
+    let mut new_instruments: Option<Vec<String>> = None;
+    let mut clear_instruments: bool = false;
+    let mut new_tags: Option<Vec<String>> = None;
+    let mut new_rating: Option<f32> = None;
+    let mut clear_rating: bool = false;
+
+    if let Some(obj) = updates.metadata.as_object() {
+        if let Some(title) = obj.get("title").and_then(|v| v.as_str()) {
+            new_title = Some(title.to_string());
+        }
+        if let Some(album) = obj.get("album").and_then(|v| v.as_str()) {
+            new_album = Some(album.to_string());
+        }
+
+        if let Some(year) = obj.get("year").and_then(|v| v.as_i64()) {
+            if let Ok(year) = i32::try_from(year) {
+                new_year = Some(year);
+            }
+        }
+
+        if let Some(bpm_value) = obj.get("bpm") {
+            if bpm_value.is_null() {
+                clear_bpm = true;
+            } else if let Some(bpm) = bpm_value.as_f64().filter(|b| b.is_finite()) {
+                new_bpm = Some(bpm as f32);
+            }
+        }
+
+        // Preferred: { artists: ["a", "b"] }
+        if let Some(artists) = obj.get("artists").and_then(|v| v.as_array()) {
+            let artists_vec: Vec<String> = artists
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect();
+            new_artists = Some(artists_vec);
+        } else if let Some(artist) = obj.get("artist").and_then(|v| v.as_str()) {
+            // Fallback: { artist: "single" }
+            new_artists = Some(vec![artist.to_string()]);
+        }
+
+        // Preferred: { genres: ["a", "b"] }, but also accept { genre: "single" }
+        if let Some(genres) = obj.get("genres") {
+            if let Some(arr) = genres.as_array() {
+                let genres_vec: Vec<String> = arr
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .collect();
+                new_genres = Some(genres_vec);
+            } else if let Some(genre) = genres.as_str() {
+                new_genres = Some(vec![genre.to_string()]);
+            }
+        } else if let Some(genre) = obj.get("genre") {
+            if let Some(arr) = genre.as_array() {
+                let genres_vec: Vec<String> = arr
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .collect();
+                new_genres = Some(genres_vec);
+            } else if let Some(genre) = genre.as_str() {
+                new_genres = Some(vec![genre.to_string()]);
+            }
+        }
+
+        if let Some(comment) = obj.get("comment").and_then(|v| v.as_str()) {
+            new_comment = Some(comment.to_string());
+        }
+
+        if let Some(image_value) = obj.get("image") {
+            if image_value.is_null() {
+                clear_image = true;
+                new_image = None;
+            } else if let Some(image) = image_value.as_str() {
+                clear_image = false;
+                new_image = Some(image.to_string());
+            }
+        }
+
+        let get_i32 = |key: &str| {
+            obj.get(key)
+                .and_then(|v| v.as_i64())
+                .and_then(|n| i32::try_from(n).ok())
+        };
+        new_track = get_i32("track");
+        new_disc = get_i32("disc");
+        new_disc_total = get_i32("disc_total");
+        new_track_total = get_i32("track_total");
+
+        if let Some(album_artist_value) = obj.get("album_artist") {
+            if album_artist_value.is_null() {
+                clear_album_artist = true;
+            } else if let Some(album_artist) = album_artist_value.as_str() {
+                new_album_artist = Some(album_artist.to_string());
+            }
+        }
+
+        new_compilation = obj.get("compilation").and_then(|v| v.as_bool());
+
+        if let Some(instruments_value) = obj.get("instruments") {
+            if instruments_value.is_null() {
+                clear_instruments = true;
+            } else if let Some(arr) = instruments_value.as_array() {
+                new_instruments = Some(tidy_values(arr.iter().filter_map(|v| v.as_str())));
+            }
+        }
+        if let Some(arr) = obj.get("tags").and_then(|v| v.as_array()) {
+            new_tags = Some(tidy_values(arr.iter().filter_map(|v| v.as_str())));
+        }
+
+        if let Some(rating_value) = obj.get("rating") {
+            if rating_value.is_null() {
+                clear_rating = true;
+            } else {
+                new_rating = rating_value.as_f64().and_then(ratings::normalize_rating);
+            }
+        }
+
+        extended_changed = extended.apply_updates(obj);
+    }
+
+    // Split featured artists and map artist and genre aliases before storing.
+    let new_artists = match new_artists {
+        Some(artists) => {
+            let artists = artist_resolver.normalize(&artists);
+            insert_artists(conn, &artists).await?;
+            Some(artists)
+        }
+        None => None,
+    };
+
+    let new_genres = match new_genres {
+        Some(genres) => {
+            let genres = genre_resolver.normalize(&genres);
+            insert_genres(conn, &genres).await?;
+            Some(genres)
+        }
+        None => None,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE songs
+        SET
+          title = COALESCE(?, title),
+          album = COALESCE(?, album),
+          artists = COALESCE(?, artists),
+                        year = COALESCE(?, year),
+                        bpm = CASE WHEN ? = 1 THEN NULL ELSE COALESCE(?, bpm) END,
+                        genres = COALESCE(?, genres),
+                        comment = COALESCE(?, comment),
+                        image = CASE WHEN ? = 1 THEN NULL ELSE COALESCE(?, image) END,
+          track = COALESCE(?, track),
+          album_artist = CASE WHEN ? = 1 THEN NULL ELSE COALESCE(?, album_artist) END,
+          disc = COALESCE(?, disc),
+          disc_total = COALESCE(?, disc_total),
+          track_total = COALESCE(?, track_total),
+          compilation = COALESCE(?, compilation),
+          instruments = CASE WHEN ? = 1 THEN NULL ELSE COALESCE(?, instruments) END,
+          tags = COALESCE(?, tags),
+          rating = CASE WHEN ? = 1 THEN NULL ELSE COALESCE(?, rating) END,
+          filename = COALESCE(?, filename),
+          updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(new_title)
+    .bind(new_album)
+    .bind(
+        new_artists
+            .as_ref()
+            .map(|a| serde_json::to_string(a).unwrap_or("[]".into())),
+    )
+            .bind(new_year)
+            .bind(if clear_bpm { 1 } else { 0 })
+            .bind(new_bpm)
+            .bind(
+                new_genres
+                    .as_ref()
+                    .map(|g| serde_json::to_string(g).unwrap_or("[]".into())),
+            )
+            .bind(new_comment)
+        .bind(if clear_image { 1 } else { 0 })
+        .bind(new_image)
+    .bind(new_track)
+    .bind(if clear_album_artist { 1 } else { 0 })
+    .bind(new_album_artist)
+    .bind(new_disc)
+    .bind(new_disc_total)
+    .bind(new_track_total)
+    .bind(new_compilation)
+    .bind(if clear_instruments { 1 } else { 0 })
+    .bind(
+        new_instruments
+            .as_ref()
+            .map(|i| serde_json::to_string(i).unwrap_or("[]".into())),
+    )
+    .bind(
+        new_tags
+            .as_ref()
+            .map(|t| serde_json::to_string(t).unwrap_or("[]".into())),
+    )
+    .bind(if clear_rating { 1 } else { 0 })
+    .bind(new_rating)
+    .bind(updates.filename)
+    .bind(Utc::now())
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+
+    if let Some(artists) = &new_artists {
+        set_song_links(conn, SongLink::Artist, id, artists).await?;
+    }
+    if let Some(genres) = &new_genres {
+        set_song_links(conn, SongLink::Genre, id, genres).await?;
+    }
+    if clear_instruments || new_instruments.is_some() {
+        let instruments = new_instruments.unwrap_or_default();
+        set_song_links(conn, SongLink::Instrument, id, &instruments).await?;
+    }
+    if let Some(tags) = &new_tags {
+        set_song_links(conn, SongLink::Tag, id, tags).await?;
+    }
+    if extended_changed {
+        set_extended_tags(conn, id, &extended).await?;
+    }
+
+    assign_album_in(conn, id).await?;
+    fetch_song(conn, id).await
+}
+
+/// Write a song's extended tag columns.
+async fn set_extended_tags(
+    conn: &mut SqliteConnection,
+    song_id: &str,
+    extended: &ExtendedTags,
+) -> Result<(), sqlx::Error> {
+    let columns: Vec<String> = ExtendedTags::TEXT_FIELDS
+        .iter()
+        .map(|field| format!("{field} = ?"))
+        .collect();
+    let sql = format!(
+        "UPDATE songs SET {}, original_year = ? WHERE id = ?",
+        columns.join(", ")
+    );
+
+    let mut query = sqlx::query(&sql);
+    for field in ExtendedTags::TEXT_FIELDS {
+        query = query.bind(extended.text(field));
+    }
+    query
+        .bind(extended.original_year)
+        .bind(song_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// How many edits the journal keeps; older ones are dropped.
+const JOURNAL_LIMIT: i64 = 100;
+
+async fn record_edit_in(
+    conn: &mut SqliteConnection,
+    operation: &str,
+    changes: &[JournalChange],
+) -> Result<(), sqlx::Error> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    // A new edit ends the redo history.
+    sqlx::query("DELETE FROM edit_journal WHERE undone = 1")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("INSERT INTO edit_journal (operation, changes, created_at) VALUES (?, ?, ?)")
+        .bind(operation)
+        .bind(serde_json::to_string(changes).unwrap_or("[]".into()))
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("DELETE FROM edit_journal WHERE id <= (SELECT MAX(id) FROM edit_journal) - ?")
+        .bind(JOURNAL_LIMIT)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Put what a journal change touched into its state before the edit, or after it when `redo`.
+async fn restore_journal_change(
+    conn: &mut SqliteConnection,
+    change: &JournalChange,
+    redo: bool,
+) -> Result<(), sqlx::Error> {
+    match change {
+        JournalChange::Song { id, before, after, .. } => {
+            restore_song_metadata(conn, id, if redo { after } else { before }).await
+        }
+        JournalChange::Playlist { id, before, after } => {
+            let snapshot = if redo { after } else { before };
+            restore_playlist(conn, id, snapshot.as_ref()).await
+        }
+        JournalChange::Marker { id, before, after } => {
+            let marker = if redo { after } else { before };
+            restore_marker(conn, id, marker.as_ref()).await
+        }
+    }
+}
+
+/// Overwrite a song's tag-backed metadata with a full copy, from the journal or the original
+/// tags. Songs deleted since are skipped.
+async fn restore_song_metadata(
+    conn: &mut SqliteConnection,
+    id: &str,
+    metadata: &SongMetadata,
+) -> Result<(), sqlx::Error> {
+    let to_json = |values: &Vec<String>| serde_json::to_string(values).unwrap_or("[]".into());
+
+    let result = sqlx::query(
+        r#"
+        UPDATE songs SET
+          title = ?, album = ?, artists = ?, year = ?, bpm = ?, genres = ?, comment = ?,
+          image = ?, track = ?, album_artist = ?, disc = ?, disc_total = ?, track_total = ?,
+          compilation = ?, instruments = ?, tags = ?, rating = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&metadata.title)
+    .bind(&metadata.album)
+    .bind(to_json(&metadata.artists))
+    .bind(metadata.year)
+    .bind(metadata.bpm)
+    .bind(to_json(&metadata.genres))
+    .bind(&metadata.comment)
+    .bind(&metadata.image)
+    .bind(metadata.track)
+    .bind(&metadata.album_artist)
+    .bind(metadata.disc)
+    .bind(metadata.disc_total)
+    .bind(metadata.track_total)
+    .bind(metadata.compilation)
+    .bind(metadata.instruments.as_ref().map(to_json))
+    .bind(to_json(&metadata.tags))
+    .bind(metadata.rating)
+    .bind(Utc::now())
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(());
+    }
+
+    insert_artists(conn, &metadata.artists).await?;
+    insert_genres(conn, &metadata.genres).await?;
+    set_song_links(conn, SongLink::Artist, id, &metadata.artists).await?;
+    set_song_links(conn, SongLink::Genre, id, &metadata.genres).await?;
+    let instruments = metadata.instruments.clone().unwrap_or_default();
+    set_song_links(conn, SongLink::Instrument, id, &instruments).await?;
+    set_song_links(conn, SongLink::Tag, id, &metadata.tags).await?;
+    set_extended_tags(conn, id, &metadata.extended).await?;
+    assign_album_in(conn, id).await?;
+    Ok(())
+}
+
+/// Recreate a journaled playlist, or delete it when `snapshot` is `None`. Songs deleted since
+/// are left out.
+async fn restore_playlist(
+    conn: &mut SqliteConnection,
+    id: &str,
+    snapshot: Option<&PlaylistSnapshot>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM playlist_songs WHERE playlist_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+
+    let Some(snapshot) = snapshot else {
+        sqlx::query("DELETE FROM playlists WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(());
+    };
+
+    let playlist = &snapshot.playlist;
+    sqlx::query(
+        r#"
+        INSERT INTO playlists (id, name, tags, total_duration, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+          name = excluded.name,
+          tags = excluded.tags,
+          total_duration = excluded.total_duration,
+          updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(&playlist.name)
+    .bind(serde_json::to_string(&playlist.tags).unwrap_or("[]".into()))
+    .bind(playlist.total_duration)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    let mut position = 0;
+    for song_id in &snapshot.song_ids {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO playlist_songs (id, playlist_id, song_id, position, added_at)
+            SELECT ?, ?, ?, ?, ? WHERE EXISTS (SELECT 1 FROM songs WHERE id = ?)
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(id)
+        .bind(song_id)
+        .bind(position)
+        .bind(Utc::now())
+        .bind(song_id)
+        .execute(&mut *conn)
+        .await?;
+        position += result.rows_affected() as i32;
+    }
+    Ok(())
+}
+
+/// Recreate a journaled marker, or delete it when `marker` is `None`.
+async fn restore_marker(
+    conn: &mut SqliteConnection,
+    id: &str,
+    marker: Option<&Marker>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM markers WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+
+    let Some(marker) = marker else {
+        return Ok(());
+    };
+    let song_exists: Option<(String,)> = sqlx::query_as("SELECT id FROM songs WHERE id = ?")
+        .bind(&marker.song)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if song_exists.is_some() {
+        insert_marker(conn, marker).await?;
+    }
+    Ok(())
+}
+
+async fn insert_marker(conn: &mut SqliteConnection, marker: &Marker) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO markers (id, song_id, start, end, comment, color, kind, section, hot_cue,
+                             position, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&marker.id)
+    .bind(&marker.song)
+    .bind(marker.start)
+    .bind(marker.end)
+    .bind(&marker.comment)
+    .bind(&marker.color)
+    .bind(marker.kind.as_str())
+    .bind(marker.section.map(|s| s.as_str()))
+    .bind(marker.hot_cue.map(i64::from))
+    .bind(marker.position)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Matches songs tagged with a genre or any of its descendants. Binds the genre name twice.
+const GENRE_OR_DESCENDANT_CLAUSE: &str = r#"songs.id IN (
+    SELECT song_id FROM song_genres
+    WHERE genre = ? OR genre IN (
+        WITH RECURSIVE sub(id, name) AS (
+            SELECT id, name FROM genres WHERE name = ?
+            UNION
+            SELECT genres.id, genres.name FROM genres JOIN sub ON genres.parent_id = sub.id
+        )
+        SELECT name FROM sub
+    )
+)"#;
+
+/// The album artist a song is filed under, and whether that makes its album a compilation.
+///
+/// Compilations (the compilation flag, or an album artist like "VA") all go under
+/// "Various Artists"; otherwise the album artist falls back to the first track artist.
+fn resolve_album_artist(metadata: &SongMetadata) -> (String, bool) {
+    let album_artist = metadata
+        .album_artist
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty());
+
+    let is_various = album_artist.is_some_and(|a| {
+        matches!(
+            a.to_lowercase().as_str(),
+            "various artists" | "various" | "va" | "v.a." | "v/a"
+        )
+    });
+    if metadata.compilation || is_various {
+        return (VARIOUS_ARTISTS.to_string(), true);
+    }
+
+    let artist = album_artist
+        .or_else(|| metadata.artists.first().map(|a| a.trim()).filter(|a| !a.is_empty()))
+        .unwrap_or("Unknown Artist");
+    (artist.to_string(), false)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    async fn setup_test_db() -> Database {
-        let db_url = "sqlite::memory:";
-        Database::new(db_url)
-            .await
-            .expect("Failed to create test database")
-    }
+    async fn setup_test_db() -> Database {
+        let db_url = "sqlite::memory:";
+        Database::new(db_url)
+            .await
+            .expect("Failed to create test database")
+    }
+
+    #[tokio::test]
+    async fn test_database_creation() {
+        let db = setup_test_db().await;
+        assert!(db.pool.is_closed() == false);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_get_song() {
+        let db = setup_test_db().await;
+
+        let song = Song {
+            id: "test-song-1".to_string(),
+            url: "/path/to/test.mp3".to_string(),
+            filename: "test.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "Test Song".to_string(),
+                album: "Test Album".to_string(),
+                year: Some(2023),
+                track: Some(1),
+                duration: 180.0,
+                artists: vec!["Test Artist".to_string()],
+                bpm: Some(120.0),
+                genres: vec!["Rock".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        let created = db.create_song(song.clone()).await.unwrap();
+        assert_eq!(created.id, song.id);
+
+        let retrieved = db.get_song_by_id(&song.id).await.unwrap();
+        assert!(retrieved.is_some());
+        assert_eq!(retrieved.unwrap().metadata.title, "Test Song");
+    }
+
+    #[tokio::test]
+    async fn test_get_song_by_url_found() {
+        let db = setup_test_db().await;
+
+        let song = Song {
+            id: "test-song-url".to_string(),
+            url: "/path/to/test-url.mp3".to_string(),
+            filename: "test-url.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "URL Song".to_string(),
+                album: "Test Album".to_string(),
+                year: Some(2023),
+                track: Some(1),
+                duration: 180.0,
+                artists: vec!["Test Artist".to_string()],
+                bpm: Some(120.0),
+                genres: vec!["Rock".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        db.create_song(song.clone()).await.unwrap();
+
+        let retrieved = db.get_song_by_url(&song.url).await.unwrap();
+        assert!(retrieved.is_some());
+        assert_eq!(retrieved.unwrap().id, song.id);
+    }
+
+    #[tokio::test]
+    async fn test_get_song_by_url_not_found() {
+        let db = setup_test_db().await;
+
+        let retrieved = db.get_song_by_url("/path/to/missing.mp3").await.unwrap();
+        assert!(retrieved.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_songs_with_query() {
+        let db = setup_test_db().await;
+
+        // Create test songs
+        for i in 1..=3 {
+            let song = Song {
+                id: format!("song-{}", i),
+                url: format!("/path/song{}.mp3", i),
+                filename: format!("song{}.mp3", i),
+                metadata: SongMetadata {
+                    title: format!("Song {}", i),
+                    album: "Album".to_string(),
+                    year: Some(2023),
+                    track: Some(i),
+                    duration: 180.0 * i as f64,
+                    artists: vec!["Artist".to_string()],
+                    bpm: Some(120.0),
+                    genres: vec!["Rock".to_string()],
+                    file_exists: true,
+                    ..Default::default()
+                },
+                available: true,
+                range: None,
+            };
+            db.create_song(song).await.unwrap();
+        }
+
+        let query = GetSongsQuery {
+            filters: None,
+            sort: Some("title".to_string()),
+            limit: Some(10),
+            offset: None,
+        };
+
+        let result = db.get_songs(query).await.unwrap();
+        assert_eq!(result.total, 3);
+        assert_eq!(result.songs.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_get_songs_filter_by_genre() {
+        let db = setup_test_db().await;
+
+        let rock_song = Song {
+            id: "song-rock".to_string(),
+            url: "/path/rock.mp3".to_string(),
+            filename: "rock.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "Rock Song".to_string(),
+                album: "Album".to_string(),
+                year: Some(2023),
+                track: Some(1),
+                duration: 180.0,
+                artists: vec!["Artist".to_string()],
+                bpm: Some(120.0),
+                genres: vec!["Rock".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+        let pop_song = Song {
+            id: "song-pop".to_string(),
+            url: "/path/pop.mp3".to_string(),
+            filename: "pop.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "Pop Song".to_string(),
+                album: "Album".to_string(),
+                year: Some(2023),
+                track: Some(2),
+                duration: 200.0,
+                artists: vec!["Artist".to_string()],
+                bpm: Some(110.0),
+                genres: vec!["Pop".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        db.create_song(rock_song).await.unwrap();
+        db.create_song(pop_song).await.unwrap();
+
+        let query = GetSongsQuery {
+            filters: Some(serde_json::json!({ "genre": "Rock" })),
+            sort: None,
+            limit: None,
+            offset: None,
+        };
 
-    #[tokio::test]
-    async fn test_database_creation() {
-        let db = setup_test_db().await;
-        assert!(db.pool.is_closed() == false);
+        let result = db.get_songs(query).await.unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.songs.len(), 1);
+        assert_eq!(result.songs[0].metadata.genres, vec!["Rock".to_string()]);
     }
 
     #[tokio::test]
-    async fn test_create_and_get_song() {
+    async fn test_update_song() {
         let db = setup_test_db().await;
 
         let song = Song {
-            id: "test-song-1".to_string(),
-            url: "/path/to/test.mp3".to_string(),
+            id: "update-test".to_string(),
+            url: "/path/test.mp3".to_string(),
             filename: "test.mp3".to_string(),
             metadata: SongMetadata {
-                title: "Test Song".to_string(),
-                album: "Test Album".to_string(),
+                title: "Original".to_string(),
+                album: "Album".to_string(),
                 year: Some(2023),
                 track: Some(1),
-                image: None,
                 duration: 180.0,
-                artists: vec!["Test Artist".to_string()],
-                instruments: None,
+                artists: vec!["Artist".to_string()],
                 bpm: Some(120.0),
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        db.create_song(song).await.unwrap();
+
+        let update_payload = UpdateSongPayload {
+            id: "update-test".to_string(),
+            metadata: serde_json::json!({
+                "title": "Updated",
+                "year": 2024,
+                "bpm": 128.5,
+                "genre": "Rock",
+                "comment": "hello"
+            }),
+            update_id3: None,
+            filename: None,
+        };
+
+        let updated = db.update_song("update-test", update_payload).await.unwrap();
+        assert!(updated.is_some());
+
+        let updated = updated.unwrap();
+        assert_eq!(updated.metadata.title, "Updated");
+        assert_eq!(updated.metadata.year, Some(2024));
+        assert_eq!(updated.metadata.genres, vec!["Rock".to_string()]);
+        assert_eq!(updated.metadata.comment, Some("hello".to_string()));
+        assert!(updated
+            .metadata
+            .bpm
+            .map(|b| (b - 128.5).abs() < 0.0001)
+            .unwrap_or(false));
+
+        // A null BPM clears it.
+        let clear_payload = UpdateSongPayload {
+            id: "update-test".to_string(),
+            metadata: serde_json::json!({ "bpm": null }),
+            update_id3: None,
+            filename: None,
+        };
+        let updated = db.update_song("update-test", clear_payload).await.unwrap().unwrap();
+        assert_eq!(updated.metadata.bpm, None);
+        assert_eq!(updated.metadata.title, "Updated");
+    }
+
+    #[tokio::test]
+    async fn test_delete_song() {
+        let db = setup_test_db().await;
+
+        let song = Song {
+            id: "delete-test".to_string(),
+            url: "/path/test.mp3".to_string(),
+            filename: "test.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "Delete Me".to_string(),
+                album: "Album".to_string(),
+                duration: 120.0,
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        db.create_song(song).await.unwrap();
+        let deleted = db.delete_song("delete-test").await.unwrap();
+        assert!(deleted);
+
+        let retrieved = db.get_song_by_id("delete-test").await.unwrap();
+        assert!(retrieved.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fingerprint_failures_are_not_retried() {
+        let db = setup_test_db().await;
+        for id in ["short", "broken"] {
+            db.create_song(Song {
+                id: id.to_string(),
+                url: format!("/path/{}.mp3", id),
+                filename: format!("{}.mp3", id),
+                metadata: SongMetadata {
+                    title: id.to_string(),
+                    file_exists: true,
+                    ..Default::default()
+                },
+                available: true,
+                range: None,
+            })
+            .await
+            .unwrap();
+        }
+
+        db.save_fingerprint_failure("short", None).await.unwrap();
+        db.save_fingerprint_failure("broken", Some("unsupported codec")).await.unwrap();
+        assert!(db.get_songs_without_fingerprint().await.unwrap().is_empty());
+
+        // A fingerprint saved later replaces the failure.
+        db.save_song_fingerprint("broken", &[1, 2, 3]).await.unwrap();
+        let fingerprinted = db.get_fingerprinted_songs().await.unwrap();
+        assert_eq!(fingerprinted.len(), 1);
+        assert_eq!(fingerprinted[0].1, [1, 2, 3]);
+        let failures: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fingerprint_failures")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_merge_songs_preserves_playlists_and_markers() {
+        let db = setup_test_db().await;
+
+        let make_song = |id: &str, genres: Vec<&str>, tags: Vec<&str>, times_played: i32| Song {
+            id: id.to_string(),
+            url: format!("/path/{}.mp3", id),
+            filename: format!("{}.mp3", id),
+            metadata: SongMetadata {
+                title: "Same".to_string(),
+                album: "Album".to_string(),
+                duration: 180.0,
+                artists: vec!["Artist".to_string()],
+                genres: genres.into_iter().map(String::from).collect(),
+                tags: tags.into_iter().map(String::from).collect(),
+                file_exists: true,
+                times_played,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        db.create_song(make_song("keep", vec!["Rock"], vec!["fav"], 2))
+            .await
+            .unwrap();
+        db.create_song(make_song("dup", vec!["Rock", "Indie"], vec!["live"], 3))
+            .await
+            .unwrap();
+
+        db.create_playlist(Playlist {
+            id: "pl".to_string(),
+            name: "PL".to_string(),
+            tags: vec![],
+            total_duration: 0.0,
+        })
+        .await
+        .unwrap();
+        db.add_song_to_playlist("ps-1", "pl", "dup", 0).await.unwrap();
+
+        db.create_marker(Marker {
+            id: "m-1".to_string(),
+            song: "dup".to_string(),
+            start: 1.0,
+            end: None,
+            comment: None,
+            color: None,
+            ..Marker::default()
+        })
+        .await
+        .unwrap();
+
+        let merged = db
+            .merge_songs("keep", &["dup".to_string()])
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(merged.metadata.times_played, 5);
+        assert_eq!(merged.metadata.genres, vec!["Rock", "Indie"]);
+        assert_eq!(merged.metadata.tags, vec!["fav", "live"]);
+        assert!(db.get_song_by_id("dup").await.unwrap().is_none());
+
+        let playlist_songs = db.get_playlist_songs("pl", None).await.unwrap();
+        assert_eq!(playlist_songs.len(), 1);
+        assert_eq!(playlist_songs[0].id, "keep");
+        assert_eq!(db.get_markers("keep").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_settings_round_trip() {
+        let db = setup_test_db().await;
+
+        let defaults = db.get_settings().await.unwrap();
+        assert!(defaults.artwork.use_sidecar);
+
+        let mut settings = defaults.clone();
+        settings.artwork.sidecar_patterns = vec!["art.png".to_string()];
+        db.save_settings(&settings).await.unwrap();
+
+        let loaded = db.get_settings().await.unwrap();
+        assert_eq!(loaded.artwork.sidecar_patterns, vec!["art.png".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_get_playlist() {
+        let db = setup_test_db().await;
+
+        let playlist = Playlist {
+            id: "playlist-1".to_string(),
+            name: "Test Playlist".to_string(),
+            tags: vec!["chill".to_string()],
+            total_duration: 1200.0,
+        };
+
+        let created = db.create_playlist(playlist.clone()).await.unwrap();
+        assert_eq!(created.name, "Test Playlist");
+
+        let query = GetPlaylistsQuery {
+            filters: None,
+            sort: None,
+        };
+
+        let playlists = db.get_playlists(query).await.unwrap();
+        assert_eq!(playlists.len(), 1);
+        assert_eq!(playlists[0].name, "Test Playlist");
+    }
+
+    #[tokio::test]
+    async fn test_reorder_playlist_with_duplicate_song() {
+        let db = setup_test_db().await;
+
+        // Create two songs
+        let song_a = Song {
+            id: "song-a".to_string(),
+            url: "/tmp/a.mp3".to_string(),
+            filename: "a.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "A".to_string(),
+                album: "Album".to_string(),
+                duration: 10.0,
+                artists: vec!["Artist".to_string()],
                 genres: vec!["Rock".to_string()],
-                comment: None,
-                tags: vec![],
                 file_exists: true,
-                times_played: 0,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
 
-        let created = db.create_song(song.clone()).await.unwrap();
-        assert_eq!(created.id, song.id);
+        let song_b = Song {
+            id: "song-b".to_string(),
+            url: "/tmp/b.mp3".to_string(),
+            filename: "b.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "B".to_string(),
+                album: "Album".to_string(),
+                duration: 10.0,
+                artists: vec!["Artist".to_string()],
+                genres: vec!["Rock".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+
+        db.create_song(song_a.clone()).await.unwrap();
+        db.create_song(song_b.clone()).await.unwrap();
+
+        // Create playlist
+        let playlist = Playlist {
+            id: "pl-dup".to_string(),
+            name: "Dup".to_string(),
+            tags: vec![],
+            total_duration: 0.0,
+        };
+        db.create_playlist(playlist).await.unwrap();
+
+        // Add A, B, A (duplicate)
+        db.add_song_to_playlist("ps-1", "pl-dup", "song-a", 0)
+            .await
+            .unwrap();
+        db.add_song_to_playlist("ps-2", "pl-dup", "song-b", 1)
+            .await
+            .unwrap();
+        db.add_song_to_playlist("ps-3", "pl-dup", "song-a", 2)
+            .await
+            .unwrap();
+
+        // Reorder to A, A, B
+        let ok = db
+            .reorder_playlist_songs(
+                "pl-dup",
+                &vec!["song-a".to_string(), "song-a".to_string(), "song-b".to_string()],
+            )
+            .await
+            .unwrap();
+        assert!(ok);
+
+        let rows: Vec<(String, i32)> = sqlx::query_as(
+            "SELECT song_id, position FROM playlist_songs WHERE playlist_id = ? ORDER BY position",
+        )
+        .bind("pl-dup")
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].0, "song-a");
+        assert_eq!(rows[1].0, "song-a");
+        assert_eq!(rows[2].0, "song-b");
+        assert_eq!(rows[0].1, 0);
+        assert_eq!(rows[1].1, 1);
+        assert_eq!(rows[2].1, 2);
 
-        let retrieved = db.get_song_by_id(&song.id).await.unwrap();
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap().metadata.title, "Test Song");
+        // And the joined query returns duplicates in correct order
+        let songs = db.get_playlist_songs("pl-dup", None).await.unwrap();
+        let ids: Vec<String> = songs.into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["song-a", "song-a", "song-b"]);
     }
 
     #[tokio::test]
-    async fn test_get_song_by_url_found() {
+    async fn test_create_and_get_markers() {
         let db = setup_test_db().await;
 
+        // First create a song
         let song = Song {
-            id: "test-song-url".to_string(),
-            url: "/path/to/test-url.mp3".to_string(),
-            filename: "test-url.mp3".to_string(),
+            id: "song-with-markers".to_string(),
+            url: "/path/test.mp3".to_string(),
+            filename: "test.mp3".to_string(),
             metadata: SongMetadata {
-                title: "URL Song".to_string(),
-                album: "Test Album".to_string(),
-                year: Some(2023),
-                track: Some(1),
-                image: None,
-                duration: 180.0,
-                artists: vec!["Test Artist".to_string()],
-                instruments: None,
-                bpm: Some(120.0),
-                genres: vec!["Rock".to_string()],
-                comment: None,
-                tags: vec![],
+                title: "Song".to_string(),
+                album: "Album".to_string(),
+                duration: 300.0,
                 file_exists: true,
-                times_played: 0,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
+        db.create_song(song).await.unwrap();
 
-        db.create_song(song.clone()).await.unwrap();
+        // Create markers
+        let marker = Marker {
+            id: "marker-1".to_string(),
+            song: "song-with-markers".to_string(),
+            start: 30.0,
+            end: Some(60.0),
+            comment: Some("Chorus".to_string()),
+            color: Some("#FF0000".to_string()),
+            ..Marker::default()
+        };
 
-        let retrieved = db.get_song_by_url(&song.url).await.unwrap();
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap().id, song.id);
-    }
+        let created = db.create_marker(marker).await.unwrap();
+        assert_eq!(created.start, 30.0);
 
-    #[tokio::test]
-    async fn test_get_song_by_url_not_found() {
-        let db = setup_test_db().await;
+        let markers = db.get_markers("song-with-markers").await.unwrap();
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].comment, Some("Chorus".to_string()));
 
-        let retrieved = db.get_song_by_url("/path/to/missing.mp3").await.unwrap();
-        assert!(retrieved.is_none());
+        // New markers go last; reordering moves them.
+        let cue = db
+            .create_marker(Marker {
+                id: "marker-2".to_string(),
+                song: "song-with-markers".to_string(),
+                start: 5.0,
+                kind: MarkerKind::Section,
+                section: Some(SectionLabel::Intro),
+                ..Marker::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(cue.position, 1);
+        let reordered = db
+            .reorder_markers("song-with-markers", &["marker-2".to_string()])
+            .await
+            .unwrap();
+        let ids: Vec<&str> = reordered.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["marker-2", "marker-1"]);
+        let markers = db.get_markers("song-with-markers").await.unwrap();
+        assert_eq!(markers[0].section, Some(SectionLabel::Intro));
+        assert_eq!(markers[1].position, 1);
+
+        // Songs by the markers they have, or lack.
+        let count = |filters: serde_json::Value| {
+            let db = &db;
+            async move {
+                let query = GetSongsQuery {
+                    filters: Some(filters),
+                    sort: None,
+                    limit: None,
+                    offset: None,
+                };
+                db.get_songs(query).await.unwrap().total
+            }
+        };
+        assert_eq!(count(serde_json::json!({ "markers": "section" })).await, 1);
+        assert_eq!(count(serde_json::json!({ "markers": "loop" })).await, 0);
+        assert_eq!(count(serde_json::json!({ "noMarker": "loop" })).await, 1);
+        assert_eq!(count(serde_json::json!({ "noMarker": "cue" })).await, 0);
+        assert_eq!(count(serde_json::json!({ "markers": false })).await, 0);
+
+        assert!(db.delete_marker("marker-1").await.unwrap());
+        assert!(db.get_marker("marker-1").await.unwrap().is_none());
     }
 
     #[tokio::test]
-    async fn test_get_songs_with_query() {
+    async fn test_get_songs_with_limit() {
         let db = setup_test_db().await;
 
-        // Create test songs
-        for i in 1..=3 {
+        // Create 5 songs
+        for i in 1..=5 {
             let song = Song {
-                id: format!("song-{}", i),
-                url: format!("/path/song{}.mp3", i),
-                filename: format!("song{}.mp3", i),
+                id: format!("limit-song-{}", i),
+                url: format!("/path/{}.mp3", i),
+                filename: format!("{}.mp3", i),
                 metadata: SongMetadata {
                     title: format!("Song {}", i),
                     album: "Album".to_string(),
-                    year: Some(2023),
-                    track: Some(i),
-                    image: None,
-                    duration: 180.0 * i as f64,
-                    artists: vec!["Artist".to_string()],
-                    instruments: None,
-                    bpm: Some(120.0),
-                    genres: vec!["Rock".to_string()],
-                    comment: None,
-                    tags: vec![],
+                    duration: 180.0,
                     file_exists: true,
-                    times_played: 0,
+                    ..Default::default()
                 },
                 available: true,
+                range: None,
             };
             db.create_song(song).await.unwrap();
         }
 
         let query = GetSongsQuery {
             filters: None,
-            sort: Some("title".to_string()),
-            limit: Some(10),
+            sort: None,
+            limit: Some(3),
             offset: None,
         };
 
         let result = db.get_songs(query).await.unwrap();
-        assert_eq!(result.total, 3);
         assert_eq!(result.songs.len(), 3);
+        assert_eq!(result.total, 5);
     }
 
     #[tokio::test]
-    async fn test_get_songs_filter_by_genre() {
+    async fn test_get_songs_with_offset() {
         let db = setup_test_db().await;
 
-        let rock_song = Song {
-            id: "song-rock".to_string(),
-            url: "/path/rock.mp3".to_string(),
-            filename: "rock.mp3".to_string(),
-            metadata: SongMetadata {
-                title: "Rock Song".to_string(),
-                album: "Album".to_string(),
-                year: Some(2023),
-                track: Some(1),
-                image: None,
-                duration: 180.0,
-                artists: vec!["Artist".to_string()],
-                instruments: None,
-                bpm: Some(120.0),
-                genres: vec!["Rock".to_string()],
-                comment: None,
-                tags: vec![],
-                file_exists: true,
-                times_played: 0,
-            },
-            available: true,
-        };
-        let pop_song = Song {
-            id: "song-pop".to_string(),
-            url: "/path/pop.mp3".to_string(),
-            filename: "pop.mp3".to_string(),
-            metadata: SongMetadata {
-                title: "Pop Song".to_string(),
-                album: "Album".to_string(),
-                year: Some(2023),
-                track: Some(2),
-                image: None,
-                duration: 200.0,
-                artists: vec!["Artist".to_string()],
-                instruments: None,
-                bpm: Some(110.0),
-                genres: vec!["Pop".to_string()],
-                comment: None,
-                tags: vec![],
-                file_exists: true,
-                times_played: 0,
-            },
-            available: true,
-        };
-
-        db.create_song(rock_song).await.unwrap();
-        db.create_song(pop_song).await.unwrap();
+        for i in 1..=5 {
+            let song = Song {
+                id: format!("offset-song-{}", i),
+                url: format!("/path/{}.mp3", i),
+                filename: format!("{}.mp3", i),
+                metadata: SongMetadata {
+                    title: format!("Song {}", i),
+                    album: "Album".to_string(),
+                    duration: 180.0,
+                    file_exists: true,
+                    ..Default::default()
+                },
+                available: true,
+                range: None,
+            };
+            db.create_song(song).await.unwrap();
+        }
 
         let query = GetSongsQuery {
-            filters: Some(serde_json::json!({ "genre": "Rock" })),
+            filters: None,
             sort: None,
-            limit: None,
-            offset: None,
+            limit: Some(2),
+            offset: Some(2),
         };
 
         let result = db.get_songs(query).await.unwrap();
-        assert_eq!(result.total, 1);
-        assert_eq!(result.songs.len(), 1);
-        assert_eq!(result.songs[0].metadata.genres, vec!["Rock".to_string()]);
+        assert_eq!(result.songs.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_song_groups_genre_then_year() {
+        let db = setup_test_db().await;
+
+        let songs = vec![
+            Song {
+                id: "g1".to_string(),
+                url: "/path/g1.mp3".to_string(),
+                filename: "g1.mp3".to_string(),
+                metadata: SongMetadata {
+                    title: "G1".to_string(),
+                    album: "A".to_string(),
+                    year: Some(2020),
+                    duration: 1.0,
+                    artists: vec!["Artist1".to_string()],
+                    bpm: Some(128.0),
+                    genres: vec!["Rock".to_string(), "Pop".to_string()],
+                    file_exists: true,
+                    ..Default::default()
+                },
+                available: true,
+                range: None,
+            },
+            Song {
+                id: "g2".to_string(),
+                url: "/path/g2.mp3".to_string(),
+                filename: "g2.mp3".to_string(),
+                metadata: SongMetadata {
+                    title: "G2".to_string(),
+                    album: "A".to_string(),
+                    year: Some(2021),
+                    duration: 1.0,
+                    artists: vec!["Artist2".to_string()],
+                    bpm: Some(95.0),
+                    genres: vec!["Rock".to_string()],
+                    file_exists: true,
+                    ..Default::default()
+                },
+                available: true,
+                range: None,
+            },
+            Song {
+                id: "g3".to_string(),
+                url: "/path/g3.mp3".to_string(),
+                filename: "g3.mp3".to_string(),
+                metadata: SongMetadata {
+                    title: "G3".to_string(),
+                    album: "B".to_string(),
+                    year: Some(2021),
+                    duration: 1.0,
+                    artists: vec!["Artist2".to_string(), "Artist3".to_string()],
+                    genres: vec!["Jazz".to_string()],
+                    file_exists: true,
+                    ..Default::default()
+                },
+                available: true,
+                range: None,
+            },
+        ];
+
+        for song in songs {
+            db.create_song(song).await.unwrap();
+        }
+
+        let result = db
+            .get_song_groups(GetSongsGroupsQuery {
+                groups: vec![
+                    SongGroupRequestItem {
+                        name: "genre".to_string(),
+                        selected: serde_json::json!("Rock"),
+                        sort_by: SongGroupSortBy::ValueAsec,
+                        level: None,
+                    },
+                    SongGroupRequestItem {
+                        name: "year".to_string(),
+                        selected: serde_json::Value::Null,
+                        sort_by: SongGroupSortBy::ValueAsec,
+                        level: None,
+                    },
+                ],
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result.groups.len(), 2);
+
+        // Group 0: genre counts across all songs
+        let genre_group = &result.groups[0];
+        let mut genre_counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+        for item in &genre_group.items {
+            genre_counts.insert(item.name.clone(), item.count);
+        }
+        assert_eq!(genre_counts.get("Rock"), Some(&2));
+        assert_eq!(genre_counts.get("Pop"), Some(&1));
+        assert_eq!(genre_counts.get("Jazz"), Some(&1));
+
+        // Group 1: year counts restricted to selected genre=Rock
+        let year_group = &result.groups[1];
+        let mut year_counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+        for item in &year_group.items {
+            year_counts.insert(item.name.clone(), item.count);
+        }
+        assert_eq!(year_counts.get("2020"), Some(&1));
+        assert_eq!(year_counts.get("2021"), Some(&1));
     }
 
     #[tokio::test]
-    async fn test_update_song() {
+    async fn test_albums_keyed_by_album_artist_with_compilations() {
         let db = setup_test_db().await;
 
-        let song = Song {
-            id: "update-test".to_string(),
-            url: "/path/test.mp3".to_string(),
-            filename: "test.mp3".to_string(),
+        let make_song = |id: &str, album: &str, artist: &str, compilation: bool| Song {
+            id: id.to_string(),
+            url: format!("/path/{}.mp3", id),
+            filename: format!("{}.mp3", id),
             metadata: SongMetadata {
-                title: "Original".to_string(),
-                album: "Album".to_string(),
-                year: Some(2023),
-                track: Some(1),
-                image: None,
+                title: id.to_string(),
+                album: album.to_string(),
+                year: Some(2000),
                 duration: 180.0,
-                artists: vec!["Artist".to_string()],
-                instruments: None,
-                bpm: Some(120.0),
-                genres: vec![],
-                comment: None,
-                tags: vec![],
+                artists: vec![artist.to_string()],
                 file_exists: true,
-                times_played: 0,
+                disc: Some(1),
+                disc_total: Some(2),
+                compilation,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
 
-        db.create_song(song).await.unwrap();
+        let queen = db
+            .create_song(make_song("q1", "Greatest Hits", "Queen", false))
+            .await
+            .unwrap();
+        db.create_song(make_song("q2", "Greatest Hits", "Queen", false))
+            .await
+            .unwrap();
+        let abba = db
+            .create_song(make_song("a1", "Greatest Hits", "ABBA", false))
+            .await
+            .unwrap();
+        let mix = db
+            .create_song(make_song("m1", "Now 42", "Blur", true))
+            .await
+            .unwrap();
+        db.create_song(make_song("m2", "Now 42", "Oasis", true))
+            .await
+            .unwrap();
 
-        let update_payload = UpdateSongPayload {
-            id: "update-test".to_string(),
-            metadata: serde_json::json!({
-                "title": "Updated",
-                "year": 2024,
-                "bpm": 128.5,
-                "genre": "Rock",
-                "comment": "hello"
-            }),
-            update_id3: None,
-            filename: None,
-        };
+        assert_ne!(queen.metadata.album_id, abba.metadata.album_id);
 
-        let updated = db.update_song("update-test", update_payload).await.unwrap();
-        assert!(updated.is_some());
+        let albums = db.get_albums().await.unwrap();
+        assert_eq!(albums.len(), 3);
 
-        let updated = updated.unwrap();
-        assert_eq!(updated.metadata.title, "Updated");
-        assert_eq!(updated.metadata.year, Some(2024));
-        assert_eq!(updated.metadata.genres, vec!["Rock".to_string()]);
-        assert_eq!(updated.metadata.comment, Some("hello".to_string()));
-        assert!(updated
-            .metadata
-            .bpm
-            .map(|b| (b - 128.5).abs() < 0.0001)
-            .unwrap_or(false));
+        let compilation = db
+            .get_album_by_id(mix.metadata.album_id.as_deref().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(compilation.album_artist, "Various Artists");
+        assert!(compilation.compilation);
+        assert_eq!(compilation.track_count, Some(2));
+        assert_eq!(compilation.disc_count, Some(2));
+
+        let result = db
+            .get_song_groups(GetSongsGroupsQuery {
+                groups: vec![
+                    SongGroupRequestItem {
+                        name: "album".to_string(),
+                        selected: serde_json::json!(queen.metadata.album_id),
+                        sort_by: SongGroupSortBy::ValueAsec,
+                        level: None,
+                    },
+                    SongGroupRequestItem {
+                        name: "year".to_string(),
+                        selected: serde_json::Value::Null,
+                        sort_by: SongGroupSortBy::ValueAsec,
+                        level: None,
+                    },
+                ],
+            })
+            .await
+            .unwrap();
+
+        let hits: Vec<&SongGroupItemCount> = result.groups[0]
+            .items
+            .iter()
+            .filter(|i| i.name == "Greatest Hits")
+            .collect();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(result.groups[1].items.len(), 1);
+        assert_eq!(result.groups[1].items[0].count, 2);
+
+        // Retagging the album artist moves the song and drops the emptied album.
+        let updated = db
+            .update_song(
+                "a1",
+                UpdateSongPayload {
+                    id: "a1".to_string(),
+                    metadata: serde_json::json!({ "album_artist": "Queen" }),
+                    update_id3: None,
+                    filename: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.metadata.album_id, queen.metadata.album_id);
+        assert_eq!(db.get_albums().await.unwrap().len(), 2);
+
+        let songs = db
+            .get_songs(GetSongsQuery {
+                filters: Some(serde_json::json!({ "album_id": queen.metadata.album_id })),
+                sort: None,
+                limit: None,
+                offset: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(songs.total, 3);
     }
 
     #[tokio::test]
-    async fn test_delete_song() {
+    async fn test_merge_artists_rewrites_songs_and_adds_alias() {
         let db = setup_test_db().await;
 
-        let song = Song {
-            id: "delete-test".to_string(),
-            url: "/path/test.mp3".to_string(),
-            filename: "test.mp3".to_string(),
+        let make_song = |id: &str, artists: Vec<&str>| Song {
+            id: id.to_string(),
+            url: format!("/path/{}.mp3", id),
+            filename: format!("{}.mp3", id),
             metadata: SongMetadata {
-                title: "Delete Me".to_string(),
+                title: id.to_string(),
                 album: "Album".to_string(),
-                year: None,
-                track: None,
-                image: None,
-                duration: 120.0,
-                artists: vec![],
-                instruments: None,
-                bpm: None,
-                genres: vec![],
-                comment: None,
-                tags: vec![],
+                duration: 180.0,
+                artists: artists.into_iter().map(String::from).collect(),
                 file_exists: true,
-                times_played: 0,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
 
-        db.create_song(song).await.unwrap();
-        let deleted = db.delete_song("delete-test").await.unwrap();
-        assert!(deleted);
+        let song = db
+            .create_song(make_song("s1", vec!["Beyoncé feat. Jay-Z"]))
+            .await
+            .unwrap();
+        assert_eq!(song.metadata.artists, vec!["Beyoncé", "Jay-Z"]);
 
-        let retrieved = db.get_song_by_id("delete-test").await.unwrap();
-        assert!(retrieved.is_none());
+        db.create_song(make_song("s2", vec!["Beyonce"])).await.unwrap();
+
+        let (artist, songs) = db
+            .merge_artists("Beyoncé", &["Beyonce".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(artist.name, "Beyoncé");
+        assert_eq!(artist.aliases, vec!["Beyonce"]);
+        assert_eq!(artist.song_count, 2);
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].metadata.artists, vec!["Beyoncé"]);
+
+        let names: Vec<String> = db
+            .get_artists()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, vec!["Beyoncé", "Jay-Z"]);
+
+        // New songs pick up the alias.
+        let song = db
+            .create_song(make_song("s3", vec!["beyonce & Jay-Z"]))
+            .await
+            .unwrap();
+        assert_eq!(song.metadata.artists, vec!["Beyoncé", "Jay-Z"]);
     }
 
     #[tokio::test]
-    async fn test_create_and_get_playlist() {
+    async fn test_artist_seed_leaves_credits_to_split() {
         let db = setup_test_db().await;
+        db.create_song(Song {
+            id: "s1".to_string(),
+            url: "/path/s1.mp3".to_string(),
+            filename: "s1.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "s1".to_string(),
+                album: "Album".to_string(),
+                duration: 180.0,
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        })
+        .await
+        .unwrap();
 
-        let playlist = Playlist {
-            id: "playlist-1".to_string(),
-            name: "Test Playlist".to_string(),
-            tags: vec!["chill".to_string()],
-            total_duration: 1200.0,
-        };
-
-        let created = db.create_playlist(playlist.clone()).await.unwrap();
-        assert_eq!(created.name, "Test Playlist");
-
-        let query = GetPlaylistsQuery {
-            filters: None,
-            sort: None,
-        };
+        // A library from before the artist registry, seeded the way the migration does it.
+        sqlx::query("UPDATE songs SET artists = ? WHERE id = 's1'")
+            .bind(r#"["Beyoncé feat. Jay-Z","Malcolm X","The Beatles"]"#)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM artists").execute(&db.pool).await.unwrap();
+        sqlx::raw_sql(include_str!("../migrations/20261018030000_artists.sql"))
+            .execute(&db.pool)
+            .await
+            .unwrap();
 
-        let playlists = db.get_playlists(query).await.unwrap();
-        assert_eq!(playlists.len(), 1);
-        assert_eq!(playlists[0].name, "Test Playlist");
+        let artists = db.get_artists().await.unwrap();
+        let names: Vec<(&str, &str)> = artists
+            .iter()
+            .map(|a| (a.name.as_str(), a.sort_name.as_str()))
+            .collect();
+        assert_eq!(names, vec![("The Beatles", "Beatles, The"), ("Malcolm X", "Malcolm X")]);
+
+        let songs = db.normalize_song_artists().await.unwrap();
+        assert_eq!(
+            songs[0].metadata.artists,
+            vec!["Beyoncé", "Jay-Z", "Malcolm X", "The Beatles"]
+        );
     }
 
     #[tokio::test]
-    async fn test_reorder_playlist_with_duplicate_song() {
+    async fn test_genre_taxonomy_filters_and_rolls_up() {
         let db = setup_test_db().await;
 
-        // Create two songs
-        let song_a = Song {
-            id: "song-a".to_string(),
-            url: "/tmp/a.mp3".to_string(),
-            filename: "a.mp3".to_string(),
+        let make_song = |id: &str, genres: Vec<&str>| Song {
+            id: id.to_string(),
+            url: format!("/path/{}.mp3", id),
+            filename: format!("{}.mp3", id),
             metadata: SongMetadata {
-                title: "A".to_string(),
+                title: id.to_string(),
                 album: "Album".to_string(),
-                year: None,
-                track: None,
-                image: None,
-                duration: 10.0,
+                duration: 180.0,
                 artists: vec!["Artist".to_string()],
-                instruments: None,
-                bpm: None,
-                genres: vec!["Rock".to_string()],
-                comment: None,
-                tags: vec![],
+                genres: genres.into_iter().map(String::from).collect(),
                 file_exists: true,
-                times_played: 0,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
 
-        let song_b = Song {
-            id: "song-b".to_string(),
-            url: "/tmp/b.mp3".to_string(),
-            filename: "b.mp3".to_string(),
-            metadata: SongMetadata {
-                title: "B".to_string(),
-                album: "Album".to_string(),
-                year: None,
-                track: None,
-                image: None,
-                duration: 10.0,
-                artists: vec!["Artist".to_string()],
-                instruments: None,
-                bpm: None,
-                genres: vec!["Rock".to_string()],
-                comment: None,
-                tags: vec![],
-                file_exists: true,
-                times_played: 0,
-            },
-            available: true,
+        let song = db.create_song(make_song("s1", vec!["deep house"])).await.unwrap();
+        assert_eq!(song.metadata.genres, vec!["Deep House"]);
+        db.create_song(make_song("s2", vec!["House"])).await.unwrap();
+        db.create_song(make_song("s3", vec!["techno"])).await.unwrap();
+        let song = db.create_song(make_song("s4", vec!["hip-hop; RnB"])).await.unwrap();
+        assert_eq!(song.metadata.genres, vec!["Hip Hop", "R&B"]);
+
+        let count = |genre: &'static str| {
+            let db = &db;
+            async move {
+                db.get_songs(GetSongsQuery {
+                    filters: Some(serde_json::json!({ "genre": genre })),
+                    sort: None,
+                    limit: None,
+                    offset: None,
+                })
+                .await
+                .unwrap()
+                .total
+            }
         };
-
-        db.create_song(song_a.clone()).await.unwrap();
-        db.create_song(song_b.clone()).await.unwrap();
-
-        // Create playlist
-        let playlist = Playlist {
-            id: "pl-dup".to_string(),
-            name: "Dup".to_string(),
-            tags: vec![],
-            total_duration: 0.0,
+        assert_eq!(count("Electronic").await, 3);
+        assert_eq!(count("House").await, 2);
+        assert_eq!(count("Deep House").await, 1);
+
+        let group_counts = |level: u32| {
+            let db = &db;
+            async move {
+                let result = db
+                    .get_song_groups(GetSongsGroupsQuery {
+                        groups: vec![SongGroupRequestItem {
+                            name: "genre".to_string(),
+                            selected: serde_json::Value::Null,
+                            sort_by: SongGroupSortBy::ValueAsec,
+                            level: Some(level),
+                        }],
+                    })
+                    .await
+                    .unwrap();
+                result.groups[0]
+                    .items
+                    .iter()
+                    .map(|i| (i.name.clone(), i.count))
+                    .collect::<Vec<_>>()
+            }
         };
-        db.create_playlist(playlist).await.unwrap();
-
-        // Add A, B, A (duplicate)
-        db.add_song_to_playlist("ps-1", "pl-dup", "song-a", 0)
-            .await
-            .unwrap();
-        db.add_song_to_playlist("ps-2", "pl-dup", "song-b", 1)
-            .await
-            .unwrap();
-        db.add_song_to_playlist("ps-3", "pl-dup", "song-a", 2)
+        assert_eq!(
+            group_counts(0).await,
+            vec![
+                ("Electronic".to_string(), 3),
+                ("Hip Hop".to_string(), 1),
+                ("R&B".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            group_counts(1).await,
+            vec![
+                ("Hip Hop".to_string(), 1),
+                ("House".to_string(), 2),
+                ("R&B".to_string(), 1),
+                ("Techno".to_string(), 1)
+            ]
+        );
+
+        // Folding a stray genre into the taxonomy through an alias.
+        let stray = db.create_song(make_song("s5", vec!["Deep-Tech"])).await.unwrap();
+        assert_eq!(stray.metadata.genres, vec!["Deep-Tech"]);
+        let tech_house = db
+            .get_genres()
             .await
+            .unwrap()
+            .into_iter()
+            .find(|g| g.name == "Tech House")
             .unwrap();
-
-        // Reorder to A, A, B
-        let ok = db
-            .reorder_playlist_songs(
-                "pl-dup",
-                &vec!["song-a".to_string(), "song-a".to_string(), "song-b".to_string()],
-            )
-            .await
-            .unwrap();
-        assert!(ok);
-
-        let rows: Vec<(String, i32)> = sqlx::query_as(
-            "SELECT song_id, position FROM playlist_songs WHERE playlist_id = ? ORDER BY position",
-        )
-        .bind("pl-dup")
-        .fetch_all(&db.pool)
+        db.update_genre(UpdateGenrePayload {
+            id: tech_house.id,
+            aliases: Some(vec!["Deep Tech".to_string()]),
+        })
         .await
         .unwrap();
 
-        assert_eq!(rows.len(), 3);
-        assert_eq!(rows[0].0, "song-a");
-        assert_eq!(rows[1].0, "song-a");
-        assert_eq!(rows[2].0, "song-b");
-        assert_eq!(rows[0].1, 0);
-        assert_eq!(rows[1].1, 1);
-        assert_eq!(rows[2].1, 2);
-
-        // And the joined query returns duplicates in correct order
-        let songs = db.get_playlist_songs("pl-dup", None).await.unwrap();
-        let ids: Vec<String> = songs.into_iter().map(|s| s.id).collect();
-        assert_eq!(ids, vec!["song-a", "song-a", "song-b"]);
+        let changed = db.normalize_song_genres().await.unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].metadata.genres, vec!["Tech House"]);
+        assert!(!db.get_genres().await.unwrap().iter().any(|g| g.name == "Deep-Tech"));
     }
 
-    #[tokio::test]
-    async fn test_create_and_get_markers() {
-        let db = setup_test_db().await;
-
-        // First create a song
-        let song = Song {
-            id: "song-with-markers".to_string(),
-            url: "/path/test.mp3".to_string(),
-            filename: "test.mp3".to_string(),
-            metadata: SongMetadata {
-                title: "Song".to_string(),
-                album: "Album".to_string(),
-                year: None,
-                track: None,
-                image: None,
-                duration: 300.0,
-                artists: vec![],
-                instruments: None,
-                bpm: None,
-                genres: vec![],
-                comment: None,
-                tags: vec![],
+    #[tokio::test]
+    async fn test_song_links_follow_song_writes() {
+        let db = setup_test_db().await;
+
+        let song = Song {
+            id: "s1".to_string(),
+            url: "/path/s1.mp3".to_string(),
+            filename: "s1.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "Song".to_string(),
+                album: "Album".to_string(),
+                duration: 180.0,
+                artists: vec!["A feat. B".to_string()],
+                genres: vec!["Rock".to_string()],
+                tags: vec!["Live".to_string(), "Live".to_string()],
                 file_exists: true,
-                times_played: 0,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
         db.create_song(song).await.unwrap();
 
-        // Create markers
-        let marker = Marker {
-            id: "marker-1".to_string(),
-            song: "song-with-markers".to_string(),
-            start: 30.0,
-            end: Some(60.0),
-            comment: Some("Chorus".to_string()),
-            color: Some("#FF0000".to_string()),
+        let links = |table: &'static str, column: &'static str| {
+            let pool = db.pool.clone();
+            async move {
+                sqlx::query_scalar::<_, String>(&format!(
+                    "SELECT {column} FROM {table} WHERE song_id = 's1' ORDER BY position"
+                ))
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+            }
         };
+        assert_eq!(links("song_artists", "artist").await, vec!["A", "B"]);
+        assert_eq!(links("song_genres", "genre").await, vec!["Rock"]);
+        assert_eq!(links("song_tags", "tag").await, vec!["Live", "Live"]);
+
+        db.update_song(
+            "s1",
+            UpdateSongPayload {
+                id: "s1".to_string(),
+                metadata: serde_json::json!({ "artists": ["C"], "genres": ["Pop"] }),
+                update_id3: None,
+                filename: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(links("song_artists", "artist").await, vec!["C"]);
+        assert_eq!(links("song_genres", "genre").await, vec!["Pop"]);
 
-        let created = db.create_marker(marker).await.unwrap();
-        assert_eq!(created.start, 30.0);
-
-        let markers = db.get_markers("song-with-markers").await.unwrap();
-        assert_eq!(markers.len(), 1);
-        assert_eq!(markers[0].comment, Some("Chorus".to_string()));
+        db.delete_song("s1").await.unwrap();
+        assert!(links("song_artists", "artist").await.is_empty());
+        assert!(links("song_tags", "tag").await.is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_songs_with_limit() {
+    async fn test_edit_song_tags_and_tag_groups() {
         let db = setup_test_db().await;
 
-        // Create 5 songs
-        for i in 1..=5 {
+        for (id, tags) in [("s1", vec!["Chill"]), ("s2", vec!["chill", "Live"]), ("s3", vec![])] {
             let song = Song {
-                id: format!("limit-song-{}", i),
-                url: format!("/path/{}.mp3", i),
-                filename: format!("{}.mp3", i),
+                id: id.to_string(),
+                url: format!("/path/{}.mp3", id),
+                filename: format!("{}.mp3", id),
                 metadata: SongMetadata {
-                    title: format!("Song {}", i),
+                    title: id.to_string(),
                     album: "Album".to_string(),
-                    year: None,
-                    track: None,
-                    image: None,
                     duration: 180.0,
-                    artists: vec![],
-                    instruments: None,
-                    bpm: None,
-                    genres: vec![],
-                    comment: None,
-                    tags: vec![],
+                    artists: vec!["Artist".to_string()],
+                    instruments: Some(vec!["Piano".to_string()]),
+                    tags: tags.into_iter().map(String::from).collect(),
                     file_exists: true,
-                    times_played: 0,
+                    ..Default::default()
                 },
                 available: true,
+                range: None,
             };
             db.create_song(song).await.unwrap();
         }
 
-        let query = GetSongsQuery {
-            filters: None,
-            sort: None,
-            limit: Some(3),
-            offset: None,
-        };
+        let ids: Vec<String> = ["s1", "s2", "s3"].iter().map(|s| s.to_string()).collect();
+        let changed = db
+            .edit_song_tags(&ids, &["Favourite".to_string()], &["CHILL".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(changed.len(), 3);
+        let s2 = db.get_song_by_id("s2").await.unwrap().unwrap();
+        assert_eq!(s2.metadata.tags, vec!["Live", "Favourite"]);
 
-        let result = db.get_songs(query).await.unwrap();
-        assert_eq!(result.songs.len(), 3);
-        assert_eq!(result.total, 5);
+        // Nothing left to change the second time.
+        let changed = db
+            .edit_song_tags(&ids, &["favourite".to_string()], &[])
+            .await
+            .unwrap();
+        assert!(changed.is_empty());
+
+        db.update_song(
+            "s3",
+            UpdateSongPayload {
+                id: "s3".to_string(),
+                metadata: serde_json::json!({ "instruments": ["Piano", " Drums ", ""] }),
+                update_id3: None,
+                filename: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let result = db
+            .get_song_groups(GetSongsGroupsQuery {
+                groups: vec![
+                    SongGroupRequestItem {
+                        name: "instrument".to_string(),
+                        selected: serde_json::json!("Drums"),
+                        sort_by: SongGroupSortBy::ValueAsec,
+                        level: None,
+                    },
+                    SongGroupRequestItem {
+                        name: "tags".to_string(),
+                        selected: serde_json::Value::Null,
+                        sort_by: SongGroupSortBy::ValueAsec,
+                        level: None,
+                    },
+                ],
+            })
+            .await
+            .unwrap();
+        let instruments: Vec<_> = result.groups[0]
+            .items
+            .iter()
+            .map(|i| (i.name.as_str(), i.count))
+            .collect();
+        assert_eq!(instruments, vec![("Drums", 1), ("Piano", 3)]);
+        let tags: Vec<_> = result.groups[1]
+            .items
+            .iter()
+            .map(|i| (i.name.as_str(), i.count))
+            .collect();
+        assert_eq!(tags, vec![("Favourite", 1)]);
+
+        let live = db
+            .get_songs(GetSongsQuery {
+                filters: Some(serde_json::json!({ "tag": "Live" })),
+                sort: None,
+                limit: None,
+                offset: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(live.total, 1);
     }
 
     #[tokio::test]
-    async fn test_get_songs_with_offset() {
+    async fn test_ratings_filter_and_group() {
         let db = setup_test_db().await;
 
-        for i in 1..=5 {
+        for (id, rating) in [("s1", Some(4.5)), ("s2", Some(3.0)), ("s3", None)] {
             let song = Song {
-                id: format!("offset-song-{}", i),
-                url: format!("/path/{}.mp3", i),
-                filename: format!("{}.mp3", i),
+                id: id.to_string(),
+                url: format!("/path/{}.mp3", id),
+                filename: format!("{}.mp3", id),
                 metadata: SongMetadata {
-                    title: format!("Song {}", i),
+                    title: id.to_string(),
                     album: "Album".to_string(),
-                    year: None,
-                    track: None,
-                    image: None,
                     duration: 180.0,
-                    artists: vec![],
-                    instruments: None,
-                    bpm: None,
-                    genres: vec![],
-                    comment: None,
-                    tags: vec![],
+                    artists: vec!["Artist".to_string()],
                     file_exists: true,
-                    times_played: 0,
+                    rating,
+                    ..Default::default()
                 },
                 available: true,
+                range: None,
             };
             db.create_song(song).await.unwrap();
         }
 
-        let query = GetSongsQuery {
-            filters: None,
-            sort: None,
-            limit: Some(2),
-            offset: Some(2),
+        // Ratings are rounded to half stars; null clears them.
+        let update = |id: &'static str, rating: serde_json::Value| {
+            let db = &db;
+            async move {
+                db.update_song(
+                    id,
+                    UpdateSongPayload {
+                        id: id.to_string(),
+                        metadata: serde_json::json!({ "rating": rating }),
+                        update_id3: None,
+                        filename: None,
+                    },
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .metadata
+                .rating
+            }
         };
+        assert_eq!(update("s3", serde_json::json!(3.8)).await, Some(4.0));
+        assert_eq!(update("s2", serde_json::Value::Null).await, None);
+
+        let rated = db
+            .get_songs(GetSongsQuery {
+                filters: Some(serde_json::json!({ "minRating": 4 })),
+                sort: Some("rating DESC".to_string()),
+                limit: None,
+                offset: None,
+            })
+            .await
+            .unwrap();
+        let ids: Vec<&str> = rated.songs.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["s1", "s3"]);
 
-        let result = db.get_songs(query).await.unwrap();
-        assert_eq!(result.songs.len(), 2);
+        let result = db
+            .get_song_groups(GetSongsGroupsQuery {
+                groups: vec![SongGroupRequestItem {
+                    name: "rating".to_string(),
+                    selected: serde_json::Value::Null,
+                    sort_by: SongGroupSortBy::ValueDesc,
+                    level: None,
+                }],
+            })
+            .await
+            .unwrap();
+        let groups: Vec<_> = result.groups[0]
+            .items
+            .iter()
+            .map(|i| (i.name.as_str(), i.count))
+            .collect();
+        assert_eq!(groups, vec![("Unrated", 1), ("4.5", 1), ("4.0", 1)]);
     }
 
     #[tokio::test]
-    async fn test_get_song_groups_genre_then_year() {
+    async fn test_lyrics_are_stored_and_searchable() {
         let db = setup_test_db().await;
 
-        let songs = vec![
-            Song {
-                id: "g1".to_string(),
-                url: "/path/g1.mp3".to_string(),
-                filename: "g1.mp3".to_string(),
+        for id in ["s1", "s2"] {
+            let song = Song {
+                id: id.to_string(),
+                url: format!("/path/{}.mp3", id),
+                filename: format!("{}.mp3", id),
                 metadata: SongMetadata {
-                    title: "G1".to_string(),
-                    album: "A".to_string(),
-                    year: Some(2020),
-                    track: None,
-                    image: None,
-                    duration: 1.0,
-                    artists: vec!["Artist1".to_string()],
-                    instruments: None,
-                    bpm: Some(128.0),
-                    genres: vec!["Rock".to_string(), "Pop".to_string()],
-                    comment: None,
-                    tags: vec![],
+                    title: id.to_string(),
+                    album: "Album".to_string(),
+                    duration: 180.0,
+                    artists: vec!["Artist".to_string()],
                     file_exists: true,
-                    times_played: 0,
+                    ..Default::default()
                 },
                 available: true,
-            },
-            Song {
-                id: "g2".to_string(),
-                url: "/path/g2.mp3".to_string(),
-                filename: "g2.mp3".to_string(),
-                metadata: SongMetadata {
-                    title: "G2".to_string(),
-                    album: "A".to_string(),
-                    year: Some(2021),
-                    track: None,
-                    image: None,
-                    duration: 1.0,
-                    artists: vec!["Artist2".to_string()],
-                    instruments: None,
-                    bpm: Some(95.0),
-                    genres: vec!["Rock".to_string()],
-                    comment: None,
-                    tags: vec![],
-                    file_exists: true,
-                    times_played: 0,
+                range: None,
+            };
+            db.create_song(song).await.unwrap();
+        }
+
+        let synced = Lyrics {
+            song_id: "s1".to_string(),
+            text: "Hello darkness\nMy old friend".to_string(),
+            lines: vec![
+                LyricLine {
+                    time: 1.5,
+                    text: "Hello darkness".to_string(),
                 },
-                available: true,
-            },
-            Song {
-                id: "g3".to_string(),
-                url: "/path/g3.mp3".to_string(),
-                filename: "g3.mp3".to_string(),
-                metadata: SongMetadata {
-                    title: "G3".to_string(),
-                    album: "B".to_string(),
-                    year: Some(2021),
-                    track: None,
-                    image: None,
-                    duration: 1.0,
-                    artists: vec!["Artist2".to_string(), "Artist3".to_string()],
-                    instruments: None,
-                    bpm: None,
-                    genres: vec!["Jazz".to_string()],
-                    comment: None,
-                    tags: vec![],
-                    file_exists: true,
-                    times_played: 0,
+                LyricLine {
+                    time: 4.0,
+                    text: "My old friend".to_string(),
                 },
-                available: true,
-            },
-        ];
+            ],
+            language: Some("eng".to_string()),
+            source: "lrc".to_string(),
+        };
+        db.save_lyrics(&synced).await.unwrap();
+        db.save_lyrics(&Lyrics {
+            song_id: "s2".to_string(),
+            text: "Friends will be friends".to_string(),
+            lines: vec![],
+            language: None,
+            source: "tag".to_string(),
+        })
+        .await
+        .unwrap();
 
-        for song in songs {
-            db.create_song(song).await.unwrap();
-        }
+        let stored = db.get_lyrics("s1").await.unwrap().unwrap();
+        assert_eq!(stored.lines, synced.lines);
+        assert!(db.get_lyrics("s2").await.unwrap().unwrap().lines.is_empty());
 
-        let result = db
-            .get_song_groups(GetSongsGroupsQuery {
-                groups: vec![
-                    SongGroupRequestItem {
-                        name: "genre".to_string(),
-                        selected: serde_json::json!("Rock"),
-                        sort_by: SongGroupSortBy::ValueAsec,
-                    },
-                    SongGroupRequestItem {
-                        name: "year".to_string(),
-                        selected: serde_json::Value::Null,
-                        sort_by: SongGroupSortBy::ValueAsec,
-                    },
-                ],
+        // Words match as prefixes; the index follows edits.
+        let ids = |results: Vec<LyricsSearchResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.song.id).collect()
+        };
+        assert_eq!(ids(db.search_lyrics("friend", 10).await.unwrap()).len(), 2);
+        let results = db.search_lyrics("old friend", 10).await.unwrap();
+        assert_eq!(results[0].snippet, "Hello darkness\nMy [old] [friend]");
+        assert_eq!(ids(results), vec!["s1"]);
+
+        db.save_lyrics(&Lyrics {
+            text: "Silence".to_string(),
+            lines: vec![],
+            source: "user".to_string(),
+            ..synced
+        })
+        .await
+        .unwrap();
+        assert!(db.search_lyrics("darkness", 10).await.unwrap().is_empty());
+        assert_eq!(ids(db.search_lyrics("\"silence", 10).await.unwrap()), vec!["s1"]);
+
+        // Lyrics go with their song.
+        db.delete_song("s2").await.unwrap();
+        assert!(db.get_lyrics("s2").await.unwrap().is_none());
+        assert!(db.search_lyrics("friends", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_extended_tags_are_stored_and_updated() {
+        let db = setup_test_db().await;
+
+        let song = Song {
+            id: "s1".to_string(),
+            url: "/path/s1.flac".to_string(),
+            filename: "s1.flac".to_string(),
+            metadata: SongMetadata {
+                title: "Symphony No. 5".to_string(),
+                album: "Album".to_string(),
+                year: Some(2001),
+                track: None,
+                image: None,
+                duration: 180.0,
+                artists: vec!["Orchestra".to_string()],
+                instruments: None,
+                bpm: None,
+                genres: vec![],
+                comment: None,
+                tags: vec![],
+                file_exists: true,
+                times_played: 0,
+                album_artist: None,
+                disc: None,
+                disc_total: None,
+                track_total: None,
+                compilation: false,
+                album_id: None,
+                rating: None,
+                extended: ExtendedTags {
+                    composer: Some("Ludwig van Beethoven".to_string()),
+                    conductor: Some("Carlos Kleiber".to_string()),
+                    label: Some("DG".to_string()),
+                    original_year: Some(1975),
+                    composer_sort: Some("Beethoven, Ludwig van".to_string()),
+                    ..ExtendedTags::default()
+                },
+            },
+            available: true,
+            range: None,
+        };
+        db.create_song(song.clone()).await.unwrap();
+
+        let stored = db.get_song_by_id("s1").await.unwrap().unwrap();
+        assert_eq!(stored.metadata.extended, song.metadata.extended);
+
+        // Only the fields in the payload change; null clears one.
+        let updated = db
+            .update_song(
+                "s1",
+                UpdateSongPayload {
+                    id: "s1".to_string(),
+                    metadata: serde_json::json!({
+                        "isrc": " DEF123456789 ",
+                        "label": null,
+                        "original_year": 1976,
+                    }),
+                    update_id3: None,
+                    filename: None,
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let extended = &updated.metadata.extended;
+        assert_eq!(extended.isrc.as_deref(), Some("DEF123456789"));
+        assert_eq!(extended.label, None);
+        assert_eq!(extended.original_year, Some(1976));
+        assert_eq!(extended.composer.as_deref(), Some("Ludwig van Beethoven"));
+
+        let by_composer = db
+            .get_songs(GetSongsQuery {
+                filters: Some(serde_json::json!({ "composer": "Ludwig van Beethoven" })),
+                sort: Some("composer_sort".to_string()),
+                limit: None,
+                offset: None,
             })
             .await
             .unwrap();
+        assert_eq!(by_composer.songs.len(), 1);
+    }
 
-        assert_eq!(result.groups.len(), 2);
+    #[tokio::test]
+    async fn test_edit_journal_undo_and_redo() {
+        let db = setup_test_db().await;
+        let song = Song {
+            id: "s1".to_string(),
+            url: "/path/s1.mp3".to_string(),
+            filename: "s1.mp3".to_string(),
+            metadata: SongMetadata {
+                title: "Original".to_string(),
+                album: "Album".to_string(),
+                duration: 180.0,
+                artists: vec!["Artist".to_string()],
+                genres: vec!["Rock".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        };
+        db.create_song(song).await.unwrap();
 
-        // Group 0: genre counts across all songs
-        let genre_group = &result.groups[0];
-        let mut genre_counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
-        for item in &genre_group.items {
-            genre_counts.insert(item.name.clone(), item.count);
-        }
-        assert_eq!(genre_counts.get("Rock"), Some(&2));
-        assert_eq!(genre_counts.get("Pop"), Some(&1));
-        assert_eq!(genre_counts.get("Jazz"), Some(&1));
+        db.update_song(
+            "s1",
+            UpdateSongPayload {
+                id: "s1".to_string(),
+                metadata: serde_json::json!({"genres": ["Jazz"], "year": 2020}),
+                update_id3: Some(true),
+                filename: None,
+            },
+        )
+        .await
+        .unwrap();
 
-        // Group 1: year counts restricted to selected genre=Rock
-        let year_group = &result.groups[1];
-        let mut year_counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
-        for item in &year_group.items {
-            year_counts.insert(item.name.clone(), item.count);
-        }
-        assert_eq!(year_counts.get("2020"), Some(&1));
-        assert_eq!(year_counts.get("2021"), Some(&1));
+        let mut batch = db.begin_song_updates("bulk_update_songs").await.unwrap();
+        batch
+            .update_song(
+                "s1",
+                UpdateSongPayload {
+                    id: "s1".to_string(),
+                    metadata: serde_json::json!({"title": "Bulk"}),
+                    update_id3: None,
+                    filename: None,
+                },
+            )
+            .await
+            .unwrap();
+        batch.keep_song(false).await.unwrap();
+        batch.commit().await.unwrap();
+
+        let playlist = Playlist {
+            id: "p1".to_string(),
+            name: "Mix".to_string(),
+            tags: vec![],
+            total_duration: 0.0,
+        };
+        db.create_playlist(playlist).await.unwrap();
+        let after = db.get_playlist_snapshot("p1").await.unwrap();
+        let change = JournalChange::Playlist {
+            id: "p1".to_string(),
+            before: None,
+            after,
+        };
+        db.record_edit("create_playlist", &[change]).await.unwrap();
+
+        let journal = db.get_edit_journal(10).await.unwrap();
+        let operations: Vec<&str> = journal.iter().map(|e| e.operation.as_str()).collect();
+        assert_eq!(operations, ["create_playlist", "bulk_update_songs", "update_song"]);
+
+        let undone = db.undo_edit().await.unwrap().unwrap();
+        assert_eq!(undone.operation, "create_playlist");
+        assert!(db.get_playlist_snapshot("p1").await.unwrap().is_none());
+
+        db.undo_edit().await.unwrap();
+        assert_eq!(db.get_song_by_id("s1").await.unwrap().unwrap().metadata.title, "Original");
+
+        let undone = db.undo_edit().await.unwrap().unwrap();
+        assert!(matches!(undone.changes[0], JournalChange::Song { tags_written: true, .. }));
+        let restored = db.get_song_by_id("s1").await.unwrap().unwrap();
+        assert_eq!(restored.metadata.genres, vec!["Rock"]);
+        assert_eq!(restored.metadata.year, None);
+        let rock = db
+            .get_songs(GetSongsQuery {
+                filters: Some(serde_json::json!({"genre": "Rock"})),
+                sort: None,
+                limit: None,
+                offset: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(rock.songs.len(), 1);
+        assert!(db.undo_edit().await.unwrap().is_none());
+
+        let redone = db.redo_edit().await.unwrap().unwrap();
+        assert_eq!(redone.operation, "update_song");
+        let redone_song = db.get_song_by_id("s1").await.unwrap().unwrap();
+        assert_eq!(redone_song.metadata.genres, vec!["Jazz"]);
+        assert_eq!(redone_song.metadata.year, Some(2020));
+
+        // A new edit drops what could still be redone.
+        db.update_song(
+            "s1",
+            UpdateSongPayload {
+                id: "s1".to_string(),
+                metadata: serde_json::json!({"comment": "new"}),
+                update_id3: None,
+                filename: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(db.redo_edit().await.unwrap().is_none());
+        assert_eq!(db.get_edit_journal(10).await.unwrap().len(), 2);
     }
 }
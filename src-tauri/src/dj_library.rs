@@ -0,0 +1,736 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::models::{Marker, Song, SongMetadata};
+use crate::playlist_files::{percent_encode, resolve_location};
+
+/// Hot cue slots both Rekordbox and Traktor have.
+pub const HOT_CUES: usize = 8;
+
+/// The DJ software collection formats we read and write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DjFormat {
+    Rekordbox,
+    Traktor,
+}
+
+impl DjFormat {
+    /// The format of a collection file, by extension: Rekordbox `.xml`, Traktor `.nml`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "xml" => Some(DjFormat::Rekordbox),
+            "nml" => Some(DjFormat::Traktor),
+            _ => None,
+        }
+    }
+}
+
+/// A cue point, in seconds from the start of the file. Cues with an end are loops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DjCue {
+    pub start: f64,
+    pub end: Option<f64>,
+    pub name: Option<String>,
+    pub color: Option<String>,  // "#rrggbb"
+    pub hot_cue: Option<usize>, // slot, counting from 0
+}
+
+/// One file of the collection. A file holding several cue sheet tracks is one DJ track.
+#[derive(Debug, Clone)]
+pub struct DjTrack {
+    pub path: String,
+    pub metadata: SongMetadata,
+    pub cues: Vec<DjCue>,
+}
+
+/// A playlist, as indices into the collection's tracks.
+#[derive(Debug, Clone)]
+pub struct DjPlaylist {
+    pub name: String,
+    pub tracks: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DjCollection {
+    pub tracks: Vec<DjTrack>,
+    pub playlists: Vec<DjPlaylist>,
+}
+
+/// The cue points of a file, read from a collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedCues {
+    pub path: String,
+    pub cues: Vec<DjCue>,
+}
+
+/// The collection holding the given playlists. Every marker becomes a memory cue; those with a
+/// hot cue slot keep it, and the first others of each file fill the free slots. Markers of cue
+/// sheet tracks are moved to their position in the file.
+pub fn collection(
+    playlists: &[(String, Vec<Song>)],
+    markers: &HashMap<String, Vec<Marker>>,
+) -> DjCollection {
+    let mut collection = DjCollection::default();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+
+    for (name, songs) in playlists {
+        let mut playlist = DjPlaylist {
+            name: name.clone(),
+            tracks: Vec::new(),
+        };
+        for song in songs {
+            let index = *indices.entry(song.url.as_str()).or_insert_with(|| {
+                collection.tracks.push(DjTrack {
+                    path: song.url.clone(),
+                    metadata: song.metadata.clone(),
+                    cues: Vec::new(),
+                });
+                collection.tracks.len() - 1
+            });
+
+            if seen.insert(&song.id) {
+                let offset = song.range.as_ref().map_or(0.0, |r| r.start);
+                let cues = markers.get(&song.id).into_iter().flatten().map(|m| DjCue {
+                    start: m.start + offset,
+                    end: m.end.map(|end| end + offset),
+                    name: m.comment.clone(),
+                    color: m
+                        .color
+                        .as_deref()
+                        .and_then(rgb)
+                        .map(|(r, g, b)| hex(r, g, b)),
+                    hot_cue: m.hot_cue.map(usize::from),
+                });
+                collection.tracks[index].cues.extend(cues);
+            }
+            // Cue sheet tracks of one file share its entry.
+            if playlist.tracks.last() != Some(&index) || song.range.is_none() {
+                playlist.tracks.push(index);
+            }
+        }
+        collection.playlists.push(playlist);
+    }
+
+    for track in &mut collection.tracks {
+        track.cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+        let taken: Vec<usize> = track.cues.iter().filter_map(|c| c.hot_cue).collect();
+        let mut free = (0..HOT_CUES).filter(|slot| !taken.contains(slot));
+        for cue in track.cues.iter_mut().filter(|c| c.hot_cue.is_none()) {
+            cue.hot_cue = free.next();
+        }
+    }
+    collection
+}
+
+pub fn render(format: DjFormat, collection: &DjCollection) -> String {
+    match format {
+        DjFormat::Rekordbox => render_rekordbox(collection),
+        DjFormat::Traktor => render_traktor(collection),
+    }
+}
+
+pub fn parse(format: DjFormat, content: &str) -> Result<Vec<ImportedCues>, String> {
+    match format {
+        DjFormat::Rekordbox => parse_rekordbox(content),
+        DjFormat::Traktor => parse_traktor(content),
+    }
+}
+
+/// Write a Rekordbox XML collection. Hot cues are written both as memory and hot cues, as
+/// Rekordbox shows them in separate lists.
+pub fn render_rekordbox(collection: &DjCollection) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<DJ_PLAYLISTS Version=\"1.0.0\">\n");
+    out.push_str(&format!(
+        "  <PRODUCT Name=\"nagan\" Version=\"{}\" Company=\"\"/>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    out.push_str(&format!(
+        "  <COLLECTION Entries=\"{}\">\n",
+        collection.tracks.len()
+    ));
+    for (i, track) in collection.tracks.iter().enumerate() {
+        let metadata = &track.metadata;
+        let mut attributes = vec![
+            ("TrackID", (i + 1).to_string()),
+            ("Name", metadata.title.clone()),
+            ("Artist", metadata.artists.join(", ")),
+            ("Album", metadata.album.clone()),
+            ("Genre", metadata.genres.join(", ")),
+            ("Kind", kind(&track.path)),
+            ("TotalTime", (metadata.duration.round() as i64).to_string()),
+            (
+                "Location",
+                format!("file://localhost{}", percent_encode(&uri_path(&track.path))),
+            ),
+        ];
+        if let Some(year) = metadata.year {
+            attributes.push(("Year", year.to_string()));
+        }
+        if let Some(bpm) = metadata.bpm {
+            attributes.push(("AverageBpm", format!("{:.2}", bpm)));
+        }
+        if let Some(key) = &metadata.extended.initial_key {
+            attributes.push(("Tonality", key.clone()));
+        }
+        if let Some(comment) = &metadata.comment {
+            attributes.push(("Comments", comment.clone()));
+        }
+
+        if track.cues.is_empty() {
+            out.push_str(&format!("    <TRACK{}/>\n", attributes_text(&attributes)));
+            continue;
+        }
+        out.push_str(&format!("    <TRACK{}>\n", attributes_text(&attributes)));
+        for cue in &track.cues {
+            out.push_str(&rekordbox_mark(cue, None));
+            if let Some(slot) = cue.hot_cue {
+                out.push_str(&rekordbox_mark(cue, Some(slot)));
+            }
+        }
+        out.push_str("    </TRACK>\n");
+    }
+    out.push_str("  </COLLECTION>\n  <PLAYLISTS>\n");
+    out.push_str(&format!(
+        "    <NODE Type=\"0\" Name=\"ROOT\" Count=\"{}\">\n",
+        collection.playlists.len()
+    ));
+    for playlist in &collection.playlists {
+        out.push_str(&format!(
+            "      <NODE Name=\"{}\" Type=\"1\" KeyType=\"0\" Entries=\"{}\">\n",
+            escape(&playlist.name),
+            playlist.tracks.len()
+        ));
+        for index in &playlist.tracks {
+            out.push_str(&format!("        <TRACK Key=\"{}\"/>\n", index + 1));
+        }
+        out.push_str("      </NODE>\n");
+    }
+    out.push_str("    </NODE>\n  </PLAYLISTS>\n</DJ_PLAYLISTS>\n");
+    out
+}
+
+fn rekordbox_mark(cue: &DjCue, hot_cue: Option<usize>) -> String {
+    let mut attributes = vec![
+        ("Name", cue.name.clone().unwrap_or_default()),
+        (
+            "Type",
+            if cue.end.is_some() { "4" } else { "0" }.to_string(),
+        ),
+        ("Start", format!("{:.3}", cue.start)),
+    ];
+    if let Some(end) = cue.end {
+        attributes.push(("End", format!("{:.3}", end)));
+    }
+    attributes.push(("Num", hot_cue.map_or(-1, |slot| slot as i64).to_string()));
+    if let Some((r, g, b)) = cue.color.as_deref().and_then(rgb) {
+        attributes.push(("Red", r.to_string()));
+        attributes.push(("Green", g.to_string()));
+        attributes.push(("Blue", b.to_string()));
+    }
+    format!("      <POSITION_MARK{}/>\n", attributes_text(&attributes))
+}
+
+/// Write a Traktor NML collection. Traktor has no cue colors, so they are left out.
+pub fn render_traktor(collection: &DjCollection) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\" ?>\n");
+    out.push_str("<NML VERSION=\"19\"><HEAD COMPANY=\"www.native-instruments.com\" PROGRAM=\"Traktor\"></HEAD>\n");
+    out.push_str(&format!(
+        "<COLLECTION ENTRIES=\"{}\">\n",
+        collection.tracks.len()
+    ));
+    for track in &collection.tracks {
+        let metadata = &track.metadata;
+        let (volume, dir, file) = traktor_location(&track.path);
+        out.push_str(&format!(
+            "<ENTRY{}>\n",
+            attributes_text(&[
+                ("TITLE", metadata.title.clone()),
+                ("ARTIST", metadata.artists.join(", ")),
+            ])
+        ));
+        out.push_str(&format!(
+            "<LOCATION{}></LOCATION>\n",
+            attributes_text(&[("DIR", dir), ("FILE", file), ("VOLUME", volume)])
+        ));
+        out.push_str(&format!(
+            "<ALBUM{}></ALBUM>\n",
+            attributes_text(&[("TITLE", metadata.album.clone())])
+        ));
+        let mut info = vec![
+            ("GENRE", metadata.genres.join(", ")),
+            ("PLAYTIME", (metadata.duration.round() as i64).to_string()),
+        ];
+        if let Some(key) = &metadata.extended.initial_key {
+            info.push(("KEY", key.clone()));
+        }
+        if let Some(comment) = &metadata.comment {
+            info.push(("COMMENT", comment.clone()));
+        }
+        out.push_str(&format!("<INFO{}></INFO>\n", attributes_text(&info)));
+        if let Some(bpm) = metadata.bpm {
+            out.push_str(&format!(
+                "<TEMPO BPM=\"{:.6}\" BPM_QUALITY=\"100.000000\"></TEMPO>\n",
+                bpm
+            ));
+        }
+        for (order, cue) in track.cues.iter().enumerate() {
+            let length = cue.end.map_or(0.0, |end| (end - cue.start).max(0.0));
+            out.push_str(&format!(
+                "<CUE_V2{}></CUE_V2>\n",
+                attributes_text(&[
+                    (
+                        "NAME",
+                        cue.name.clone().unwrap_or_else(|| "n.n.".to_string())
+                    ),
+                    ("DISPL_ORDER", order.to_string()),
+                    (
+                        "TYPE",
+                        if cue.end.is_some() { "5" } else { "0" }.to_string()
+                    ),
+                    ("START", format!("{:.6}", cue.start * 1000.0)),
+                    ("LEN", format!("{:.6}", length * 1000.0)),
+                    ("REPEATS", "-1".to_string()),
+                    (
+                        "HOTCUE",
+                        cue.hot_cue.map_or(-1, |slot| slot as i64).to_string()
+                    ),
+                ])
+            ));
+        }
+        out.push_str("</ENTRY>\n");
+    }
+    out.push_str("</COLLECTION>\n<PLAYLISTS><NODE TYPE=\"FOLDER\" NAME=\"$ROOT\">");
+    out.push_str(&format!(
+        "<SUBNODES COUNT=\"{}\">\n",
+        collection.playlists.len()
+    ));
+    for playlist in &collection.playlists {
+        out.push_str(&format!(
+            "<NODE TYPE=\"PLAYLIST\" NAME=\"{}\"><PLAYLIST ENTRIES=\"{}\" TYPE=\"LIST\">\n",
+            escape(&playlist.name),
+            playlist.tracks.len()
+        ));
+        for index in &playlist.tracks {
+            let (volume, dir, file) = traktor_location(&collection.tracks[*index].path);
+            out.push_str(&format!(
+                "<ENTRY><PRIMARYKEY TYPE=\"TRACK\" KEY=\"{}\"></PRIMARYKEY></ENTRY>\n",
+                escape(format!("{}{}{}", volume, dir, file))
+            ));
+        }
+        out.push_str("</PLAYLIST></NODE>\n");
+    }
+    out.push_str("</SUBNODES></NODE></PLAYLISTS>\n</NML>\n");
+    out
+}
+
+/// Read the cue points of a Rekordbox XML collection. A hot cue that is also a memory cue at the
+/// same position is read once.
+pub fn parse_rekordbox(content: &str) -> Result<Vec<ImportedCues>, String> {
+    let mut reader = Reader::from_str(content);
+    let mut tracks = Vec::new();
+    let mut track: Option<ImportedCues> = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| invalid(&e))?;
+        let (element, empty) = match &event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"TRACK" {
+                    tracks.extend(track.take());
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        match element.local_name().as_ref() {
+            // Playlist entries are TRACK elements too, with only a Key.
+            b"TRACK" => {
+                let attributes = attributes(element)?;
+                let path = attributes
+                    .get("Location")
+                    .and_then(|location| resolve_location(location, Path::new("/")));
+                let imported = path.map(|path| ImportedCues {
+                    path,
+                    cues: Vec::new(),
+                });
+                if empty {
+                    tracks.extend(imported);
+                } else {
+                    track = imported;
+                }
+            }
+            b"POSITION_MARK" => {
+                let Some(track) = track.as_mut() else {
+                    continue;
+                };
+                let attributes = attributes(element)?;
+                let Some(start) = attributes.get("Start").and_then(|s| s.parse::<f64>().ok())
+                else {
+                    continue;
+                };
+                let end = attributes
+                    .get("End")
+                    .and_then(|e| e.parse::<f64>().ok())
+                    .filter(|_| attributes.get("Type").map(String::as_str) == Some("4"));
+                let color = match (
+                    attributes.get("Red"),
+                    attributes.get("Green"),
+                    attributes.get("Blue"),
+                ) {
+                    (Some(r), Some(g), Some(b)) => match (r.parse(), g.parse(), b.parse()) {
+                        (Ok(r), Ok(g), Ok(b)) => Some(hex(r, g, b)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let hot_cue = attributes
+                    .get("Num")
+                    .and_then(|n| n.parse::<i64>().ok())
+                    .and_then(|n| usize::try_from(n).ok());
+                let cue = DjCue {
+                    start,
+                    end,
+                    name: attributes.get("Name").cloned().filter(|n| !n.is_empty()),
+                    color,
+                    hot_cue,
+                };
+                push_cue(&mut track.cues, cue);
+            }
+            _ => {}
+        }
+    }
+    Ok(tracks)
+}
+
+/// Read the cue points of a Traktor NML collection. Beat grid markers are skipped.
+pub fn parse_traktor(content: &str) -> Result<Vec<ImportedCues>, String> {
+    let mut reader = Reader::from_str(content);
+    let mut tracks = Vec::new();
+    let mut in_collection = false;
+    let mut track: Option<ImportedCues> = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| invalid(&e))?;
+        let element = match &event {
+            Event::Start(e) | Event::Empty(e) => e,
+            Event::End(e) => {
+                match e.local_name().as_ref() {
+                    b"ENTRY" => tracks.extend(track.take().filter(|t| !t.path.is_empty())),
+                    b"COLLECTION" => in_collection = false,
+                    _ => {}
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        match element.local_name().as_ref() {
+            b"COLLECTION" => in_collection = true,
+            b"ENTRY" if in_collection => {
+                track = Some(ImportedCues {
+                    path: String::new(),
+                    cues: Vec::new(),
+                });
+            }
+            b"LOCATION" => {
+                let Some(track) = track.as_mut() else {
+                    continue;
+                };
+                let attributes = attributes(element)?;
+                let get = |name: &str| attributes.get(name).map(String::as_str).unwrap_or("");
+                track.path = traktor_path(get("VOLUME"), get("DIR"), get("FILE"));
+            }
+            b"CUE_V2" => {
+                let Some(track) = track.as_mut() else {
+                    continue;
+                };
+                let attributes = attributes(element)?;
+                let number = |name: &str| attributes.get(name).and_then(|v| v.parse::<f64>().ok());
+                let kind = attributes.get("TYPE").map(String::as_str).unwrap_or("0");
+                let Some(start) = number("START").filter(|_| kind != "4") else {
+                    continue;
+                };
+                let length = number("LEN").filter(|l| kind == "5" && *l > 0.0);
+                let cue = DjCue {
+                    start: start / 1000.0,
+                    end: length.map(|l| (start + l) / 1000.0),
+                    name: attributes
+                        .get("NAME")
+                        .cloned()
+                        .filter(|n| !n.is_empty() && n != "n.n."),
+                    color: None,
+                    hot_cue: number("HOTCUE").filter(|n| *n >= 0.0).map(|n| n as usize),
+                };
+                push_cue(&mut track.cues, cue);
+            }
+            _ => {}
+        }
+    }
+    Ok(tracks)
+}
+
+/// Add a cue unless one is already at its position, keeping the hot cue slot of either.
+fn push_cue(cues: &mut Vec<DjCue>, cue: DjCue) {
+    match cues
+        .iter_mut()
+        .find(|c| (c.start - cue.start).abs() < 0.001 && c.end.is_some() == cue.end.is_some())
+    {
+        Some(existing) => {
+            existing.hot_cue = existing.hot_cue.or(cue.hot_cue);
+            existing.name = existing.name.take().or(cue.name);
+            existing.color = existing.color.take().or(cue.color);
+        }
+        None => cues.push(cue),
+    }
+}
+
+fn attributes(element: &BytesStart) -> Result<HashMap<String, String>, String> {
+    element
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|e| invalid(&e))?;
+            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string();
+            let value = attribute.unescape_value().map_err(|e| invalid(&e))?;
+            Ok((key, value.to_string()))
+        })
+        .collect()
+}
+
+fn attributes_text(attributes: &[(&str, String)]) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape(value)))
+        .collect()
+}
+
+fn invalid(e: &dyn std::fmt::Display) -> String {
+    format!("invalidInput: malformed collection file: {}", e)
+}
+
+/// `path` with forward slashes and a leading one, as file URLs have it ("/C:/Music/a.mp3").
+fn uri_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if path.starts_with('/') {
+        path
+    } else {
+        format!("/{}", path)
+    }
+}
+
+fn kind(path: &str) -> String {
+    let extension = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+    format!("{} File", extension)
+}
+
+/// Traktor's volume, directory and file name of a path: "C:", "/:Music/:Set/:", "a.mp3".
+/// Paths without a drive letter get an empty volume.
+fn traktor_location(path: &str) -> (String, String, String) {
+    let path = path.replace('\\', "/");
+    let (volume, rest) = match path.as_bytes().get(1) {
+        Some(b':') => (path[..2].to_string(), &path[2..]),
+        _ => (String::new(), path.as_str()),
+    };
+    let (dir, file) = rest.rsplit_once('/').unwrap_or(("", rest));
+    let dir: String = dir
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(|part| format!("/:{}", part))
+        .collect();
+    (volume, format!("{}/:", dir), file.to_string())
+}
+
+/// The path a Traktor location points to. Volume names other than drive letters (macOS disk
+/// names) stand for the root.
+fn traktor_path(volume: &str, dir: &str, file: &str) -> String {
+    if file.is_empty() {
+        return String::new();
+    }
+    let dir = dir.replace("/:", "/");
+    let dir = dir.trim_end_matches('/');
+    let drive = if volume.len() == 2 && volume.ends_with(':') {
+        volume
+    } else {
+        ""
+    };
+    format!("{}{}/{}", drive, dir, file)
+}
+
+/// The red, green and blue of a CSS hex color ("#rrggbb" or "#rgb").
+pub(crate) fn rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.trim().trim_start_matches('#');
+    let channel = |i: usize, width: usize| {
+        let value = u8::from_str_radix(hex.get(i * width..(i + 1) * width)?, 16).ok()?;
+        Some(if width == 1 { value * 17 } else { value })
+    };
+    let width = match hex.len() {
+        6 => 2,
+        3 => 1,
+        _ => return None,
+    };
+    Some((channel(0, width)?, channel(1, width)?, channel(2, width)?))
+}
+
+pub(crate) fn hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ExtendedTags, TrackRange};
+
+    fn song(id: &str, url: &str, range: Option<TrackRange>) -> Song {
+        Song {
+            id: id.to_string(),
+            url: url.to_string(),
+            filename: id.to_string(),
+            metadata: SongMetadata {
+                title: format!("Title {}", id),
+                album: "Album".to_string(),
+                duration: 300.0,
+                artists: vec!["Band & Co".to_string()],
+                bpm: Some(124.0),
+                genres: vec!["House".to_string()],
+                file_exists: true,
+                extended: ExtendedTags {
+                    initial_key: Some("8A".to_string()),
+                    ..ExtendedTags::default()
+                },
+                ..Default::default()
+            },
+            available: true,
+            range,
+        }
+    }
+
+    fn marker(
+        song: &str,
+        start: f64,
+        end: Option<f64>,
+        comment: &str,
+        color: Option<&str>,
+    ) -> Marker {
+        Marker {
+            id: format!("{}-{}", song, start),
+            song: song.to_string(),
+            start,
+            end,
+            comment: Some(comment.to_string()),
+            color: color.map(str::to_string),
+            ..Marker::default()
+        }
+    }
+
+    fn sample() -> DjCollection {
+        let songs = vec![
+            song("a", "/music/My Track.mp3", None),
+            song(
+                "one",
+                "/music/Mix.flac",
+                Some(TrackRange {
+                    start: 0.0,
+                    end: Some(200.0),
+                }),
+            ),
+            song(
+                "two",
+                "/music/Mix.flac",
+                Some(TrackRange {
+                    start: 200.0,
+                    end: None,
+                }),
+            ),
+        ];
+        let mut markers = HashMap::new();
+        markers.insert(
+            "a".to_string(),
+            vec![
+                marker("a", 64.0, None, "Drop", Some("#f00")),
+                marker("a", 16.0, Some(24.0), "Intro loop", None),
+            ],
+        );
+        markers.insert(
+            "two".to_string(),
+            vec![marker("two", 10.0, None, "Break", None)],
+        );
+        collection(&[("Friday <Set>".to_string(), songs)], &markers)
+    }
+
+    #[test]
+    fn test_collection_groups_files_and_assigns_hot_cues() {
+        let collection = sample();
+        assert_eq!(collection.tracks.len(), 2);
+        assert_eq!(collection.playlists[0].tracks, [0, 1]);
+
+        let cues = &collection.tracks[0].cues;
+        assert_eq!(cues[0].start, 16.0);
+        assert_eq!(cues[0].hot_cue, Some(0));
+        assert_eq!(cues[1].color.as_deref(), Some("#ff0000"));
+        // The cue sheet track's marker is at its place in the file.
+        assert_eq!(collection.tracks[1].cues[0].start, 210.0);
+    }
+
+    #[test]
+    fn test_rekordbox_round_trip() {
+        let collection = sample();
+        let content = render_rekordbox(&collection);
+        assert!(content.contains("Location=\"file://localhost/music/My%20Track.mp3\""));
+        assert!(content.contains("AverageBpm=\"124.00\""));
+        assert!(content.contains("Tonality=\"8A\""));
+        assert!(content.contains("Artist=\"Band &amp; Co\""));
+        assert!(content.contains("Name=\"Friday &lt;Set&gt;\""));
+        assert!(content.contains(
+            "<POSITION_MARK Name=\"Drop\" Type=\"0\" Start=\"64.000\" Num=\"1\" Red=\"255\" Green=\"0\" Blue=\"0\"/>"
+        ));
+
+        let imported = parse_rekordbox(&content).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].path, "/music/My Track.mp3");
+        assert_eq!(imported[0].cues, collection.tracks[0].cues);
+        assert_eq!(imported[1].cues[0].start, 210.0);
+    }
+
+    #[test]
+    fn test_traktor_round_trip() {
+        let collection = sample();
+        let content = render_traktor(&collection);
+        assert!(content.contains("DIR=\"/:music/:\" FILE=\"My Track.mp3\" VOLUME=\"\""));
+        assert!(content.contains("KEY=\"/:music/:Mix.flac\""));
+        assert!(content.contains("BPM=\"124.000000\""));
+
+        let imported = parse_traktor(&content).unwrap();
+        assert_eq!(imported[0].path, "/music/My Track.mp3");
+        let loop_cue = &imported[0].cues[0];
+        assert_eq!((loop_cue.start, loop_cue.end), (16.0, Some(24.0)));
+        assert_eq!(loop_cue.name.as_deref(), Some("Intro loop"));
+        assert_eq!(imported[0].cues[1].hot_cue, Some(1));
+        // Traktor has no cue colors.
+        assert_eq!(imported[0].cues[1].color, None);
+
+        assert_eq!(
+            traktor_location("C:\\Music\\a.mp3"),
+            (
+                "C:".to_string(),
+                "/:Music/:".to_string(),
+                "a.mp3".to_string()
+            )
+        );
+        assert_eq!(traktor_path("C:", "/:Music/:", "a.mp3"), "C:/Music/a.mp3");
+        assert_eq!(
+            traktor_path("Macintosh HD", "/:Users/:me/:", "a.mp3"),
+            "/Users/me/a.mp3"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use serde_json::{Map, Value};
+
+use crate::models::{DriftResolution, FieldDrift, SongMetadata};
+use crate::original_tags::tag_fields;
+
+/// Tag fields left out of drift checks. The library may hold sidecar artwork that was never in
+/// the file.
+const IGNORED_FIELDS: [&str; 1] = ["image"];
+
+fn drift_fields(metadata: &SongMetadata) -> Map<String, Value> {
+    let mut fields = tag_fields(metadata);
+    for field in IGNORED_FIELDS {
+        fields.remove(field);
+    }
+    fields
+}
+
+/// The tag fields whose value in the library differs from the one in the file.
+pub fn compare(database: &SongMetadata, file: &SongMetadata) -> Vec<FieldDrift> {
+    let file = drift_fields(file);
+    drift_fields(database)
+        .into_iter()
+        .filter_map(|(field, database)| {
+            let file = file.get(&field).cloned().unwrap_or(Value::Null);
+            (database != file).then_some(FieldDrift {
+                field,
+                database,
+                file,
+            })
+        })
+        .collect()
+}
+
+/// The agreed value of each drifted field, limited to `fields` when given.
+pub fn resolve(
+    database: &SongMetadata,
+    file: &SongMetadata,
+    resolution: DriftResolution,
+    fields: Option<&[String]>,
+) -> Result<Map<String, Value>, String> {
+    if let Some(fields) = fields {
+        let known = drift_fields(database);
+        if let Some(unknown) = fields.iter().find(|f| !known.contains_key(*f)) {
+            return Err(format!("invalidInput: unknown tag field '{}'", unknown));
+        }
+    }
+
+    Ok(compare(database, file)
+        .into_iter()
+        .filter(|d| match fields {
+            Some(fields) => fields.contains(&d.field),
+            None => true,
+        })
+        .map(|d| {
+            let value = match resolution {
+                DriftResolution::File => d.file,
+                DriftResolution::Database => d.database,
+                DriftResolution::Merge => merge(&d.database, &d.file),
+            };
+            (d.field, value)
+        })
+        .collect())
+}
+
+/// `metadata` with the given field values.
+pub fn apply(metadata: &SongMetadata, values: &Map<String, Value>) -> Result<SongMetadata, String> {
+    let mut fields = match serde_json::to_value(metadata) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return Err("Failed to read the song's metadata".to_string()),
+    };
+    fields.extend(values.clone());
+    serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+}
+
+fn merge(database: &Value, file: &Value) -> Value {
+    match (database, file) {
+        (Value::Array(database), Value::Array(file)) => {
+            let mut merged = database.clone();
+            merged.extend(file.iter().filter(|v| !database.contains(v)).cloned());
+            Value::Array(merged)
+        }
+        (database, file) if is_empty(database) => file.clone(),
+        (database, _) => database.clone(),
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(a) => a.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(title: &str) -> SongMetadata {
+        SongMetadata {
+            title: title.to_string(),
+            album: "Album".to_string(),
+            duration: 180.0,
+            artists: vec!["Artist".to_string()],
+            genres: vec!["Rock".to_string()],
+            file_exists: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_resolve_and_apply() {
+        let mut database = metadata("Edited");
+        database.year = Some(1999);
+        database.times_played = 3;
+        database.extended.composer = Some("Composer".to_string());
+        let mut file = metadata("Song");
+        file.genres.push("Pop".to_string());
+        file.image = Some("data:image/png;base64,AA==".to_string());
+
+        let fields: Vec<String> = compare(&database, &file).into_iter().map(|d| d.field).collect();
+        assert_eq!(fields, ["composer", "genres", "title", "year"]);
+
+        let merged = resolve(&database, &file, DriftResolution::Merge, None).unwrap();
+        assert_eq!(merged["title"], "Edited");
+        assert_eq!(merged["genres"], serde_json::json!(["Rock", "Pop"]));
+        assert_eq!(merged["year"], 1999);
+
+        let year = ["year".to_string()];
+        let taken = resolve(&database, &file, DriftResolution::File, Some(&year)).unwrap();
+        assert_eq!(taken.len(), 1);
+        let updated = apply(&database, &taken).unwrap();
+        assert_eq!(updated.year, None);
+        assert_eq!(updated.title, "Edited");
+
+        let duration = ["duration".to_string()];
+        assert!(resolve(&database, &file, DriftResolution::File, Some(&duration)).is_err());
+    }
+}
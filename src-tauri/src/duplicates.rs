@@ -0,0 +1,239 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use crate::fingerprint;
+use crate::models::{DuplicateCandidate, DuplicateGroup, Song};
+
+/// Songs whose durations differ by more than this can't be the same recording.
+const MAX_DURATION_DELTA: f64 = 10.0;
+
+/// Cluster songs whose fingerprints match and pick a best copy for each cluster.
+///
+/// Only clusters with at least two songs are returned.
+pub fn find_duplicate_groups(entries: Vec<(Song, Vec<u32>)>, threshold: f32) -> Vec<DuplicateGroup> {
+    let n = entries.len();
+    let mut parents: Vec<usize> = (0..n).collect();
+
+    // Sort by duration so we only compare songs of similar length. Songs with an unknown
+    // duration (0.0) sort first and are compared against everything.
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| {
+        entries[a]
+            .0
+            .metadata
+            .duration
+            .partial_cmp(&entries[b].0.metadata.duration)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    for (pos, &i) in order.iter().enumerate() {
+        let duration_i = entries[i].0.metadata.duration;
+        for &j in &order[pos + 1..] {
+            let duration_j = entries[j].0.metadata.duration;
+            if duration_i > 0.0 && duration_j - duration_i > MAX_DURATION_DELTA {
+                break;
+            }
+            if find_root(&mut parents, i) == find_root(&mut parents, j) {
+                continue;
+            }
+            if fingerprint::similarity(&entries[i].1, &entries[j].1) >= threshold {
+                union(&mut parents, i, j);
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for i in 0..n {
+        let root = find_root(&mut parents, i);
+        clusters[root].push(i);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = clusters
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let mut candidates: Vec<(DuplicateCandidate, &[u32])> = members
+                .iter()
+                .map(|&i| {
+                    let (song, fp) = &entries[i];
+                    (describe_copy(song), fp.as_slice())
+                })
+                .collect();
+            candidates.sort_by(|a, b| compare_quality(&b.0, &a.0));
+
+            let best_fp = candidates[0].1;
+            let songs: Vec<DuplicateCandidate> = candidates
+                .iter()
+                .map(|(candidate, fp)| DuplicateCandidate {
+                    similarity: fingerprint::similarity(best_fp, fp),
+                    ..candidate.clone()
+                })
+                .collect();
+
+            DuplicateGroup {
+                best_copy: songs[0].song.id.clone(),
+                songs,
+            }
+        })
+        .collect();
+
+    groups.sort_by(|a, b| a.songs[0].song.metadata.title.cmp(&b.songs[0].song.metadata.title));
+    groups
+}
+
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut cur = i;
+    while parents[cur] != root {
+        let next = parents[cur];
+        parents[cur] = root;
+        cur = next;
+    }
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let root_a = find_root(parents, a);
+    let root_b = find_root(parents, b);
+    if root_a != root_b {
+        parents[root_b] = root_a;
+    }
+}
+
+fn describe_copy(song: &Song) -> DuplicateCandidate {
+    let path = Path::new(&song.url);
+    let format = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let bitrate = std::fs::metadata(path)
+        .ok()
+        .filter(|_| song.metadata.duration > 0.0)
+        .map(|m| (m.len() as f64 * 8.0 / song.metadata.duration / 1000.0).round() as u32);
+
+    DuplicateCandidate {
+        song: song.clone(),
+        format,
+        bitrate,
+        tag_score: tag_score(song),
+        similarity: 1.0,
+    }
+}
+
+fn format_rank(format: &str) -> u8 {
+    match format {
+        "flac" | "wav" | "aiff" | "aif" | "ape" | "wv" | "alac" => 3,
+        "m4a" | "aac" | "ogg" | "oga" | "opus" => 2,
+        "mp3" => 1,
+        _ => 0,
+    }
+}
+
+/// Number of meaningful fields filled in the song's metadata.
+fn tag_score(song: &Song) -> u32 {
+    let m = &song.metadata;
+    let file_stem = Path::new(&song.url)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("");
+
+    [
+        !m.title.is_empty() && m.title != file_stem,
+        !m.album.is_empty() && m.album != "Unknown Album",
+        !m.artists.is_empty() && m.artists != ["Unknown Artist"],
+        m.year.is_some(),
+        m.track.is_some(),
+        !m.genres.is_empty(),
+        m.image.is_some(),
+        m.bpm.is_some(),
+        m.comment.is_some(),
+    ]
+    .iter()
+    .filter(|filled| **filled)
+    .count() as u32
+}
+
+/// Orders copies by format, then bitrate, then tag completeness.
+fn compare_quality(a: &DuplicateCandidate, b: &DuplicateCandidate) -> Ordering {
+    format_rank(&a.format)
+        .cmp(&format_rank(&b.format))
+        .then(a.bitrate.unwrap_or(0).cmp(&b.bitrate.unwrap_or(0)))
+        .then(a.tag_score.cmp(&b.tag_score))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SongMetadata;
+
+    fn song(id: &str, url: &str, duration: f64, year: Option<i32>) -> Song {
+        Song {
+            id: id.to_string(),
+            url: url.to_string(),
+            filename: url.to_string(),
+            metadata: SongMetadata {
+                title: "Same Song".to_string(),
+                album: "Album".to_string(),
+                year,
+                duration,
+                artists: vec!["Artist".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range: None,
+        }
+    }
+
+    fn fp(seed: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..500)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_groups_matching_fingerprints_and_prefers_lossless() {
+        let entries = vec![
+            (song("mp3", "/music/a.mp3", 200.0, Some(2001)), fp(1)),
+            (song("flac", "/music/a.flac", 201.0, None), fp(1)),
+            (song("other", "/music/b.mp3", 200.0, None), fp(2)),
+        ];
+
+        let groups = find_duplicate_groups(entries, fingerprint::DEFAULT_MATCH_THRESHOLD);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].best_copy, "flac");
+        assert_eq!(groups[0].songs.len(), 2);
+        assert_eq!(groups[0].songs[1].song.id, "mp3");
+    }
+
+    #[test]
+    fn test_duration_mismatch_is_not_grouped() {
+        let entries = vec![
+            (song("short", "/music/a.mp3", 100.0, None), fp(1)),
+            (song("long", "/music/b.mp3", 300.0, None), fp(1)),
+        ];
+
+        assert!(find_duplicate_groups(entries, fingerprint::DEFAULT_MATCH_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn test_tag_score_breaks_ties() {
+        let entries = vec![
+            (song("bare", "/music/a.mp3", 0.0, None), fp(3)),
+            (song("tagged", "/music/b.mp3", 0.0, Some(1999)), fp(3)),
+        ];
+
+        let groups = find_duplicate_groups(entries, fingerprint::DEFAULT_MATCH_THRESHOLD);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].best_copy, "tagged");
+    }
+}
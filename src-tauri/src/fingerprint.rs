@@ -0,0 +1,332 @@
+use crate::audio;
+use crate::models::TrackRange;
+
+// Acoustic fingerprinting in the spirit of Haitsma & Kalker ("A Highly Robust Audio
+// Fingerprinting System"): the signal is downsampled, split into overlapping frames and each
+// frame yields a 32-bit sub-fingerprint from the sign of energy differences between adjacent
+// frequency bands over time. Those bits survive re-encoding, so an MP3 and a FLAC of the same
+// recording produce nearly identical fingerprints.
+
+/// Sample rate the audio is reduced to before analysis.
+const TARGET_SAMPLE_RATE: usize = 5512;
+const FRAME_SIZE: usize = 2048;
+const HOP_SIZE: usize = 256;
+const BAND_COUNT: usize = 33;
+const MIN_FREQ: f32 = 300.0;
+const MAX_FREQ: f32 = 2000.0;
+
+/// Maximum audio analysed per file.
+const MAX_SECONDS: usize = 120;
+/// Minimum audio needed for a fingerprint to be meaningful.
+const MIN_SECONDS: usize = 10;
+
+/// Similarity above which two fingerprints are considered the same recording.
+pub const DEFAULT_MATCH_THRESHOLD: f32 = 0.7;
+
+/// Largest shift (in sub-fingerprints, ~46ms each) tried when aligning two fingerprints.
+const MAX_ALIGN_OFFSET: usize = 64;
+/// Overlap required for a comparison to count.
+const MIN_OVERLAP: usize = 64;
+
+/// Fingerprint the decoded audio of a file, or of `range` for cue sheet tracks.
+///
+/// Returns `Ok(None)` when the file is too short (or silent) to produce a usable fingerprint.
+pub fn fingerprint_file(
+    file_path: &str,
+    range: Option<&TrackRange>,
+) -> Result<Option<Vec<u32>>, String> {
+    let audio = audio::decode_mono_samples(file_path, range, MAX_SECONDS)?;
+    Ok(fingerprint_samples(&audio.samples, audio.sample_rate))
+}
+
+fn fingerprint_samples(samples: &[f32], sample_rate: usize) -> Option<Vec<u32>> {
+    if sample_rate == 0 {
+        return None;
+    }
+
+    // Leading silence differs between rips and encoders; skip it so fingerprints line up.
+    let samples = trim_leading_silence(samples, sample_rate);
+    let samples = downsample(samples, sample_rate, TARGET_SAMPLE_RATE);
+
+    if samples.len() < TARGET_SAMPLE_RATE * MIN_SECONDS {
+        return None;
+    }
+
+    let band_edges = band_bin_edges();
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| {
+            0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FRAME_SIZE - 1) as f32).cos()
+        })
+        .collect();
+
+    let mut re = vec![0.0f32; FRAME_SIZE];
+    let mut im = vec![0.0f32; FRAME_SIZE];
+    let mut previous: Option<[f32; BAND_COUNT]> = None;
+    let mut fingerprint = Vec::with_capacity(samples.len() / HOP_SIZE);
+
+    let mut start = 0;
+    while start + FRAME_SIZE <= samples.len() {
+        for i in 0..FRAME_SIZE {
+            re[i] = samples[start + i] * window[i];
+            im[i] = 0.0;
+        }
+        fft(&mut re, &mut im);
+
+        let mut energies = [0.0f32; BAND_COUNT];
+        for (band, energy) in energies.iter_mut().enumerate() {
+            let (lo, hi) = (band_edges[band], band_edges[band + 1]);
+            for bin in lo..hi {
+                *energy += re[bin] * re[bin] + im[bin] * im[bin];
+            }
+        }
+
+        if let Some(prev) = previous {
+            let mut bits = 0u32;
+            for m in 0..BAND_COUNT - 1 {
+                let diff = (energies[m] - energies[m + 1]) - (prev[m] - prev[m + 1]);
+                if diff > 0.0 {
+                    bits |= 1 << m;
+                }
+            }
+            fingerprint.push(bits);
+        }
+        previous = Some(energies);
+        start += HOP_SIZE;
+    }
+
+    if fingerprint.is_empty() {
+        None
+    } else {
+        Some(fingerprint)
+    }
+}
+
+/// Similarity of two fingerprints in `[0, 1]`, where 1.0 means every bit matches.
+///
+/// Tries small alignment offsets in both directions to absorb encoder delay and padding.
+pub fn similarity(a: &[u32], b: &[u32]) -> f32 {
+    let mut best = 0.0f32;
+
+    for offset in 0..=MAX_ALIGN_OFFSET {
+        for (x, y) in [(a, b), (b, a)] {
+            if offset >= x.len() {
+                continue;
+            }
+            let x = &x[offset..];
+            let overlap = x.len().min(y.len());
+            if overlap < MIN_OVERLAP {
+                continue;
+            }
+
+            let differing: u32 = x[..overlap]
+                .iter()
+                .zip(&y[..overlap])
+                .map(|(p, q)| (p ^ q).count_ones())
+                .sum();
+            let score = 1.0 - differing as f32 / (overlap as f32 * 32.0);
+            if score > best {
+                best = score;
+            }
+        }
+    }
+
+    best
+}
+
+pub fn to_bytes(fingerprint: &[u32]) -> Vec<u8> {
+    fingerprint.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+fn trim_leading_silence(samples: &[f32], sample_rate: usize) -> &[f32] {
+    const THRESHOLD: f32 = 1e-3;
+    let window = (sample_rate / 100).max(1);
+
+    for (idx, chunk) in samples.chunks(window).enumerate() {
+        let level = chunk.iter().map(|x| x.abs()).sum::<f32>() / chunk.len() as f32;
+        if level > THRESHOLD {
+            return &samples[idx * window..];
+        }
+    }
+    &[]
+}
+
+/// Downsample by averaging each output period (a crude low-pass that's plenty for band energies).
+fn downsample(samples: &[f32], from_rate: usize, to_rate: usize) -> Vec<f32> {
+    if from_rate <= to_rate {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = (samples.len() as f64 / ratio) as usize;
+    let mut out = Vec::with_capacity(out_len);
+
+    for i in 0..out_len {
+        let start = (i as f64 * ratio) as usize;
+        let end = (((i + 1) as f64 * ratio) as usize).min(samples.len()).max(start + 1);
+        let sum: f32 = samples[start..end].iter().sum();
+        out.push(sum / (end - start) as f32);
+    }
+    out
+}
+
+/// FFT bin boundaries of the logarithmically spaced bands between `MIN_FREQ` and `MAX_FREQ`.
+fn band_bin_edges() -> Vec<usize> {
+    let bin_width = TARGET_SAMPLE_RATE as f32 / FRAME_SIZE as f32;
+    let ratio = (MAX_FREQ / MIN_FREQ).powf(1.0 / BAND_COUNT as f32);
+
+    let mut edges: Vec<usize> = (0..=BAND_COUNT)
+        .map(|i| (MIN_FREQ * ratio.powi(i as i32) / bin_width).round() as usize)
+        .collect();
+
+    // Keep every band at least one bin wide.
+    for i in 1..edges.len() {
+        if edges[i] <= edges[i - 1] {
+            edges[i] = edges[i - 1] + 1;
+        }
+    }
+    edges
+}
+
+/// In-place iterative radix-2 FFT. `re.len()` must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f32::consts::PI / len as f32;
+        let (w_im, w_re) = angle.sin_cos();
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A melody-ish test signal: plucked notes picked from `seed`, plus optional noise.
+    fn synth(sample_rate: usize, seconds: usize, seed: u32, noise: f32) -> Vec<f32> {
+        let scale = [261.63f32, 293.66, 329.63, 392.0, 440.0, 523.25, 587.33, 659.25];
+        let mut rng = seed;
+        let mut next = move || {
+            rng = rng.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            rng
+        };
+
+        let note_len = sample_rate / 4;
+        let notes: Vec<f32> = (0..seconds * 4 + 1)
+            .map(|_| scale[(next() >> 24) as usize % scale.len()])
+            .collect();
+
+        (0..sample_rate * seconds)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let freq = notes[i / note_len];
+                let since_onset = (i % note_len) as f32 / sample_rate as f32;
+                let env = (-since_onset * 6.0).exp();
+                let tone = (2.0 * std::f32::consts::PI * freq * t).sin()
+                    + 0.5 * (2.0 * std::f32::consts::PI * freq * 2.0 * t).sin();
+                let hiss = noise * ((next() >> 8) as f32 / (1u32 << 24) as f32 - 0.5);
+                0.4 * env * tone + hiss
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fft_single_tone_peak() {
+        let n = 64;
+        let mut re: Vec<f32> = (0..n)
+            .map(|i| (2.0 * std::f32::consts::PI * 4.0 * i as f32 / n as f32).cos())
+            .collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+
+        let peak = (0..n / 2)
+            .max_by(|&a, &b| {
+                let ma = re[a] * re[a] + im[a] * im[a];
+                let mb = re[b] * re[b] + im[b] * im[b];
+                ma.partial_cmp(&mb).unwrap()
+            })
+            .unwrap();
+        assert_eq!(peak, 4);
+    }
+
+    #[test]
+    fn test_too_short_returns_none() {
+        assert!(fingerprint_samples(&synth(44100, 3, 1, 0.0), 44100).is_none());
+        assert!(fingerprint_samples(&vec![0.0; 44100 * 20], 44100).is_none());
+    }
+
+    #[test]
+    fn test_same_audio_with_noise_matches() {
+        let a = fingerprint_samples(&synth(44100, 20, 1, 0.0), 44100).unwrap();
+        let b = fingerprint_samples(&synth(44100, 20, 1, 0.01), 44100).unwrap();
+        assert!(similarity(&a, &b) > DEFAULT_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn test_same_audio_different_rate_matches() {
+        let a = fingerprint_samples(&synth(44100, 20, 1, 0.0), 44100).unwrap();
+        let b = fingerprint_samples(&synth(48000, 20, 1, 0.0), 48000).unwrap();
+        assert!(similarity(&a, &b) > DEFAULT_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn test_leading_silence_is_ignored() {
+        let a = fingerprint_samples(&synth(44100, 20, 1, 0.0), 44100).unwrap();
+        let mut padded = vec![0.0f32; 44100];
+        padded.extend(synth(44100, 20, 1, 0.0));
+        let b = fingerprint_samples(&padded, 44100).unwrap();
+        assert!(similarity(&a, &b) > 0.95);
+    }
+
+    #[test]
+    fn test_different_audio_does_not_match() {
+        let a = fingerprint_samples(&synth(44100, 20, 1, 0.0), 44100).unwrap();
+        let b = fingerprint_samples(&synth(44100, 20, 2, 0.0), 44100).unwrap();
+        assert!(similarity(&a, &b) < DEFAULT_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let fp = vec![0u32, 1, 0xDEADBEEF, u32::MAX];
+        assert_eq!(from_bytes(&to_bytes(&fp)), fp);
+    }
+}
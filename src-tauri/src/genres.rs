@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+/// Maps free-text genres onto the genre taxonomy's canonical names.
+///
+/// Genres match a registered name or alias when their folded forms agree, so "hip-hop",
+/// "HipHop" and "Hip Hop" are all the same genre. Unknown genres are kept as written.
+pub struct GenreResolver {
+    canonical: HashMap<String, String>,
+}
+
+impl GenreResolver {
+    /// `registry` holds each genre's canonical name and its aliases, oldest genre first.
+    pub fn new(registry: Vec<(String, Vec<String>)>) -> Self {
+        let mut canonical = HashMap::new();
+        // Names win over aliases that fold to the same key, and older names over newer ones.
+        for (name, _) in &registry {
+            canonical.entry(fold_genre(name)).or_insert_with(|| name.clone());
+        }
+        for (name, aliases) in &registry {
+            for alias in aliases {
+                canonical.entry(fold_genre(alias)).or_insert_with(|| name.clone());
+            }
+        }
+        Self { canonical }
+    }
+
+    /// Split "Rock; Pop" style values, map each genre to its canonical name and de-duplicate.
+    pub fn normalize(&self, genres: &[String]) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        let mut seen: Vec<String> = Vec::new();
+
+        for genre in genres.iter().flat_map(|g| g.split(';')) {
+            let genre = genre.trim();
+            let key = fold_genre(genre);
+            if key.is_empty() || seen.contains(&key) {
+                continue;
+            }
+
+            let name = self
+                .canonical
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| genre.to_string());
+            seen.push(key);
+            out.push(name);
+        }
+        out
+    }
+}
+
+/// Lowercased ASCII with everything but letters and digits removed, and "and"/"n" joiners
+/// dropped so "&" spellings match.
+pub fn fold_genre(name: &str) -> String {
+    let ascii = deunicode::deunicode(name).to_lowercase();
+    let words: Vec<&str> = ascii
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    // "Drum & Bass", "Drum 'n' Bass" and "Drum and Bass" fold alike; "R&B" stays "rb".
+    let mut folded = String::new();
+    for (i, word) in words.iter().enumerate() {
+        let is_joiner = matches!(*word, "and" | "n") && i > 0 && i + 1 < words.len();
+        if !is_joiner {
+            folded.push_str(word);
+        }
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genres(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fold_genre() {
+        assert_eq!(fold_genre("Hip Hop"), "hiphop");
+        assert_eq!(fold_genre("hip-hop"), "hiphop");
+        assert_eq!(fold_genre("HipHop"), "hiphop");
+        assert_eq!(fold_genre("Drum & Bass"), fold_genre("Drum and Bass"));
+        assert_eq!(fold_genre("Drum 'n' Bass"), fold_genre("Drum and Bass"));
+        assert_eq!(fold_genre("R&B"), "rb");
+        assert_eq!(fold_genre("Électronique"), "electronique");
+    }
+
+    #[test]
+    fn test_normalize_maps_aliases_and_splits() {
+        let resolver = GenreResolver::new(vec![
+            ("Hip Hop".to_string(), vec![]),
+            ("R&B".to_string(), vec!["RnB".to_string(), "Rhythm and Blues".to_string()]),
+        ]);
+
+        assert_eq!(
+            resolver.normalize(&genres(&["hip-hop; rnb", "HIPHOP", "Trip Hop"])),
+            genres(&["Hip Hop", "R&B", "Trip Hop"])
+        );
+        assert_eq!(resolver.normalize(&genres(&["", " ; "])), Vec::<String>::new());
+    }
+}
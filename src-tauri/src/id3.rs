@@ -1,13 +1,26 @@
-use crate::models::SongMetadata;
+use crate::atomic_write;
+use crate::chapters;
+use crate::lyrics::{self, FileLyrics};
+use crate::models::{
+    AppSettings, ExtendedTags, LyricLine, MultiValueSettings, RatingSettings, SongMetadata, TagFieldSettings,
+    TagFrame, TagSnapshot, TagWriteSettings,
+};
+use crate::ratings;
+use crate::dj_library::DjCue;
+use crate::serato::{self, SeratoData};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use id3::frame::{Comment, Content, Picture};
-use id3::{Frame, Tag, TagLike};
+use id3::frame::{
+    Comment, Content, EncapsulatedObject, ExtendedText, Picture, Popularimeter, SynchronisedLyrics, SynchronisedLyricsType,
+    TimestampFormat, UniqueFileIdentifier,
+};
+use id3::{Frame, Tag, TagLike, Timestamp};
 use lofty::config::WriteOptions;
 use lofty::file::{AudioFile, TaggedFileExt};
 use lofty::picture::PictureType;
-use lofty::tag::Accessor;
-use std::fs::File;
-use std::path::Path;
+use lofty::tag::{Accessor, ItemKey, ItemValue, TagItem, TagType};
+use std::fs::{File, OpenOptions};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 
 fn parse_base64_data_url(data_url: &str) -> Result<(String, Vec<u8>), Box<dyn std::error::Error>> {
     if !data_url.starts_with("data:") {
@@ -39,11 +52,87 @@ fn parse_base64_data_url(data_url: &str) -> Result<(String, Vec<u8>), Box<dyn st
     Ok((mime_type.to_string(), bytes))
 }
 
-pub struct Id3Manager;
+/// Split multi-valued text into trimmed, non-empty values. Several values (repeated fields, or
+/// null-separated as in ID3v2.4 and APE) are kept as they are; a single value is split on the
+/// `split_on` separators.
+fn split_values<'a>(values: impl Iterator<Item = &'a str>, split_on: &[String]) -> Vec<String> {
+    let native: Vec<&str> = values
+        .flat_map(|v| v.split('\0'))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if native.len() != 1 {
+        return native.into_iter().map(|v| v.to_string()).collect();
+    }
+
+    let mut parts = native;
+    for separator in split_on.iter().filter(|s| !s.is_empty()) {
+        parts = parts
+            .into_iter()
+            .flat_map(|p| p.split(separator.as_str()))
+            .collect();
+    }
+    parts
+        .into_iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+        .collect()
+}
+
+/// Save a lofty file's tags into `path`, an untouched copy of the file they were read from.
+fn save_lofty_file(parsed_file: &lofty::file::TaggedFile, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    parsed_file.save_to(&mut file, WriteOptions::default())?;
+    Ok(())
+}
+
+fn verify_title(written: &SongMetadata, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if written.title != title {
+        return Err(format!("expected title \"{}\", read \"{}\"", title, written.title).into());
+    }
+    Ok(())
+}
+
+/// Whether a (lowercased) path is an MP4 container, which carries Nero chapters.
+fn is_mp4(lower_path: &str) -> bool {
+    [".m4a", ".m4b", ".mp4"].iter().any(|ext| lower_path.ends_with(ext))
+}
+
+/// The ID3v2 version to write: ID3v2.3 tags stay 2.3 for players that only read 2.3, anything
+/// else is written as 2.4.
+fn write_version(tag: &Tag) -> id3::Version {
+    match tag.version() {
+        id3::Version::Id3v23 => id3::Version::Id3v23,
+        _ => id3::Version::Id3v24,
+    }
+}
+
+/// Vorbis/APE comment (and MP4 freeform atom) holding the star rating.
+const RATING_FIELD: &str = "RATING";
+/// APE item holding the tempo; lofty has a key for it in the other formats.
+const BPM_FIELD: &str = "BPM";
+
+pub struct Id3Manager {
+    fields: TagFieldSettings,
+    ratings: RatingSettings,
+    multi_value: MultiValueSettings,
+    writes: TagWriteSettings,
+}
 
 impl Id3Manager {
     pub fn new() -> Self {
-        Self
+        Self::from_settings(&AppSettings::default())
+    }
+
+    /// Use the custom field names and rating options from the settings.
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            fields: settings.tag_fields.clone(),
+            ratings: settings.ratings.clone(),
+            multi_value: settings.multi_value.clone(),
+            writes: settings.tag_writes.clone(),
+        }
     }
 
     pub fn read_metadata(&self, file_path: &str) -> Result<SongMetadata, Box<dyn std::error::Error>> {
@@ -54,7 +143,13 @@ impl Id3Manager {
         }
 
         // Try reading with lofty first (supports more formats)
-        if let Ok(metadata) = self.read_with_lofty(file_path) {
+        if let Ok(mut metadata) = self.read_with_lofty(file_path) {
+            // lofty keeps no item for POPM frames, so MP3 ratings and play counts come from id3.
+            if file_path.to_lowercase().ends_with(".mp3") {
+                if let Ok(tag) = Tag::read_from_path(file_path) {
+                    (metadata.rating, metadata.times_played) = self.read_popularimeter(&tag);
+                }
+            }
             return Ok(metadata);
         }
 
@@ -85,9 +180,12 @@ impl Id3Manager {
                     .to_string()
             });
 
-        let artists = tag
-            .and_then(|t| t.artist())
-            .map(|a| vec![a.to_string()])
+        let values = |key: ItemKey| -> Vec<String> {
+            tag.map(|t| split_values(t.get_strings(&key), &self.multi_value.split_on))
+                .unwrap_or_default()
+        };
+        let artists = Some(values(ItemKey::TrackArtist))
+            .filter(|a| !a.is_empty())
             .unwrap_or_else(|| vec!["Unknown Artist".to_string()]);
 
         let album = tag
@@ -96,12 +194,48 @@ impl Id3Manager {
             .unwrap_or_else(|| "Unknown Album".to_string());
         let year = tag.and_then(|t| t.year()).map(|y| y as i32);
         let track = tag.and_then(|t| t.track()).map(|t| t as i32);
-        let genres = tag
-            .and_then(|t| t.genre())
-            .map(|g| vec![g.to_string()])
-            .unwrap_or_default();
+        let genres = values(ItemKey::Genre);
         let comment = tag.and_then(|t| t.comment()).map(|c| c.to_string());
-        let bpm = None;
+
+        let album_artist = tag
+            .and_then(|t| t.get_string(&ItemKey::AlbumArtist))
+            .map(|a| a.to_string());
+        let disc = tag.and_then(|t| t.disk()).map(|d| d as i32);
+        let disc_total = tag.and_then(|t| t.disk_total()).map(|d| d as i32);
+        let track_total = tag.and_then(|t| t.track_total()).map(|t| t as i32);
+        let compilation = tag
+            .and_then(|t| t.get_string(&ItemKey::FlagCompilation))
+            .is_some_and(|v| v == "1");
+        let extended = tag.map(read_extended_lofty).unwrap_or_default();
+
+        // Custom fields come back as unknown keys, e.g. a Vorbis "INSTRUMENTS" comment.
+        let custom_field = |field: &str| -> Vec<String> {
+            let Some(tag) = tag else {
+                return vec![];
+            };
+            split_values(
+                tag.items()
+                    .filter(|item| custom_key_matches(item.key(), field))
+                    .filter_map(|item| item.value().text()),
+                &self.multi_value.split_on,
+            )
+        };
+        let instruments = Some(custom_field(&self.fields.instruments)).filter(|i| !i.is_empty());
+        let tags = custom_field(&self.fields.tags);
+        // The exact tempo where the format has a field for it, else the whole-number one. APE
+        // has neither, so it's a custom field there.
+        let bpm = tag
+            .and_then(|t| {
+                t.get_string(&ItemKey::Bpm)
+                    .or_else(|| t.get_string(&ItemKey::IntegerBpm))
+                    .and_then(parse_bpm)
+            })
+            .or_else(|| custom_field(BPM_FIELD).first().and_then(|v| parse_bpm(v)));
+        let rating = match tag.map(|t| rating_key(t.tag_type())) {
+            Some(ItemKey::Popularimeter) => values(ItemKey::Popularimeter),
+            _ => custom_field(RATING_FIELD),
+        };
+        let rating = rating.first().and_then(|v| ratings::rating_field_to_stars(v));
 
         // Extract cover art image
         let image = tag.and_then(|t| {
@@ -125,13 +259,21 @@ impl Id3Manager {
             image,
             duration: properties.duration().as_secs_f64(),
             artists,
-            instruments: None,
+            instruments,
             bpm,
             genres,
             comment,
-            tags: vec![],
+            tags,
             file_exists: true,
             times_played: 0,
+            album_artist,
+            disc,
+            disc_total,
+            track_total,
+            compilation,
+            album_id: None,
+            rating,
+            extended,
         })
     }
 
@@ -147,18 +289,39 @@ impl Id3Manager {
             })
             .to_string();
 
-        let artists = tag.artist()
-            .map(|a| vec![a.to_string()])
+        let artists = Some(self.id3_text_values(&tag, "TPE1"))
+            .filter(|a| !a.is_empty())
             .unwrap_or_else(|| vec!["Unknown Artist".to_string()]);
 
         let album = tag.album().unwrap_or("Unknown Album").to_string();
-        let year = tag.year();
+        let year = tag.year().or_else(|| tag.date_recorded().map(|date| date.year));
         let track = tag.track().map(|t| t as i32);
-        let genres = tag.genre().map(|g| vec![g.to_string()]).unwrap_or_default();
+        let genres = self.id3_text_values(&tag, "TCON");
         let comment = tag.comments().next().map(|c| c.text.clone());
-        
-        // ID3 crate doesn't have BPM support directly
-        let bpm = None;
+        let album_artist = tag.album_artist().map(|a| a.to_string());
+        let disc = tag.disc().map(|d| d as i32);
+        let disc_total = tag.total_discs().map(|d| d as i32);
+        let track_total = tag.total_tracks().map(|t| t as i32);
+        let compilation = tag
+            .get("TCMP")
+            .and_then(|f| f.content().text())
+            .is_some_and(|v| v == "1");
+        let extended = read_extended_id3(&tag);
+
+        // Instruments and user tags live in TXXX frames named by the field settings.
+        let custom_field = |field: &str| -> Vec<String> {
+            split_values(
+                tag.extended_texts()
+                    .filter(|t| t.description.eq_ignore_ascii_case(field))
+                    .map(|t| t.value.as_str()),
+                &self.multi_value.split_on,
+            )
+        };
+        let instruments = Some(custom_field(&self.fields.instruments)).filter(|i| !i.is_empty());
+        let tags = custom_field(&self.fields.tags);
+
+        let (rating, times_played) = self.read_popularimeter(&tag);
+        let bpm = tag.get("TBPM").and_then(|f| f.content().text()).and_then(parse_bpm);
 
         // Extract cover art image
         let image = tag.pictures()
@@ -180,17 +343,55 @@ impl Id3Manager {
             image,
             duration,
             artists,
-            instruments: None,
+            instruments,
             bpm,
             genres,
             comment,
-            tags: vec![],
+            tags,
             file_exists: true,
-            times_played: 0,
+            times_played,
+            album_artist,
+            disc,
+            disc_total,
+            track_total,
+            compilation,
+            album_id: None,
+            rating,
+            extended,
         })
     }
 
+    /// Rating and play count from our POPM frame, or any POPM frame if we haven't written one.
+    /// The play count is 0 unless it's synced.
+    fn read_popularimeter(&self, tag: &Tag) -> (Option<f32>, i32) {
+        let popularimeters: Vec<&Popularimeter> = tag
+            .frames()
+            .filter_map(|f| f.content().popularimeter())
+            .collect();
+        let popm = popularimeters
+            .iter()
+            .find(|p| p.user == self.ratings.popm_email)
+            .or_else(|| popularimeters.first());
+        let rating = popm.and_then(|p| ratings::popm_to_stars(p.rating));
+        let times_played = match popm {
+            Some(p) if self.ratings.sync_play_count => i32::try_from(p.counter).unwrap_or(i32::MAX),
+            _ => 0,
+        };
+        (rating, times_played)
+    }
+
     pub fn write_metadata(&self, file_path: &str, metadata: &SongMetadata) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_metadata_clearing(file_path, metadata, &[])
+    }
+
+    /// Write the metadata, removing the frames of the `cleared` fields. Other fields with no
+    /// value are left as the file has them.
+    pub fn write_metadata_clearing(
+        &self,
+        file_path: &str,
+        metadata: &SongMetadata,
+        cleared: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let path = Path::new(file_path);
         
         if !path.exists() {
@@ -199,40 +400,116 @@ impl Id3Manager {
 
         // Try writing with lofty first
         if file_path.to_lowercase().ends_with(".mp3") {
-            if let Ok(()) = self.write_with_id3(file_path, metadata) {
+            if let Ok(()) = self.write_with_id3(file_path, metadata, cleared) {
                 return Ok(());
             }
         }
 
         // Try with lofty as fallback
-        self.write_with_lofty(file_path, metadata)
+        self.write_with_lofty(file_path, metadata, cleared)
     }
 
-    fn write_with_id3(&self, file_path: &str, metadata: &SongMetadata) -> Result<(), Box<dyn std::error::Error>> {
+    fn write_with_id3(
+        &self,
+        file_path: &str,
+        metadata: &SongMetadata,
+        cleared: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+        let version = write_version(&tag);
+        let separator = self.id3_separator(version);
 
         // Set basic metadata
         tag.set_title(&metadata.title);
         tag.set_album(&metadata.album);
         
+        // TDRC is ID3v2.4 only; ID3v2.3 has TYER. A full date in the same year is kept.
         if let Some(year) = metadata.year {
-            tag.set_year(year);
+            match version {
+                id3::Version::Id3v24 => {
+                    tag.remove("TYER");
+                    if tag.date_recorded().map(|date| date.year) != Some(year) {
+                        tag.set_date_recorded(Timestamp {
+                            year,
+                            month: None,
+                            day: None,
+                            hour: None,
+                            minute: None,
+                            second: None,
+                        });
+                    }
+                }
+                _ => {
+                    tag.remove("TDRC");
+                    tag.set_year(year);
+                }
+            }
         }
         
         if let Some(track) = metadata.track {
             tag.set_track(track as u32);
         }
 
-        // Set artists (join multiple artists with semicolon)
+        if let Some(track_total) = metadata.track_total {
+            tag.set_total_tracks(track_total as u32);
+        }
+
+        // Album artist (TPE2), disc number (TPOS) and the iTunes compilation flag (TCMP)
+        if let Some(album_artist) = &metadata.album_artist {
+            tag.set_album_artist(album_artist);
+        }
+
+        if let Some(disc) = metadata.disc {
+            tag.set_disc(disc as u32);
+        }
+
+        if let Some(disc_total) = metadata.disc_total {
+            tag.set_total_discs(disc_total as u32);
+        }
+
+        if metadata.compilation {
+            tag.set_text("TCMP", "1");
+        } else {
+            tag.remove("TCMP");
+        }
+
+        write_extended_id3(&mut tag, &metadata.extended, version, cleared);
+
+        // Artists and genres, null-separated in ID3v2.4
         if !metadata.artists.is_empty() {
-            tag.set_artist(&metadata.artists.join("; "));
+            tag.set_text("TPE1", metadata.artists.join(separator));
         }
 
-        // Set genres
         if !metadata.genres.is_empty() {
-            tag.set_genre(&metadata.genres.join("; "));
+            tag.set_text("TCON", metadata.genres.join(separator));
+        }
+
+        match metadata.bpm {
+            Some(bpm) => tag.set_text("TBPM", bpm.to_string()),
+            None if cleared.contains(&"bpm") => {
+                tag.remove("TBPM");
+            }
+            None => {}
         }
 
+        // Instruments (left alone when unknown) and user tags
+        if let Some(instruments) = &metadata.instruments {
+            set_extended_text(&mut tag, &self.fields.instruments, instruments, separator);
+        }
+        set_extended_text(&mut tag, &self.fields.tags, &metadata.tags, separator);
+
+        // Rating, and the play count when it's synced, in our POPM frame
+        let play_count = self
+            .ratings
+            .sync_play_count
+            .then(|| u64::try_from(metadata.times_played).unwrap_or(0));
+        let rating = match metadata.rating {
+            Some(rating) => Some(ratings::stars_to_popm(rating)),
+            None if cleared.contains(&"rating") => Some(0),
+            None => None,
+        };
+        set_popularimeter(&mut tag, &self.ratings.popm_email, rating, play_count);
+
         // Set comment
         if let Some(comment) = &metadata.comment {
             let comment_obj = Comment {
@@ -244,7 +521,9 @@ impl Id3Manager {
         }
 
         // Set/clear cover art
-        match &metadata.image {
+        match metadata.image.as_deref() {
+            // A cover cache id rather than image data: keep the embedded picture as it is.
+            Some(image) if !image.starts_with("data:") => {}
             Some(image_data_url) => {
                 let (mime_type, data) = parse_base64_data_url(image_data_url)?;
                 tag.remove_all_pictures();
@@ -263,14 +542,24 @@ impl Id3Manager {
             }
         }
 
-        tag.write_to_path(file_path, id3::Version::Id3v24)?;
-        Ok(())
+        self.save_tags(
+            file_path,
+            |temp| Ok(tag.write_to_path(temp, version)?),
+            |temp| verify_title(&self.read_with_id3(temp)?, &metadata.title),
+        )
     }
 
-    fn write_with_lofty(&self, file_path: &str, metadata: &SongMetadata) -> Result<(), Box<dyn std::error::Error>> {
-        let mut file = File::open(file_path)?;
-        
-        let mut parsed_file = lofty::read_from(&mut file)?;
+    fn write_with_lofty(
+        &self,
+        file_path: &str,
+        metadata: &SongMetadata,
+        cleared: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // The original is closed again before the rewritten copy replaces it.
+        let mut parsed_file = lofty::read_from(&mut File::open(file_path)?)?;
+        if parsed_file.primary_tag().is_none() {
+            parsed_file.insert_tag(lofty::tag::Tag::new(parsed_file.primary_tag_type()));
+        }
         
         if let Some(tag) = parsed_file.primary_tag_mut() {
             tag.set_title(metadata.title.clone());
@@ -283,32 +572,88 @@ impl Id3Manager {
             if let Some(track) = metadata.track {
                 tag.set_track(track as u32);
             }
+
+            if let Some(track_total) = metadata.track_total {
+                tag.set_track_total(track_total as u32);
+            }
+
+            if let Some(album_artist) = &metadata.album_artist {
+                tag.insert_text(ItemKey::AlbumArtist, album_artist.clone());
+            }
+
+            if let Some(disc) = metadata.disc {
+                tag.set_disk(disc as u32);
+            }
+
+            if let Some(disc_total) = metadata.disc_total {
+                tag.set_disk_total(disc_total as u32);
+            }
+
+            if metadata.compilation {
+                tag.insert_text(ItemKey::FlagCompilation, "1".to_string());
+            } else {
+                tag.remove_key(&ItemKey::FlagCompilation);
+            }
             
             if !metadata.artists.is_empty() {
-                tag.set_artist(metadata.artists.join("; "));
+                self.set_lofty_values(tag, ItemKey::TrackArtist, &metadata.artists);
             }
 
             if !metadata.genres.is_empty() {
-                tag.set_genre(metadata.genres.join("; "));
+                self.set_lofty_values(tag, ItemKey::Genre, &metadata.genres);
             }
 
             if let Some(comment) = &metadata.comment {
                 tag.set_comment(comment.clone());
             }
 
-            // Set/clear cover art
-            while !tag.pictures().is_empty() {
-                tag.remove_picture(0);
+            // MP4's tmpo atom only holds whole numbers; formats with an exact field get both.
+            if metadata.bpm.is_some() || cleared.contains(&"bpm") {
+                tag.remove_key(&ItemKey::Bpm);
+                tag.remove_key(&ItemKey::IntegerBpm);
+                self.set_custom_field(tag, BPM_FIELD, &[]);
+            }
+            if let Some(bpm) = metadata.bpm {
+                let exact = tag.insert_text(ItemKey::Bpm, bpm.to_string());
+                let whole = tag.insert_text(ItemKey::IntegerBpm, (bpm.round() as u32).to_string());
+                if !exact && !whole {
+                    self.set_custom_field(tag, BPM_FIELD, &[bpm.to_string()]);
+                }
             }
-            if let Some(image_data_url) = &metadata.image {
-                let (_mime_type, data) = parse_base64_data_url(image_data_url)?;
-                let picture = lofty::picture::Picture::new_unchecked(PictureType::CoverFront, None, None, data);
-                tag.push_picture(picture);
+
+            if let Some(instruments) = &metadata.instruments {
+                self.set_custom_field(tag, &self.fields.instruments, instruments);
+            }
+            self.set_custom_field(tag, &self.fields.tags, &metadata.tags);
+            write_extended_lofty(tag, &metadata.extended, cleared);
+
+            if metadata.rating.is_some() || cleared.contains(&"rating") {
+                let rating = metadata.rating.map(|r| ratings::stars_to_percent(r).to_string());
+                self.set_lofty_values(tag, rating_key(tag.tag_type()), rating.as_slice());
+            }
+
+            // Set/clear cover art (a cover cache id rather than image data keeps the embedded picture)
+            let keep_picture = metadata
+                .image
+                .as_deref()
+                .is_some_and(|image| !image.starts_with("data:"));
+            if !keep_picture {
+                while !tag.pictures().is_empty() {
+                    tag.remove_picture(0);
+                }
+                if let Some(image_data_url) = &metadata.image {
+                    let (_mime_type, data) = parse_base64_data_url(image_data_url)?;
+                    let picture = lofty::picture::Picture::new_unchecked(PictureType::CoverFront, None, None, data);
+                    tag.push_picture(picture);
+                }
             }
 
             // Save changes
-            let mut output_file = File::create(file_path)?;
-            parsed_file.save_to(&mut output_file, WriteOptions::default())?;
+            self.save_tags(
+                file_path,
+                |temp| save_lofty_file(&parsed_file, temp),
+                |temp| verify_title(&self.read_with_lofty(temp)?, &metadata.title),
+            )?;
         }
 
         Ok(())
@@ -328,6 +673,488 @@ impl Id3Manager {
         Ok(results)
     }
 
+    /// Replace the file's front cover with a data URL image, leaving the rest of its tags alone.
+    pub fn write_cover(&self, file_path: &str, image_data_url: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+        let (mime_type, data) = parse_base64_data_url(image_data_url)?;
+
+        if file_path.to_lowercase().ends_with(".mp3") {
+            let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+            tag.remove_picture_by_type(id3::frame::PictureType::CoverFront);
+            tag.add_frame(Picture {
+                mime_type,
+                picture_type: id3::frame::PictureType::CoverFront,
+                description: String::new(),
+                data,
+            });
+            return self.save_tags(
+                file_path,
+                |temp| Ok(tag.write_to_path(temp, write_version(&tag))?),
+                |temp| Tag::read_from_path(temp).map(|_| ()).map_err(|e| e.into()),
+            );
+        }
+
+        let mut parsed_file = lofty::read_from(&mut File::open(file_path)?)?;
+        if parsed_file.primary_tag().is_none() {
+            parsed_file.insert_tag(lofty::tag::Tag::new(parsed_file.primary_tag_type()));
+        }
+        if let Some(tag) = parsed_file.primary_tag_mut() {
+            tag.remove_picture_type(PictureType::CoverFront);
+            tag.push_picture(lofty::picture::Picture::new_unchecked(PictureType::CoverFront, None, None, data));
+            self.save_tags(
+                file_path,
+                |temp| save_lofty_file(&parsed_file, temp),
+                |temp| self.read_with_lofty(temp).map(|_| ()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Lyrics embedded in the file: SYLT/USLT frames in MP3s, the LYRICS field elsewhere.
+    pub fn read_lyrics(&self, file_path: &str) -> Result<Option<FileLyrics>, Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+
+        if file_path.to_lowercase().ends_with(".mp3") {
+            let tag = match Tag::read_from_path(file_path) {
+                Ok(tag) => tag,
+                Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            let unsynced = tag.lyrics().find(|l| !l.text.trim().is_empty());
+            let language = unsynced.map(|l| l.lang.clone()).filter(|l| !l.trim().is_empty());
+
+            let synced = tag
+                .synchronised_lyrics()
+                .find(|s| s.timestamp_format == TimestampFormat::Ms && !s.content.is_empty());
+            if let Some(synced) = synced {
+                let lines: Vec<LyricLine> = synced
+                    .content
+                    .iter()
+                    .map(|(ms, text)| LyricLine {
+                        time: *ms as f64 / 1000.0,
+                        text: text.trim().to_string(),
+                    })
+                    .collect();
+                let language = Some(synced.lang.clone()).filter(|l| !l.trim().is_empty()).or(language);
+                return Ok(Some(FileLyrics {
+                    text: lyrics::plain_text(&lines),
+                    lines,
+                    language,
+                }));
+            }
+
+            return Ok(unsynced.and_then(|l| FileLyrics::from_text(&l.text, language)));
+        }
+
+        let mut file = File::open(file_path)?;
+        let parsed_file = lofty::read_from(&mut file)?;
+        Ok(parsed_file
+            .primary_tag()
+            .or_else(|| parsed_file.tags().first())
+            .and_then(|tag| tag.get_string(&ItemKey::Lyrics))
+            .and_then(|text| FileLyrics::from_text(text, None)))
+    }
+
+    /// Replace the file's lyrics; None removes them. Synced lyrics are written as SYLT plus a
+    /// plain USLT frame in MP3s, and as LRC text in the LYRICS field elsewhere.
+    pub fn write_lyrics(&self, file_path: &str, file_lyrics: Option<&FileLyrics>) -> Result<(), Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+
+        if file_path.to_lowercase().ends_with(".mp3") {
+            let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+            tag.remove_all_lyrics();
+            tag.remove_all_synchronised_lyrics();
+
+            if let Some(file_lyrics) = file_lyrics {
+                let lang = file_lyrics.language.clone().unwrap_or_else(|| "eng".to_string());
+                tag.add_frame(id3::frame::Lyrics {
+                    lang: lang.clone(),
+                    description: String::new(),
+                    text: file_lyrics.text.clone(),
+                });
+                if !file_lyrics.lines.is_empty() {
+                    tag.add_frame(SynchronisedLyrics {
+                        lang,
+                        timestamp_format: TimestampFormat::Ms,
+                        content_type: SynchronisedLyricsType::Lyrics,
+                        description: String::new(),
+                        content: file_lyrics
+                            .lines
+                            .iter()
+                            .map(|line| ((line.time * 1000.0).round() as u32, line.text.clone()))
+                            .collect(),
+                    });
+                }
+            }
+
+            return self.save_tags(
+                file_path,
+                |temp| Ok(tag.write_to_path(temp, write_version(&tag))?),
+                |temp| self.read_lyrics(temp).map(|_| ()),
+            );
+        }
+
+        let mut parsed_file = lofty::read_from(&mut File::open(file_path)?)?;
+        if parsed_file.primary_tag().is_none() {
+            parsed_file.insert_tag(lofty::tag::Tag::new(parsed_file.primary_tag_type()));
+        }
+        if let Some(tag) = parsed_file.primary_tag_mut() {
+            tag.remove_key(&ItemKey::Lyrics);
+            if let Some(file_lyrics) = file_lyrics {
+                let text = if file_lyrics.lines.is_empty() {
+                    file_lyrics.text.clone()
+                } else {
+                    lyrics::to_lrc(&file_lyrics.lines)
+                };
+                tag.insert_text(ItemKey::Lyrics, text);
+            }
+
+            self.save_tags(
+                file_path,
+                |temp| save_lofty_file(&parsed_file, temp),
+                |temp| self.read_lyrics(temp).map(|_| ()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Serato's cues, loops and beatgrid tempo from the `Serato Markers2` and `Serato BeatGrid`
+    /// GEOB frames of an MP3. `None` for files without them.
+    pub fn read_serato(&self, file_path: &str) -> Result<Option<SeratoData>, Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+        if !file_path.to_lowercase().ends_with(".mp3") {
+            return Ok(None);
+        }
+
+        let tag = match Tag::read_from_path(file_path) {
+            Ok(tag) => tag,
+            Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let object = |description: &str| {
+            tag.encapsulated_objects()
+                .find(|o| o.description == description)
+                .map(|o| o.data.as_slice())
+        };
+        let markers = object(serato::MARKERS2);
+        let beatgrid = object(serato::BEATGRID);
+        if markers.is_none() && beatgrid.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(SeratoData {
+            cues: markers.map(serato::parse_markers2).transpose()?.unwrap_or_default(),
+            bpm: beatgrid.and_then(serato::parse_beatgrid),
+        }))
+    }
+
+    /// Replace the cues and loops in an MP3's `Serato Markers2` frame, keeping its track color.
+    pub fn write_serato_cues(&self, file_path: &str, cues: &[DjCue]) -> Result<(), Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+        if !file_path.to_lowercase().ends_with(".mp3") {
+            return Err("Serato markers can only be written to MP3 files".into());
+        }
+
+        let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+        let existing = tag
+            .encapsulated_objects()
+            .find(|o| o.description == serato::MARKERS2)
+            .map(|o| o.data.clone());
+        tag.remove_encapsulated_object(Some(serato::MARKERS2), None, None, None);
+        tag.add_frame(EncapsulatedObject {
+            mime_type: "application/octet-stream".to_string(),
+            filename: String::new(),
+            description: serato::MARKERS2.to_string(),
+            data: serato::render_markers2(cues, existing.as_deref()),
+        });
+
+        self.save_tags(
+            file_path,
+            |temp| Ok(tag.write_to_path(temp, write_version(&tag))?),
+            |temp| self.read_serato(temp).map(|_| ()),
+        )
+    }
+
+    /// The file's chapters: CHAP frames in MP3s, in CTOC order when there is one, and the Nero
+    /// chapter list in MP4s. Other formats have none.
+    pub fn read_chapters(&self, file_path: &str) -> Result<Vec<DjCue>, Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+
+        let lower = file_path.to_lowercase();
+        if is_mp4(&lower) {
+            return Ok(chapters::read_mp4(&std::fs::read(file_path)?)?);
+        }
+        if !lower.ends_with(".mp3") {
+            return Ok(Vec::new());
+        }
+
+        let tag = match Tag::read_from_path(file_path) {
+            Ok(tag) => tag,
+            Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut found: Vec<&id3::frame::Chapter> = tag.chapters().collect();
+        match tag.tables_of_contents().find(|toc| toc.top_level) {
+            Some(toc) => found.sort_by_key(|c| toc.elements.iter().position(|e| *e == c.element_id)),
+            None => found.sort_by_key(|c| c.start_time),
+        }
+
+        Ok(found
+            .into_iter()
+            .map(|chapter| DjCue {
+                start: chapter.start_time as f64 / 1000.0,
+                end: Some(chapter.end_time as f64 / 1000.0).filter(|_| chapter.end_time > chapter.start_time),
+                name: chapter
+                    .frames
+                    .iter()
+                    .find(|f| f.id() == "TIT2")
+                    .and_then(|f| f.content().text())
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty()),
+                color: None,
+                hot_cue: None,
+            })
+            .collect())
+    }
+
+    /// Replace the file's chapters with `sections` (see `chapters::sections`): CHAP frames under
+    /// one ordered CTOC in MP3s, the Nero chapter list in MP4s.
+    pub fn write_chapters(&self, file_path: &str, sections: &[DjCue]) -> Result<(), Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+
+        let lower = file_path.to_lowercase();
+        if is_mp4(&lower) {
+            return self.save_tags(
+                file_path,
+                |temp| {
+                    let data = chapters::write_mp4(&std::fs::read(temp)?, sections)?;
+                    Ok(std::fs::write(temp, data)?)
+                },
+                |temp| self.read_chapters(temp).map(|_| ()),
+            );
+        }
+        if !lower.ends_with(".mp3") {
+            return Err("Chapters can only be written to MP3 and MP4 files".into());
+        }
+
+        let mut tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+        tag.remove_all_chapters();
+        tag.remove_all_tables_of_contents();
+        let element_ids: Vec<String> = (0..sections.len()).map(|i| format!("chp{}", i)).collect();
+        for (section, element_id) in sections.iter().zip(&element_ids) {
+            let millis = |seconds: f64| (seconds.max(0.0) * 1000.0).round() as u32;
+            tag.add_frame(id3::frame::Chapter {
+                element_id: element_id.clone(),
+                start_time: millis(section.start),
+                end_time: millis(section.end.unwrap_or(section.start)),
+                start_offset: u32::MAX,
+                end_offset: u32::MAX,
+                frames: section
+                    .name
+                    .iter()
+                    .map(|name| Frame::text("TIT2", name.clone()))
+                    .collect(),
+            });
+        }
+        if !sections.is_empty() {
+            tag.add_frame(id3::frame::TableOfContents {
+                element_id: "toc".to_string(),
+                top_level: true,
+                ordered: true,
+                elements: element_ids,
+                frames: Vec::new(),
+            });
+        }
+
+        self.save_tags(
+            file_path,
+            |temp| Ok(tag.write_to_path(temp, write_version(&tag))?),
+            |temp| self.read_chapters(temp).map(|_| ()),
+        )
+    }
+
+    /// Everything in the file's tag, for the original tag snapshot. `None` if it has no tag.
+    pub fn read_tag_snapshot(&self, file_path: &str) -> Result<Option<TagSnapshot>, Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+
+        if file_path.to_lowercase().ends_with(".mp3") {
+            let tag = match Tag::read_from_path(file_path) {
+                Ok(tag) => tag,
+                Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            let version = write_version(&tag);
+            let mut raw = Vec::new();
+            tag.write_to(&mut raw, version)?;
+
+            let frames = tag
+                .frames()
+                .map(|frame| TagFrame {
+                    id: frame.id().to_string(),
+                    value: frame.content().to_string(),
+                })
+                .collect();
+            return Ok(Some(TagSnapshot {
+                format: version.to_string(),
+                frames,
+                raw: Some(raw),
+            }));
+        }
+
+        let parsed_file = lofty::read_from(&mut File::open(file_path)?)?;
+        let Some(tag) = parsed_file.primary_tag() else {
+            return Ok(None);
+        };
+        let tag_type = tag.tag_type();
+        let frames = tag
+            .items()
+            .filter(|item| snapshot_item(item, tag_type))
+            .filter_map(|item| {
+                Some(TagFrame {
+                    id: item.key().map_key(tag_type, true)?.to_string(),
+                    value: item.value().text()?.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(Some(TagSnapshot {
+            format: format!("{:?}", tag_type),
+            frames,
+            raw: None,
+        }))
+    }
+
+    /// Put a file's tag back the way a snapshot has it: the exact ID3v2 tag for MP3s, the text
+    /// items for other formats (their pictures and binary items are left as they are).
+    pub fn restore_tag_snapshot(&self, file_path: &str, snapshot: &TagSnapshot) -> Result<(), Box<dyn std::error::Error>> {
+        if !Path::new(file_path).exists() {
+            return Err("File does not exist".into());
+        }
+
+        if let Some(raw) = &snapshot.raw {
+            let tag = Tag::read_from2(Cursor::new(raw))?;
+            return self.save_tags(
+                file_path,
+                |temp| Ok(tag.write_to_path(temp, write_version(&tag))?),
+                |temp| Tag::read_from_path(temp).map(|_| ()).map_err(|e| e.into()),
+            );
+        }
+
+        let mut parsed_file = lofty::read_from(&mut File::open(file_path)?)?;
+        if parsed_file.primary_tag().is_none() {
+            parsed_file.insert_tag(lofty::tag::Tag::new(parsed_file.primary_tag_type()));
+        }
+        if let Some(tag) = parsed_file.primary_tag_mut() {
+            // Items the snapshot couldn't hold (binary values, keys with no name in this format)
+            // stay as they are.
+            let tag_type = tag.tag_type();
+            tag.retain(|item| !snapshot_item(item, tag_type));
+            for frame in &snapshot.frames {
+                let key = ItemKey::from_key(tag_type, &frame.id);
+                push_text(tag, key, frame.value.clone());
+            }
+
+            self.save_tags(
+                file_path,
+                |temp| save_lofty_file(&parsed_file, temp),
+                |temp| self.read_with_lofty(temp).map(|_| ()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite a file's tags through a temp copy that replaces the file only once `write` and
+    /// (when enabled) `verify` succeed on it, backing up the tag block first when enabled.
+    fn save_tags(
+        &self,
+        file_path: &str,
+        write: impl FnOnce(&Path) -> Result<(), Box<dyn std::error::Error>>,
+        verify: impl FnOnce(&str) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Path::new(file_path);
+        if self.writes.backup {
+            let backup_dir = self.backup_dir().ok_or("No directory for tag backups")?;
+            atomic_write::backup_tag_block(path, &backup_dir)?;
+        }
+
+        atomic_write::replace_file(path, |temp| {
+            write(temp)?;
+            if self.writes.verify {
+                let temp = temp.to_str().ok_or("Invalid file path")?;
+                verify(temp).map_err(|e| format!("Written tags failed to read back: {}", e))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn backup_dir(&self) -> Option<PathBuf> {
+        match &self.writes.backup_dir {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => dirs::data_local_dir().map(|dir| dir.join("nagan").join("tag-backups")),
+        }
+    }
+
+    /// Values of an ID3 text frame. In ID3v2.3 the id3 crate reads "/" in TPE1 and TCOM as a
+    /// value separator; it's put back ("AC/DC") unless "/" is one of the configured separators.
+    fn id3_text_values(&self, tag: &Tag, id: &str) -> Vec<String> {
+        let Some(text) = tag.get(id).and_then(|f| f.content().text()) else {
+            return vec![];
+        };
+        let text = if tag.version() == id3::Version::Id3v24 {
+            text.to_string()
+        } else {
+            text.replace('\0', "/")
+        };
+        split_values(std::iter::once(text.as_str()), &self.multi_value.split_on)
+    }
+
+    /// Separator between values in an ID3 frame: a null byte in ID3v2.4, the configured one before.
+    fn id3_separator(&self, version: id3::Version) -> &str {
+        match version {
+            id3::Version::Id3v24 => "\0",
+            _ => &self.multi_value.separator,
+        }
+    }
+
+    /// Replace a field's values: repeated fields in Vorbis comments and MP4, null-separated in
+    /// APE and ID3v2, joined with the configured separator elsewhere. An empty list removes it.
+    fn set_lofty_values(&self, tag: &mut lofty::tag::Tag, key: ItemKey, values: &[String]) {
+        tag.remove_key(&key);
+        let values = match tag.tag_type() {
+            TagType::VorbisComments | TagType::Mp4Ilst => values.to_vec(),
+            _ if values.is_empty() => Vec::new(),
+            TagType::Ape | TagType::Id3v2 => vec![values.join("\0")],
+            _ => vec![values.join(&self.multi_value.separator)],
+        };
+        for value in values {
+            push_text(tag, key.clone(), value);
+        }
+    }
+
+    /// Replace a custom text field (a Vorbis/APE comment or MP4 freeform atom); an empty list removes it.
+    fn set_custom_field(&self, tag: &mut lofty::tag::Tag, field: &str, values: &[String]) {
+        let key = custom_key(tag.tag_type(), field);
+        self.set_lofty_values(tag, key, values);
+    }
+
     fn get_file_duration(&self, _file_path: &str) -> Result<f64, Box<dyn std::error::Error>> {
         // For now, return 0.0 as duration extraction requires audio processing
         // In a real implementation, you might use a library like symphonia
@@ -335,15 +1162,306 @@ impl Id3Manager {
     }
 }
 
+/// Where an extended text field lives in ID3v2.
+enum Id3Field {
+    Text(&'static str),
+    /// A TXXX frame with this description.
+    Extended(&'static str),
+    /// The MusicBrainz UFID frame.
+    MusicBrainzUfid,
+}
+
+const MUSICBRAINZ_UFID_OWNER: &str = "http://musicbrainz.org";
+
+/// The ID3v2 frame and lofty item key of an extended text field. lofty maps the item key to
+/// the matching Vorbis comment, MP4 atom or APE item, using the names Picard and foobar2000 write.
+fn extended_field(field: &str) -> Option<(Id3Field, ItemKey)> {
+    use Id3Field::{Extended, MusicBrainzUfid, Text};
+    Some(match field {
+        "composer" => (Text("TCOM"), ItemKey::Composer),
+        "conductor" => (Text("TPE3"), ItemKey::Conductor),
+        "label" => (Text("TPUB"), ItemKey::Label),
+        "isrc" => (Text("TSRC"), ItemKey::Isrc),
+        "catalog_number" => (Extended("CATALOGNUMBER"), ItemKey::CatalogNumber),
+        "grouping" => (Text("TIT1"), ItemKey::ContentGroup),
+        "initial_key" => (Text("TKEY"), ItemKey::InitialKey),
+        "title_sort" => (Text("TSOT"), ItemKey::TrackTitleSortOrder),
+        "artist_sort" => (Text("TSOP"), ItemKey::TrackArtistSortOrder),
+        "album_sort" => (Text("TSOA"), ItemKey::AlbumTitleSortOrder),
+        "album_artist_sort" => (Text("TSO2"), ItemKey::AlbumArtistSortOrder),
+        "composer_sort" => (Text("TSOC"), ItemKey::ComposerSortOrder),
+        "musicbrainz_recording_id" => (MusicBrainzUfid, ItemKey::MusicBrainzRecordingId),
+        "musicbrainz_track_id" => (Extended("MusicBrainz Release Track Id"), ItemKey::MusicBrainzTrackId),
+        "musicbrainz_album_id" => (Extended("MusicBrainz Album Id"), ItemKey::MusicBrainzReleaseId),
+        "musicbrainz_artist_id" => (Extended("MusicBrainz Artist Id"), ItemKey::MusicBrainzArtistId),
+        "musicbrainz_album_artist_id" => (
+            Extended("MusicBrainz Album Artist Id"),
+            ItemKey::MusicBrainzReleaseArtistId,
+        ),
+        "musicbrainz_release_group_id" => (
+            Extended("MusicBrainz Release Group Id"),
+            ItemKey::MusicBrainzReleaseGroupId,
+        ),
+        _ => return None,
+    })
+}
+
+/// A BPM tag value; taggers write whole numbers ("128") or decimals ("127.5").
+fn parse_bpm(value: &str) -> Option<f32> {
+    value.trim().parse().ok().filter(|bpm: &f32| *bpm > 0.0)
+}
+
+/// The year at the start of a date ("1999", "1999-05-01").
+fn leading_year(value: &str) -> Option<i32> {
+    value.trim().get(..4)?.parse().ok()
+}
+
+fn read_extended_id3(tag: &Tag) -> ExtendedTags {
+    let mut extended = ExtendedTags::default();
+    for field in ExtendedTags::TEXT_FIELDS {
+        let Some((location, _)) = extended_field(field) else {
+            continue;
+        };
+        let value = match location {
+            // iTunes writes grouping to GRP1 rather than TIT1.
+            Id3Field::Text("TIT1") => tag
+                .get("TIT1")
+                .or_else(|| tag.get("GRP1"))
+                .and_then(|f| f.content().text())
+                .map(|v| v.to_string()),
+            Id3Field::Text(id) => tag.get(id).and_then(|f| f.content().text()).map(|v| v.to_string()),
+            Id3Field::Extended(description) => tag
+                .extended_texts()
+                .find(|t| t.description.eq_ignore_ascii_case(description))
+                .map(|t| t.value.clone()),
+            Id3Field::MusicBrainzUfid => tag
+                .unique_file_identifiers()
+                .find(|u| u.owner_identifier == MUSICBRAINZ_UFID_OWNER)
+                .map(|u| String::from_utf8_lossy(&u.identifier).into_owned()),
+        };
+        // Several values are null-separated in ID3v2.4; in ID3v2.3 the id3 crate reads "/" in
+        // TCOM as one, so put it back.
+        let separator = if tag.version() == id3::Version::Id3v24 { "; " } else { "/" };
+        extended.set_text(field, value.map(|v| v.replace('\0', separator)).as_deref());
+    }
+
+    // TDOR in ID3v2.4, TORY in ID3v2.3
+    extended.original_year = ["TDOR", "TORY"]
+        .iter()
+        .find_map(|id| tag.get(id).and_then(|f| f.content().text()))
+        .and_then(leading_year);
+    extended
+}
+
+/// Write the extended fields that have a value and remove the frames of the `cleared` ones.
+fn write_extended_id3(tag: &mut Tag, extended: &ExtendedTags, version: id3::Version, cleared: &[&str]) {
+    for field in ExtendedTags::TEXT_FIELDS {
+        let Some((location, _)) = extended_field(field) else {
+            continue;
+        };
+        let value = extended.text(field);
+        if value.is_none() && !cleared.contains(&field) {
+            continue;
+        }
+        match location {
+            Id3Field::Text(id) => match value {
+                Some(value) => tag.set_text(id, value),
+                None => {
+                    tag.remove(id);
+                    // Grouping is read from GRP1 when TIT1 is missing.
+                    if id == "TIT1" {
+                        tag.remove("GRP1");
+                    }
+                }
+            },
+            Id3Field::Extended(description) => {
+                let values: Vec<String> = value.map(str::to_string).into_iter().collect();
+                set_extended_text(tag, description, &values, "; ")
+            }
+            Id3Field::MusicBrainzUfid => {
+                tag.remove_unique_file_identifier_by_owner_identifier(MUSICBRAINZ_UFID_OWNER);
+                if let Some(value) = value {
+                    tag.add_frame(UniqueFileIdentifier {
+                        owner_identifier: MUSICBRAINZ_UFID_OWNER.to_string(),
+                        identifier: value.as_bytes().to_vec(),
+                    });
+                }
+            }
+        }
+    }
+
+    // TDOR is ID3v2.4 only; ID3v2.3 has TORY.
+    let (frame, other) = match version {
+        id3::Version::Id3v24 => ("TDOR", "TORY"),
+        _ => ("TORY", "TDOR"),
+    };
+    match extended.original_year {
+        Some(original_year) => {
+            tag.remove(other);
+            tag.set_text(frame, original_year.to_string());
+        }
+        None if cleared.contains(&"original_year") => {
+            tag.remove(other);
+            tag.remove(frame);
+        }
+        None => {}
+    }
+}
+
+fn read_extended_lofty(tag: &lofty::tag::Tag) -> ExtendedTags {
+    let mut extended = ExtendedTags::default();
+    for field in ExtendedTags::TEXT_FIELDS {
+        if let Some((_, key)) = extended_field(field) {
+            let mut values: Vec<&str> = tag.get_strings(&key).collect();
+            // lofty reads iTunes' GRP1 grouping frame as a key of its own.
+            if values.is_empty() && key == ItemKey::ContentGroup {
+                values = tag.get_strings(&ItemKey::AppleId3v2ContentGroup).collect();
+            }
+            extended.set_text(field, Some(&values.join("; ")));
+        }
+    }
+    extended.original_year = tag
+        .get_string(&ItemKey::OriginalReleaseDate)
+        .and_then(leading_year);
+    extended
+}
+
+/// Write the extended fields that have a value and remove the items of the `cleared` ones.
+fn write_extended_lofty(tag: &mut lofty::tag::Tag, extended: &ExtendedTags, cleared: &[&str]) {
+    for field in ExtendedTags::TEXT_FIELDS {
+        let Some((_, key)) = extended_field(field) else {
+            continue;
+        };
+        match extended.text(field) {
+            Some(value) => {
+                tag.insert_text(key, value.to_string());
+            }
+            None if cleared.contains(&field) => {
+                if key == ItemKey::ContentGroup {
+                    tag.remove_key(&ItemKey::AppleId3v2ContentGroup);
+                }
+                tag.remove_key(&key);
+            }
+            None => {}
+        }
+    }
+    match extended.original_year {
+        Some(original_year) => {
+            tag.insert_text(ItemKey::OriginalReleaseDate, original_year.to_string());
+        }
+        None if cleared.contains(&"original_year") => tag.remove_key(&ItemKey::OriginalReleaseDate),
+        None => {}
+    }
+}
+
+/// Replace the TXXX frame(s) named `field`, joining the values with `separator`; an empty list
+/// removes them.
+fn set_extended_text(tag: &mut Tag, field: &str, values: &[String], separator: &str) {
+    let existing: Vec<String> = tag
+        .extended_texts()
+        .filter(|t| t.description.eq_ignore_ascii_case(field))
+        .map(|t| t.description.clone())
+        .collect();
+    for description in existing {
+        tag.remove_extended_text(Some(&description), None);
+    }
+
+    if !values.is_empty() {
+        tag.add_frame(ExtendedText {
+            description: field.to_string(),
+            value: values.join(separator),
+        });
+    }
+}
+
+/// Replace our POPM frame, keeping other users' ones. A `rating` or `play_count` of None keeps
+/// the frame's.
+fn set_popularimeter(tag: &mut Tag, user: &str, rating: Option<u8>, play_count: Option<u64>) {
+    let (ours, others): (Vec<Popularimeter>, Vec<Popularimeter>) = tag
+        .frames()
+        .filter_map(|f| f.content().popularimeter())
+        .cloned()
+        .partition(|p| p.user == user);
+    tag.remove("POPM");
+    for popm in others {
+        tag.add_frame(Frame::with_content("POPM", Content::Popularimeter(popm)));
+    }
+
+    let counter = play_count
+        .or_else(|| ours.first().map(|p| p.counter))
+        .unwrap_or(0);
+    let rating = rating
+        .or_else(|| ours.first().map(|p| p.rating))
+        .unwrap_or(0);
+    if rating != 0 || counter != 0 {
+        tag.add_frame(Frame::with_content(
+            "POPM",
+            Content::Popularimeter(Popularimeter {
+                user: user.to_string(),
+                rating,
+                counter,
+            }),
+        ));
+    }
+}
+
+/// The item key for a custom field: the field name itself, or an iTunes freeform atom in MP4.
+fn custom_key(tag_type: TagType, field: &str) -> ItemKey {
+    match tag_type {
+        TagType::Mp4Ilst => ItemKey::Unknown(format!("----:com.apple.iTunes:{}", field)),
+        _ => ItemKey::Unknown(field.to_string()),
+    }
+}
+
+/// Add a text item. `Tag::push` drops keys lofty has no mapping for, which custom fields are.
+fn push_text(tag: &mut lofty::tag::Tag, key: ItemKey, value: String) {
+    let item = TagItem::new(key, ItemValue::Text(value));
+    if matches!(item.key(), ItemKey::Unknown(_)) {
+        tag.push_unchecked(item);
+    } else {
+        tag.push(item);
+    }
+}
+
+/// Whether a tag snapshot holds the item: text items with a name in the tag's format.
+fn snapshot_item(item: &TagItem, tag_type: TagType) -> bool {
+    item.value().text().is_some() && item.key().map_key(tag_type, true).is_some()
+}
+
+/// The item key for the RATING field. lofty reads a Vorbis RATING comment as its popularimeter
+/// item; elsewhere it's a custom field.
+fn rating_key(tag_type: TagType) -> ItemKey {
+    if ItemKey::Popularimeter.map_key(tag_type, false) == Some(RATING_FIELD) {
+        ItemKey::Popularimeter
+    } else {
+        custom_key(tag_type, RATING_FIELD)
+    }
+}
+
+fn custom_key_matches(key: &ItemKey, field: &str) -> bool {
+    match key {
+        ItemKey::Unknown(key) => key
+            .rsplit(':')
+            .next()
+            .is_some_and(|name| name.eq_ignore_ascii_case(field)),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::commands::{
-        bulk_update_id3_tags, bulk_update_songs_inner, extract_metadata,
-        read_id3_tags, update_id3_tags,
+        bulk_update_id3_tags, bulk_update_songs_inner, extract_metadata, read_id3_tags,
+        replay_edit, resolve_drift_inner, revert_to_original_inner, scan_drift_inner,
+        update_id3_tags,
     };
+    use crate::covers::CoverCache;
     use crate::database::Database;
-    use crate::models::{BulkUpdateSongsPayload, Song, SongMetadata};
+    use crate::models::{
+        BulkUpdateSongsPayload, DriftResolution, ResolveDriftPayload, RevertToOriginalPayload,
+        Song, SongMetadata, SongUpdateResult, TagFailurePolicy, UpdateSongPayload,
+    };
     use serde_json;
 
     fn create_test_metadata() -> SongMetadata {
@@ -352,7 +1470,6 @@ mod tests {
             album: "Test Album".to_string(),
             year: Some(2023),
             track: Some(1),
-            image: None,
             duration: 180.0,
             artists: vec!["Test Artist".to_string(), "Second Artist".to_string()],
             instruments: Some(vec!["Guitar".to_string(), "Drums".to_string()]),
@@ -362,6 +1479,7 @@ mod tests {
             tags: vec!["test".to_string(), "demo".to_string()],
             file_exists: true,
             times_played: 5,
+            ..Default::default()
         }
     }
 
@@ -463,6 +1581,546 @@ mod tests {
         assert_eq!(json_value["bpm"], 120.0);
     }
 
+    #[test]
+    fn test_instruments_and_tags_round_trip_through_custom_fields() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()));
+        File::create(&path).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let manager = Id3Manager::from_settings(&AppSettings {
+            tag_fields: TagFieldSettings {
+                instruments: "MY_INSTRUMENTS".to_string(),
+                tags: "MY_TAGS".to_string(),
+            },
+            ..AppSettings::default()
+        });
+        manager.write_metadata(&path, &create_test_metadata()).unwrap();
+
+        let metadata = manager.read_metadata(&path).unwrap();
+        assert_eq!(
+            metadata.instruments,
+            Some(vec!["Guitar".to_string(), "Drums".to_string()])
+        );
+        assert_eq!(metadata.tags, vec!["test".to_string(), "demo".to_string()]);
+
+        // Other field names don't see them; clearing the tags removes the frame.
+        let metadata = Id3Manager::new().read_metadata(&path).unwrap();
+        assert_eq!(metadata.instruments, None);
+        assert!(metadata.tags.is_empty());
+
+        let mut cleared = create_test_metadata();
+        cleared.tags = vec![];
+        manager.write_metadata(&path, &cleared).unwrap();
+        assert!(manager.read_metadata(&path).unwrap().tags.is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    fn atom(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut atom = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        atom.extend_from_slice(kind);
+        atom.extend_from_slice(body);
+        atom
+    }
+
+    /// A one-second, untagged M4A with a single AAC track.
+    fn m4a_fixture() -> Vec<u8> {
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&1000u32.to_be_bytes());
+        let mut mdhd = vec![0u8; 24];
+        mdhd[12..16].copy_from_slice(&44100u32.to_be_bytes());
+        mdhd[16..20].copy_from_slice(&44100u32.to_be_bytes());
+        let mut hdlr = vec![0u8; 25];
+        hdlr[8..12].copy_from_slice(b"soun");
+        let mut mp4a = vec![0u8; 28];
+        mp4a[16..18].copy_from_slice(&2u16.to_be_bytes());
+        mp4a[22..26].copy_from_slice(&(44100u32 << 16).to_be_bytes());
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend(atom(b"mp4a", &mp4a));
+
+        let minf = atom(b"minf", &atom(b"stbl", &atom(b"stsd", &stsd)));
+        let mut mdia = atom(b"mdhd", &mdhd);
+        mdia.extend(atom(b"hdlr", &hdlr));
+        mdia.extend(minf);
+        let mut moov = atom(b"mvhd", &mvhd);
+        moov.extend(atom(b"trak", &atom(b"mdia", &mdia)));
+
+        let mut data = atom(b"ftyp", b"M4A \0\0\0\0M4A ");
+        data.extend(atom(b"moov", &moov));
+        data.extend(atom(b"mdat", &[0u8; 16]));
+        data
+    }
+
+    /// Half a second of silent MPEG-1 Layer III frames, untagged.
+    fn mp3_fixture() -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        frame.repeat(20)
+    }
+
+    /// An untagged FLAC with one frame, and padding for a tag to take.
+    fn flac_fixture() -> Vec<u8> {
+        let mut streaminfo = [0u8; 34];
+        streaminfo[0..2].copy_from_slice(&4096u16.to_be_bytes());
+        streaminfo[2..4].copy_from_slice(&4096u16.to_be_bytes());
+        // Sample rate, channels - 1, bits per sample - 1, total samples.
+        let packed: u64 = (44100 << 44) | (1 << 41) | (15 << 36) | 44100;
+        streaminfo[10..18].copy_from_slice(&packed.to_be_bytes());
+
+        let mut data = b"fLaC".to_vec();
+        data.extend_from_slice(&[0x00, 0, 0, 34]);
+        data.extend_from_slice(&streaminfo);
+        data.extend_from_slice(&[0x81, 0, 0, 64]);
+        data.extend_from_slice(&[0u8; 64]);
+        data.extend_from_slice(&[0xff, 0xf8, 0x69, 0x08, 0, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_instruments_and_tags_round_trip_through_mp4_freeform_atoms() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.m4a", uuid::Uuid::new_v4()));
+        std::fs::write(&path, m4a_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let manager = Id3Manager::new();
+        manager.write_metadata(&path, &create_test_metadata()).unwrap();
+        let metadata = manager.read_metadata(&path).unwrap();
+        assert_eq!(
+            metadata.instruments,
+            Some(vec!["Guitar".to_string(), "Drums".to_string()])
+        );
+        assert_eq!(metadata.tags, vec!["test".to_string(), "demo".to_string()]);
+
+        // They're iTunes freeform atoms, one per value.
+        let file = lofty::read_from_path(&path).unwrap();
+        let tag = file.primary_tag().unwrap();
+        let atom = ItemKey::Unknown("----:com.apple.iTunes:INSTRUMENTS".to_string());
+        assert_eq!(tag.get_strings(&atom).collect::<Vec<_>>(), ["Guitar", "Drums"]);
+
+        let mut cleared = create_test_metadata();
+        cleared.tags = vec![];
+        manager.write_metadata(&path, &cleared).unwrap();
+        let metadata = manager.read_metadata(&path).unwrap();
+        assert!(metadata.tags.is_empty());
+        assert_eq!(metadata.instruments.map(|i| i.len()), Some(2));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_rating_and_play_count_round_trip_through_popm() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()));
+        std::fs::write(&path, mp3_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        // Another player's rating is left alone.
+        let mut tag = Tag::new();
+        tag.add_frame(Frame::with_content(
+            "POPM",
+            Content::Popularimeter(Popularimeter {
+                user: "other@example.com".to_string(),
+                rating: 64,
+                counter: 2,
+            }),
+        ));
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let manager = Id3Manager::from_settings(&AppSettings {
+            ratings: RatingSettings {
+                popm_email: "me@example.com".to_string(),
+                sync_play_count: true,
+            },
+            ..AppSettings::default()
+        });
+        assert_eq!(manager.read_metadata(&path).unwrap().rating, Some(2.0));
+
+        let mut metadata = create_test_metadata();
+        metadata.rating = Some(3.5);
+        manager.write_metadata(&path, &metadata).unwrap();
+
+        let read = manager.read_metadata(&path).unwrap();
+        assert_eq!(read.rating, Some(3.5));
+        assert_eq!(read.times_played, 5);
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        let users: Vec<(String, u8)> = tag
+            .frames()
+            .filter_map(|f| f.content().popularimeter())
+            .map(|p| (p.user.clone(), p.rating))
+            .collect();
+        assert_eq!(users.len(), 2);
+        assert!(users.contains(&("other@example.com".to_string(), 64)));
+        assert!(users.contains(&("me@example.com".to_string(), 186)));
+
+        // The file has audio, so lofty read it; the rating still came from POPM.
+        assert!(read.duration > 0.0);
+
+        // An unknown rating keeps ours; clearing it zeroes our frame's rating byte.
+        metadata.rating = None;
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().rating, Some(3.5));
+        manager.write_metadata_clearing(&path, &metadata, &["rating"]).unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        let ours = tag
+            .frames()
+            .filter_map(|f| f.content().popularimeter())
+            .find(|p| p.user == "me@example.com")
+            .unwrap();
+        assert_eq!((ours.rating, ours.counter), (0, 5));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_rating_round_trips_through_vorbis_rating_comment() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.flac", uuid::Uuid::new_v4()));
+        std::fs::write(&path, flac_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let mut file = lofty::read_from_path(&path).unwrap();
+        let mut tag = lofty::tag::Tag::new(TagType::VorbisComments);
+        tag.set_title("Rated".to_string());
+        tag.insert_text(ItemKey::Popularimeter, "60".to_string());
+        file.insert_tag(tag);
+        file.save_to_path(&path, WriteOptions::default()).unwrap();
+
+        let manager = Id3Manager::new();
+        assert_eq!(manager.read_metadata(&path).unwrap().rating, Some(3.0));
+
+        let mut metadata = create_test_metadata();
+        metadata.rating = Some(4.5);
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().rating, Some(4.5));
+
+        // The comment is replaced, not written a second time.
+        let data = std::fs::read(&path).unwrap();
+        let count = data.windows(7).filter(|w| w == b"RATING=").count();
+        assert_eq!(count, 1);
+
+        // An unknown rating leaves the comment alone; clearing it removes it.
+        metadata.rating = None;
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().rating, Some(4.5));
+        manager.write_metadata_clearing(&path, &metadata, &["rating"]).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().rating, None);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_write_cover_leaves_other_tags_alone() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()));
+        File::create(&path).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let mut tag = Tag::new();
+        tag.set_title("Own Title");
+        tag.set_text("TKEY", "Am");
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let manager = Id3Manager::new();
+        manager
+            .write_cover(&path, &format!("data:image/png;base64,{}", BASE64.encode(b"png")))
+            .unwrap();
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("Own Title"));
+        assert_eq!(tag.get("TKEY").and_then(|f| f.content().text()), Some("Am"));
+        assert!(tag.get("TPE1").is_none());
+        let picture = tag.pictures().next().unwrap();
+        assert_eq!((picture.mime_type.as_str(), picture.data.as_slice()), ("image/png", &b"png"[..]));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_write_cover_adds_a_tag_to_untagged_files() {
+        let manager = Id3Manager::new();
+        for (extension, data) in [("flac", flac_fixture()), ("m4a", m4a_fixture())] {
+            let path = std::env::temp_dir().join(format!("nagan-test-{}.{}", uuid::Uuid::new_v4(), extension));
+            std::fs::write(&path, data).unwrap();
+            let path = path.to_str().unwrap().to_string();
+
+            manager
+                .write_cover(&path, &format!("data:image/png;base64,{}", BASE64.encode(b"png")))
+                .unwrap();
+            let file = lofty::read_from_path(&path).unwrap();
+            let pictures = file.primary_tag().map(|tag| tag.pictures()).unwrap_or_default();
+            assert_eq!(pictures.len(), 1, "{}", extension);
+            assert_eq!(pictures[0].data(), b"png", "{}", extension);
+
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    #[test]
+    fn test_extended_tags_round_trip_through_id3_frames() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()));
+        std::fs::write(&path, mp3_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        // An ID3v2.3 tag as older taggers write it.
+        let mut tag = Tag::new();
+        tag.set_title("Old");
+        tag.set_text("TORY", "1969");
+        tag.set_text("GRP1", "Side A");
+        tag.set_text("TKEY", "Am");
+        tag.add_frame(ExtendedText {
+            description: "MusicBrainz Album Id".to_string(),
+            value: "album-mbid".to_string(),
+        });
+        tag.write_to_path(&path, id3::Version::Id3v23).unwrap();
+
+        let manager = Id3Manager::new();
+        let read = manager.read_metadata(&path).unwrap().extended;
+        assert_eq!(read.original_year, Some(1969));
+        assert_eq!(read.grouping.as_deref(), Some("Side A"));
+        assert_eq!(read.initial_key.as_deref(), Some("Am"));
+        assert_eq!(read.musicbrainz_album_id.as_deref(), Some("album-mbid"));
+
+        let mut metadata = create_test_metadata();
+        metadata.extended = ExtendedTags {
+            composer: Some("Composer".to_string()),
+            isrc: Some("USABC1234567".to_string()),
+            catalog_number: Some("CAT-001".to_string()),
+            artist_sort: Some("Artist, The".to_string()),
+            musicbrainz_recording_id: Some("recording-mbid".to_string()),
+            original_year: Some(1970),
+            ..read
+        };
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().extended, metadata.extended);
+
+        // The tag stays ID3v2.3, so the original year goes to TORY.
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.version(), id3::Version::Id3v23);
+        assert_eq!(tag.get("TCOM").and_then(|f| f.content().text()), Some("Composer"));
+        assert_eq!(tag.get("TORY").and_then(|f| f.content().text()), Some("1970"));
+        assert!(tag.get("TDOR").is_none());
+        let ufid = tag.unique_file_identifiers().next().unwrap();
+        assert_eq!(ufid.owner_identifier, MUSICBRAINZ_UFID_OWNER);
+        assert_eq!(ufid.identifier, b"recording-mbid");
+
+        // Fields with no value that the edit didn't clear keep their frames.
+        let written = metadata.extended.clone();
+        metadata.extended = ExtendedTags::default();
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().extended, written);
+
+        // Clearing the fields removes their frames, GRP1 included.
+        let mut cleared: Vec<&str> = ExtendedTags::TEXT_FIELDS.to_vec();
+        cleared.push("original_year");
+        manager.write_metadata_clearing(&path, &metadata, &cleared).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().extended, ExtendedTags::default());
+        let tag = Tag::read_from_path(&path).unwrap();
+        for id in ["TCOM", "TSRC", "TIT1", "GRP1", "TKEY", "TSOP", "TORY", "TDOR", "UFID"] {
+            assert!(tag.get(id).is_none(), "{} left behind", id);
+        }
+        let descriptions: Vec<&str> = tag.extended_texts().map(|t| t.description.as_str()).collect();
+        assert!(!descriptions.contains(&"CATALOGNUMBER"));
+        assert!(!descriptions.contains(&"MusicBrainz Album Id"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_extended_tags_round_trip_through_vorbis_comments() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.flac", uuid::Uuid::new_v4()));
+        std::fs::write(&path, flac_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let manager = Id3Manager::new();
+        let mut metadata = create_test_metadata();
+        metadata.extended = ExtendedTags {
+            composer: Some("Composer".to_string()),
+            grouping: Some("Side A".to_string()),
+            musicbrainz_album_id: Some("album-mbid".to_string()),
+            original_year: Some(1970),
+            ..ExtendedTags::default()
+        };
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().extended, metadata.extended);
+
+        metadata.extended.composer = None;
+        metadata.extended.original_year = None;
+        manager.write_metadata(&path, &metadata).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().extended.composer.as_deref(), Some("Composer"));
+
+        manager
+            .write_metadata_clearing(&path, &metadata, &["composer", "original_year"])
+            .unwrap();
+        let read = manager.read_metadata(&path).unwrap().extended;
+        assert_eq!(read, metadata.extended);
+        let data = std::fs::read(&path).unwrap();
+        assert!(!data.windows(9).any(|w| w == b"COMPOSER="));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_split_values_keeps_native_multiple_values() {
+        let split_on = vec![";".to_string()];
+        assert_eq!(split_values(["A; B ;"].into_iter(), &split_on), vec!["A", "B"]);
+        // Values that are already separate aren't split again.
+        assert_eq!(
+            split_values(["Earth; Wind\0Fire"].into_iter(), &split_on),
+            vec!["Earth; Wind", "Fire"]
+        );
+        assert_eq!(
+            split_values(["Earth; Wind", "Fire"].into_iter(), &split_on),
+            vec!["Earth; Wind", "Fire"]
+        );
+        assert_eq!(split_values(["AC/DC"].into_iter(), &split_on), vec!["AC/DC"]);
+    }
+
+    #[test]
+    fn test_multiple_values_round_trip_in_id3v23_and_id3v24() {
+        for version in [id3::Version::Id3v23, id3::Version::Id3v24] {
+            let path = std::env::temp_dir().join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()));
+            File::create(&path).unwrap();
+            let path = path.to_str().unwrap().to_string();
+
+            let mut tag = Tag::new();
+            tag.set_title("Song");
+            tag.write_to_path(&path, version).unwrap();
+
+            let manager = Id3Manager::new();
+            let mut metadata = create_test_metadata();
+            metadata.artists = vec!["AC/DC".to_string(), "Earth, Wind & Fire".to_string()];
+            metadata.genres = vec!["Rock".to_string(), "Funk".to_string()];
+            metadata.tags = vec!["live".to_string(), "loud".to_string()];
+            manager.write_metadata(&path, &metadata).unwrap();
+
+            let read = manager.read_metadata(&path).unwrap();
+            assert_eq!(read.artists, metadata.artists, "{:?}", version);
+            assert_eq!(read.genres, metadata.genres, "{:?}", version);
+            assert_eq!(read.tags, metadata.tags, "{:?}", version);
+
+            // Writing again what was read changes nothing.
+            manager.write_metadata(&path, &read).unwrap();
+            assert_eq!(manager.read_metadata(&path).unwrap().artists, metadata.artists);
+
+            let tag = Tag::read_from_path(&path).unwrap();
+            assert_eq!(tag.version(), version);
+            let expected = match version {
+                id3::Version::Id3v24 => "AC/DC\0Earth, Wind & Fire",
+                // The id3 crate reads the "/" in TPE1 as a separator.
+                _ => "AC\0DC; Earth, Wind & Fire",
+            };
+            assert_eq!(tag.get("TPE1").and_then(|f| f.content().text()), Some(expected));
+
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    #[test]
+    fn test_tag_writes_back_up_and_replace_the_file() {
+        let dir = std::env::temp_dir().join(format!("nagan-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("song.mp3");
+        std::fs::write(&path, b"audio frames").unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let mut tag = Tag::new();
+        tag.set_title("Before");
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let backup_dir = dir.join("backups");
+        let manager = Id3Manager::from_settings(&AppSettings {
+            tag_writes: TagWriteSettings {
+                backup: true,
+                backup_dir: Some(backup_dir.to_str().unwrap().to_string()),
+                verify: true,
+            },
+            ..AppSettings::default()
+        });
+        manager.write_metadata(&path, &create_test_metadata()).unwrap();
+
+        assert_eq!(manager.read_metadata(&path).unwrap().title, "Test Song");
+        assert!(std::fs::read(&path).unwrap().ends_with(b"audio frames"));
+
+        // The backup holds the old tag, and no temp copy is left next to the song.
+        let backups: Vec<_> = std::fs::read_dir(&backup_dir).unwrap().collect();
+        assert_eq!(backups.len(), 1);
+        let backup = backups[0].as_ref().unwrap().path();
+        let old_tag = Tag::read_from_path(&backup).unwrap();
+        assert_eq!(old_tag.title(), Some("Before"));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_lyrics_round_trip_through_sylt_and_uslt() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()));
+        File::create(&path).unwrap();
+        let path = path.to_str().unwrap().to_string();
+        let manager = Id3Manager::new();
+
+        // Plain USLT lyrics holding LRC text come back synced.
+        let mut tag = Tag::new();
+        tag.add_frame(id3::frame::Lyrics {
+            lang: "deu".to_string(),
+            description: String::new(),
+            text: "[00:01.00]Eins\n[00:02.50]Zwei".to_string(),
+        });
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+        let read = manager.read_lyrics(&path).unwrap().unwrap();
+        assert_eq!(read.text, "Eins\nZwei");
+        assert_eq!(read.lines.len(), 2);
+        assert_eq!(read.language.as_deref(), Some("deu"));
+
+        let synced = FileLyrics {
+            text: "One\nTwo".to_string(),
+            lines: vec![
+                LyricLine {
+                    time: 1.25,
+                    text: "One".to_string(),
+                },
+                LyricLine {
+                    time: 3.0,
+                    text: "Two".to_string(),
+                },
+            ],
+            language: None,
+        };
+        manager.write_lyrics(&path, Some(&synced)).unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.lyrics().count(), 1);
+        assert_eq!(tag.synchronised_lyrics().next().unwrap().content[0], (1250, "One".to_string()));
+        assert_eq!(
+            manager.read_lyrics(&path).unwrap(),
+            Some(FileLyrics {
+                language: Some("eng".to_string()),
+                ..synced
+            })
+        );
+
+        manager.write_lyrics(&path, None).unwrap();
+        assert_eq!(manager.read_lyrics(&path).unwrap(), None);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_write_lyrics_adds_a_tag_to_untagged_files() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.flac", uuid::Uuid::new_v4()));
+        std::fs::write(&path, flac_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let manager = Id3Manager::new();
+        let plain = FileLyrics {
+            text: "La la la".to_string(),
+            lines: vec![],
+            language: None,
+        };
+        manager.write_lyrics(&path, Some(&plain)).unwrap();
+        assert_eq!(manager.read_lyrics(&path).unwrap().map(|l| l.text), Some("La la la".to_string()));
+
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_file_extension_detection() {
         let manager = Id3Manager::new();
@@ -527,11 +2185,14 @@ mod tests {
             ids: vec![],
             updates: serde_json::json!({"title": "New Title"}),
             update_id3: Some(false),
+            on_tag_failure: TagFailurePolicy::Keep,
         };
 
-        let result = bulk_update_songs_inner(payload, &db).await;
+        let result = bulk_update_songs_inner(payload, &db, &create_test_covers()).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 0);
+        let response = result.unwrap();
+        assert_eq!(response.updated_songs, 0);
+        assert!(response.results.is_empty());
     }
 
     #[tokio::test]
@@ -547,6 +2208,7 @@ mod tests {
             filename: "song1.mp3".to_string(),
             metadata: metadata.clone(),
             available: true,
+            range: None,
         };
 
         db.create_song(song.clone()).await.unwrap();
@@ -555,11 +2217,432 @@ mod tests {
             ids: vec![song.id.clone()],
             updates: serde_json::json!({"title": "Updated Title"}),
             update_id3: Some(false),
+            on_tag_failure: TagFailurePolicy::Keep,
         };
 
-        let result = bulk_update_songs_inner(payload, &db).await;
+        let result = bulk_update_songs_inner(payload, &db, &create_test_covers()).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 1);
+        let response = result.unwrap();
+        assert_eq!(response.updated_songs, 1);
+        assert_eq!(
+            response.results,
+            vec![SongUpdateResult {
+                id: song.id.clone(),
+                db_updated: true,
+                tags_written: None,
+                error: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_songs_reports_tag_failures_per_song() {
+        let db = create_test_db().await;
+        let dir = std::env::temp_dir().join(format!("nagan-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // One song with a real file, one whose file is gone.
+        let good_path = dir.join("good.mp3").to_str().unwrap().to_string();
+        std::fs::File::create(&good_path).unwrap();
+        for (id, url) in [("good", good_path.as_str()), ("missing", "/nonexistent/song.mp3")] {
+            db.create_song(Song {
+                id: id.to_string(),
+                url: url.to_string(),
+                filename: "song.mp3".to_string(),
+                metadata: create_test_metadata(),
+                available: true,
+                range: None,
+            })
+            .await
+            .unwrap();
+        }
+        let ids = vec!["good".to_string(), "missing".to_string(), "unknown".to_string()];
+
+        for policy in [TagFailurePolicy::Rollback, TagFailurePolicy::Keep] {
+            let payload = BulkUpdateSongsPayload {
+                ids: ids.clone(),
+                updates: serde_json::json!({"album": format!("{:?} Album", policy)}),
+                update_id3: Some(true),
+                on_tag_failure: policy,
+            };
+            let response = bulk_update_songs_inner(payload, &db, &create_test_covers())
+                .await
+                .unwrap();
+
+            let results = &response.results;
+            assert_eq!(results.len(), 3);
+            assert!(results[0].db_updated);
+            assert_eq!(results[0].tags_written, Some(true));
+            assert_eq!(results[1].tags_written, Some(false));
+            assert!(results[1].error.as_ref().unwrap().contains("Failed to write tags"));
+            assert_eq!(results[2].error.as_deref(), Some("Song not found"));
+
+            let missing = db.get_song_by_id("missing").await.unwrap().unwrap();
+            if policy == TagFailurePolicy::Rollback {
+                assert_eq!(response.updated_songs, 1);
+                assert!(!results[1].db_updated);
+                assert_eq!(missing.metadata.album, "Test Album");
+            } else {
+                assert_eq!(response.updated_songs, 2);
+                assert!(results[1].db_updated);
+                assert_eq!(missing.metadata.album, "Keep Album");
+            }
+
+            let good = db.get_song_by_id("good").await.unwrap().unwrap();
+            assert_eq!(good.metadata.album, format!("{:?} Album", policy));
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_file_tags_of_bulk_update() {
+        let db = create_test_db().await;
+        let covers = create_test_covers();
+        let dir = std::env::temp_dir().join(format!("nagan-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("song.mp3").to_str().unwrap().to_string();
+        std::fs::File::create(&path).unwrap();
+        Id3Manager::new().write_metadata(&path, &create_test_metadata()).unwrap();
+
+        db.create_song(Song {
+            id: "song-1".to_string(),
+            url: path.clone(),
+            filename: "song.mp3".to_string(),
+            metadata: create_test_metadata(),
+            available: true,
+            range: None,
+        })
+        .await
+        .unwrap();
+
+        let payload = BulkUpdateSongsPayload {
+            ids: vec!["song-1".to_string()],
+            updates: serde_json::json!({"title": "Oops"}),
+            update_id3: Some(true),
+            on_tag_failure: TagFailurePolicy::Keep,
+        };
+        bulk_update_songs_inner(payload, &db, &covers).await.unwrap();
+        assert_eq!(Id3Manager::new().read_metadata(&path).unwrap().title, "Oops");
+
+        let response = replay_edit(true, &db, &covers).await.unwrap();
+        assert_eq!(response.entry.unwrap().operation, "bulk_update_songs");
+        assert!(response.tag_failures.is_empty());
+        assert_eq!(Id3Manager::new().read_metadata(&path).unwrap().title, "Test Song");
+        let song = db.get_song_by_id("song-1").await.unwrap().unwrap();
+        assert_eq!(song.metadata.title, "Test Song");
+
+        replay_edit(false, &db, &covers).await.unwrap();
+        assert_eq!(Id3Manager::new().read_metadata(&path).unwrap().title, "Oops");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_removes_only_cleared_extended_frames() {
+        let db = create_test_db().await;
+        let covers = create_test_covers();
+        let dir = std::env::temp_dir().join(format!("nagan-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("song.mp3").to_str().unwrap().to_string();
+        std::fs::File::create(&path).unwrap();
+        let mut file_metadata = create_test_metadata();
+        file_metadata.extended.composer = Some("Composer".to_string());
+        file_metadata.extended.isrc = Some("USABC1234567".to_string());
+        Id3Manager::new().write_metadata(&path, &file_metadata).unwrap();
+
+        // The database doesn't know the file's extended tags.
+        db.create_song(Song {
+            id: "song-1".to_string(),
+            url: path.clone(),
+            filename: "song.mp3".to_string(),
+            metadata: create_test_metadata(),
+            available: true,
+            range: None,
+        })
+        .await
+        .unwrap();
+
+        let update = |updates: serde_json::Value| BulkUpdateSongsPayload {
+            ids: vec!["song-1".to_string()],
+            updates,
+            update_id3: Some(true),
+            on_tag_failure: TagFailurePolicy::Keep,
+        };
+        bulk_update_songs_inner(update(serde_json::json!({"title": "New"})), &db, &covers)
+            .await
+            .unwrap();
+        let read = Id3Manager::new().read_metadata(&path).unwrap();
+        assert_eq!(read.title, "New");
+        assert_eq!(read.extended.composer.as_deref(), Some("Composer"));
+        assert_eq!(read.extended.isrc.as_deref(), Some("USABC1234567"));
+
+        bulk_update_songs_inner(update(serde_json::json!({"composer": null})), &db, &covers)
+            .await
+            .unwrap();
+        let read = Id3Manager::new().read_metadata(&path).unwrap();
+        assert_eq!(read.extended.composer, None);
+        assert_eq!(read.extended.isrc.as_deref(), Some("USABC1234567"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_revert_to_original_tags_restores_unmodeled_frames() {
+        let db = create_test_db().await;
+        let covers = create_test_covers();
+        let dir = std::env::temp_dir().join(format!("nagan-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("song.mp3").to_str().unwrap().to_string();
+        std::fs::File::create(&path).unwrap();
+
+        let manager = Id3Manager::new();
+        manager.write_metadata(&path, &create_test_metadata()).unwrap();
+        let mut tag = Tag::read_from_path(&path).unwrap();
+        tag.add_frame(ExtendedText {
+            description: "DJ NOTES".to_string(),
+            value: "play after midnight".to_string(),
+        });
+        tag.write_to_path(&path, id3::Version::Id3v23).unwrap();
+
+        let snapshot = manager.read_tag_snapshot(&path).unwrap().unwrap();
+        assert_eq!(snapshot.format, "ID3v2.3");
+        assert!(snapshot.frames.iter().any(|f| f.id == "TXXX" && f.value.contains("play after midnight")));
+
+        let song = db
+            .create_song(Song {
+                id: "song-1".to_string(),
+                url: path.clone(),
+                filename: "song.mp3".to_string(),
+                metadata: manager.read_metadata(&path).unwrap(),
+                available: true,
+                range: None,
+            })
+            .await
+            .unwrap();
+        db.save_original_tags(&song.id, &snapshot, &song.metadata).await.unwrap();
+
+        // Edit the title and year, and lose the custom frame from the file.
+        db.update_song(
+            "song-1",
+            UpdateSongPayload {
+                id: "song-1".to_string(),
+                metadata: serde_json::json!({"title": "Changed", "year": 1980}),
+                update_id3: None,
+                filename: None,
+            },
+        )
+        .await
+        .unwrap();
+        let mut tag = Tag::read_from_path(&path).unwrap();
+        tag.remove_extended_text(Some("DJ NOTES"), None);
+        tag.set_title("Changed");
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let payload = RevertToOriginalPayload {
+            song_id: "song-1".to_string(),
+            fields: Some(vec!["year".to_string()]),
+            update_id3: None,
+        };
+        let reverted = revert_to_original_inner(payload, &db, &covers).await.unwrap();
+        assert_eq!(reverted.metadata.year, Some(2023));
+        assert_eq!(reverted.metadata.title, "Changed");
+
+        let payload = RevertToOriginalPayload {
+            song_id: "song-1".to_string(),
+            fields: None,
+            update_id3: Some(true),
+        };
+        let reverted = revert_to_original_inner(payload, &db, &covers).await.unwrap();
+        assert_eq!(reverted.metadata.title, "Test Song");
+
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.version(), id3::Version::Id3v23);
+        assert_eq!(tag.title(), Some("Test Song"));
+        assert!(tag.extended_texts().any(|t| t.value == "play after midnight"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// A one-second, untagged WavPack file: a single 16-bit mono block header.
+    fn wavpack_fixture() -> Vec<u8> {
+        let mut data = b"wvpk".to_vec();
+        data.extend_from_slice(&24u32.to_le_bytes());
+        data.extend_from_slice(&0x410u16.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&44100u32.to_le_bytes()); // total samples
+        data.extend_from_slice(&0u32.to_le_bytes()); // block index
+        data.extend_from_slice(&44100u32.to_le_bytes()); // block samples
+        // 2 bytes per sample, mono, initial and final block, 44.1kHz
+        let flags: u32 = 1 | (1 << 2) | (1 << 11) | (1 << 12) | (9 << 23);
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes()); // crc
+        data
+    }
+
+    #[test]
+    fn test_restore_tag_snapshot_keeps_items_it_cannot_hold() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.wv", uuid::Uuid::new_v4()));
+        std::fs::write(&path, wavpack_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let notes = ItemKey::Unknown("DJ Notes".to_string());
+        let blob = ItemKey::Unknown("Blob".to_string());
+        let mut file = lofty::read_from_path(&path).unwrap();
+        let mut tag = lofty::tag::Tag::new(TagType::Ape);
+        tag.set_title("Original".to_string());
+        tag.push_unchecked(TagItem::new(notes.clone(), ItemValue::Text("play after midnight".to_string())));
+        file.insert_tag(tag);
+        file.save_to_path(&path, WriteOptions::default()).unwrap();
+
+        let manager = Id3Manager::new();
+        let snapshot = manager.read_tag_snapshot(&path).unwrap().unwrap();
+        assert!(snapshot.frames.iter().any(|f| f.value == "play after midnight"));
+
+        // The title changes, the notes go, and a binary item the snapshot can't hold turns up.
+        let mut file = lofty::read_from_path(&path).unwrap();
+        let tag = file.primary_tag_mut().unwrap();
+        tag.set_title("Changed".to_string());
+        tag.remove_key(&notes);
+        tag.push_unchecked(TagItem::new(blob.clone(), ItemValue::Binary(vec![1, 2, 3])));
+        file.save_to_path(&path, WriteOptions::default()).unwrap();
+
+        manager.restore_tag_snapshot(&path, &snapshot).unwrap();
+        let file = lofty::read_from_path(&path).unwrap();
+        let tag = file.primary_tag().unwrap();
+        assert_eq!(tag.title().as_deref(), Some("Original"));
+        assert_eq!(tag.get_string(&notes), Some("play after midnight"));
+        assert_eq!(tag.get(&blob).map(|item| item.value()), Some(&ItemValue::Binary(vec![1, 2, 3])));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_restore_tag_snapshot_to_an_untagged_file() {
+        let path = std::env::temp_dir().join(format!("nagan-test-{}.flac", uuid::Uuid::new_v4()));
+        std::fs::write(&path, flac_fixture()).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let manager = Id3Manager::new();
+        manager.write_metadata(&path, &create_test_metadata()).unwrap();
+        let snapshot = manager.read_tag_snapshot(&path).unwrap().unwrap();
+
+        // Another tagger stripped the tag since.
+        std::fs::write(&path, flac_fixture()).unwrap();
+        manager.restore_tag_snapshot(&path, &snapshot).unwrap();
+        assert_eq!(manager.read_metadata(&path).unwrap().title, "Test Song");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_bpm_round_trips_in_each_format() {
+        let manager = Id3Manager::new();
+        let mut metadata = create_test_metadata();
+        metadata.bpm = Some(127.5);
+        for (extension, data) in [
+            ("mp3", mp3_fixture()),
+            ("flac", flac_fixture()),
+            ("m4a", m4a_fixture()),
+            ("wv", wavpack_fixture()),
+        ] {
+            let path = std::env::temp_dir().join(format!("nagan-test-{}.{}", uuid::Uuid::new_v4(), extension));
+            std::fs::write(&path, data).unwrap();
+            let path = path.to_str().unwrap().to_string();
+
+            manager.write_metadata(&path, &metadata).unwrap();
+            assert_eq!(manager.read_metadata(&path).unwrap().bpm, Some(127.5), "{}", extension);
+            // An unknown BPM leaves the file's alone; clearing it removes it.
+            let unknown = SongMetadata { bpm: None, ..metadata.clone() };
+            manager.write_metadata(&path, &unknown).unwrap();
+            assert_eq!(manager.read_metadata(&path).unwrap().bpm, Some(127.5), "{}", extension);
+            manager.write_metadata_clearing(&path, &unknown, &["bpm"]).unwrap();
+            assert_eq!(manager.read_metadata(&path).unwrap().bpm, None, "{}", extension);
+
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drift_scan_and_resolve() {
+        let db = create_test_db().await;
+        let path = std::env::temp_dir()
+            .join(format!("nagan-test-{}.mp3", uuid::Uuid::new_v4()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        std::fs::write(&path, mp3_fixture()).unwrap();
+        let manager = Id3Manager::new();
+        let mut metadata = create_test_metadata();
+        metadata.bpm = Some(127.5);
+        metadata.rating = Some(3.5);
+        manager.write_metadata(&path, &metadata).unwrap();
+        let song = db
+            .create_song(Song {
+                id: "song-1".to_string(),
+                url: path.clone(),
+                filename: "song.mp3".to_string(),
+                metadata: manager.read_metadata(&path).unwrap(),
+                available: true,
+                range: None,
+            })
+            .await
+            .unwrap();
+        // Year, BPM and rating round-trip through the file, so they don't show as drifted.
+        let read = &song.metadata;
+        assert_eq!((read.year, read.bpm, read.rating), (Some(2023), Some(127.5), Some(3.5)));
+        assert!(scan_drift_inner(None, &db).await.unwrap().is_empty());
+
+        // The title is edited in the library only, the year by another tagger.
+        db.update_song(
+            "song-1",
+            UpdateSongPayload {
+                id: "song-1".to_string(),
+                metadata: serde_json::json!({"title": "Library Title"}),
+                update_id3: None,
+                filename: None,
+            },
+        )
+        .await
+        .unwrap();
+        let mut tag = Tag::read_from_path(&path).unwrap();
+        tag.set_text("TDRC", "1990");
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let drift = scan_drift_inner(None, &db).await.unwrap();
+        assert_eq!(drift.len(), 1);
+        let fields: Vec<&str> = drift[0].fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, ["title", "year"]);
+
+        let payload = ResolveDriftPayload {
+            song_ids: vec!["song-1".to_string()],
+            resolution: DriftResolution::File,
+            fields: Some(vec!["year".to_string()]),
+        };
+        let results = resolve_drift_inner(payload, &db).await.unwrap();
+        assert!(results[0].db_updated);
+        assert_eq!(results[0].tags_written, None);
+        let song = db.get_song_by_id("song-1").await.unwrap().unwrap();
+        assert_eq!(song.metadata.year, Some(1990));
+        assert_eq!(song.metadata.title, "Library Title");
+
+        let payload = ResolveDriftPayload {
+            song_ids: vec!["song-1".to_string()],
+            resolution: DriftResolution::Database,
+            fields: None,
+        };
+        let results = resolve_drift_inner(payload, &db).await.unwrap();
+        assert!(!results[0].db_updated);
+        assert_eq!(results[0].tags_written, Some(true));
+        assert_eq!(manager.read_metadata(&path).unwrap().title, "Library Title");
+        assert!(scan_drift_inner(None, &db).await.unwrap().is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    fn create_test_covers() -> CoverCache {
+        CoverCache::new(std::env::temp_dir().join(format!("nagan-test-covers-{}", uuid::Uuid::new_v4())))
+            .expect("Failed to create test cover cache")
     }
 
     async fn create_test_db() -> Database {
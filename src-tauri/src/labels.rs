@@ -0,0 +1,91 @@
+use crate::dj_library::DjCue;
+
+/// Read an Audacity label track: one "start<TAB>end<TAB>label" line per label, in seconds.
+/// Labels whose end is their start are points. Spectral selection lines ("\<TAB>low<TAB>high")
+/// and lines that don't parse are skipped.
+pub fn parse(content: &str) -> Vec<DjCue> {
+    let mut labels = Vec::new();
+    for line in content.lines() {
+        let mut parts = line.trim_end_matches('\r').splitn(3, '\t');
+        let (Some(start), Some(end)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        // Some locales write a decimal comma.
+        let number = |value: &str| value.trim().replace(',', ".").parse::<f64>().ok();
+        let (Some(start), Some(end)) = (number(start), number(end)) else {
+            continue;
+        };
+        labels.push(DjCue {
+            start,
+            end: Some(end).filter(|end| *end > start),
+            name: parts.next().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string),
+            color: None,
+            hot_cue: None,
+        });
+    }
+    labels.sort_by(|a, b| a.start.total_cmp(&b.start));
+    labels
+}
+
+/// Write an Audacity label track. Tabs and line breaks in labels become spaces.
+pub fn render(cues: &[DjCue]) -> String {
+    let mut out = String::new();
+    for cue in cues {
+        let label = cue
+            .name
+            .as_deref()
+            .unwrap_or("")
+            .replace(['\t', '\r', '\n'], " ");
+        out.push_str(&format!(
+            "{:.6}\t{:.6}\t{}\n",
+            cue.start,
+            cue.end.unwrap_or(cue.start),
+            label
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_labels() {
+        let content = "12.500000\t12.500000\tDrop\r\n\\\t200.0\t4000.0\n30,0\t38,25\tVerse\tone\n7\t7\n";
+        let labels = parse(content);
+        assert_eq!(labels.len(), 3);
+        assert_eq!((labels[0].start, labels[0].end), (7.0, None));
+        assert_eq!(labels[0].name, None);
+        assert_eq!(labels[1].name.as_deref(), Some("Drop"));
+        assert_eq!((labels[2].start, labels[2].end), (30.0, Some(38.25)));
+        assert_eq!(labels[2].name.as_deref(), Some("Verse\tone"));
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let cues = vec![
+            DjCue {
+                start: 1.5,
+                end: None,
+                name: Some("Count\tin".to_string()),
+                color: None,
+                hot_cue: None,
+            },
+            DjCue {
+                start: 16.0,
+                end: Some(24.0),
+                name: Some("Loop".to_string()),
+                color: None,
+                hot_cue: None,
+            },
+        ];
+        let content = render(&cues);
+        assert_eq!(
+            content,
+            "1.500000\t1.500000\tCount in\n16.000000\t24.000000\tLoop\n"
+        );
+        let labels = parse(&content);
+        assert_eq!(labels[1], cues[1]);
+    }
+}
@@ -1,16 +1,35 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+mod artists;
+mod atomic_write;
+mod audio;
 mod commands;
 mod bpm;
+mod chapters;
+mod covers;
+mod cue;
 mod database;
+mod dj_library;
+mod drift;
+mod duplicates;
+mod fingerprint;
+mod genres;
 mod id3;
+mod labels;
+mod lyrics;
 mod models;
+mod original_tags;
+mod playlist_files;
+mod ratings;
+mod serato;
 
+use covers::CoverCache;
 use database::Database;
 
 pub struct AppState {
     pub db: Arc<Mutex<Database>>,
+    pub covers: CoverCache,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -33,8 +52,18 @@ pub async fn run() {
         .await
         .expect("Failed to initialize database");
 
+    let covers = CoverCache::new(app_dir.join("covers")).expect("Failed to create cover cache");
+
+    // Older databases stored cover art inline in songs.image; move it into the cache.
+    match covers::migrate_inline_images(&db, &covers).await {
+        Ok(0) => {}
+        Ok(count) => log::info!("Moved {} inline covers into the cover cache", count),
+        Err(e) => log::warn!("Failed to migrate inline covers: {}", e),
+    }
+
     let app_state = AppState {
         db: Arc::new(Mutex::new(db)),
+        covers,
     };
 
     tauri::Builder::default()
@@ -46,12 +75,20 @@ pub async fn run() {
             commands::get_songs,
             commands::get_song_groups,
             commands::add_song,
+            commands::import_folder,
             commands::update_song,
             commands::delete_song,
+            commands::merge_songs,
             commands::get_song_bpm,
+            commands::get_cover,
+            commands::embed_album_artwork,
+            commands::analyze_song_fingerprint,
+            commands::find_duplicates,
             commands::bulk_update_songs,
+            commands::bulk_edit_tags,
             commands::import_songs,
             commands::export_songs,
+            commands::export_cue_sheet,
             commands::extract_metadata,
             commands::update_id3_tags,
             commands::bulk_update_id3_tags,
@@ -66,16 +103,52 @@ pub async fn run() {
             commands::remove_song_from_playlist,
             commands::reorder_playlist_songs,
             commands::shuffle_playlist,
+            commands::import_playlist,
+            commands::export_playlist,
+            commands::export_dj_library,
+            commands::import_dj_cues,
+            commands::write_serato_markers,
+            commands::export_marker_labels,
+            commands::import_marker_labels,
+            commands::write_marker_chapters,
             commands::get_random_next,
             commands::load_song,
             commands::get_markers,
             commands::add_marker,
             commands::update_marker,
             commands::remove_marker,
+            commands::reorder_markers,
+            commands::get_original_tags,
+            commands::diff_original_tags,
+            commands::revert_to_original,
+            commands::scan_drift,
+            commands::resolve_drift,
+            commands::get_edit_journal,
+            commands::undo,
+            commands::redo,
+            commands::get_lyrics,
+            commands::update_lyrics,
+            commands::retime_lyrics,
+            commands::load_lyrics,
+            commands::search_lyrics,
             commands::monitor_files,
             commands::search_songs,
             commands::calculate_similarity,
             commands::refresh_database,
+            commands::get_albums,
+            commands::get_album,
+            commands::get_artists,
+            commands::update_artist,
+            commands::merge_artists,
+            commands::suggest_artist_merges,
+            commands::normalize_artists,
+            commands::get_genres,
+            commands::create_genre,
+            commands::update_genre,
+            commands::set_genre_parent,
+            commands::normalize_genres,
+            commands::get_settings,
+            commands::update_settings,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
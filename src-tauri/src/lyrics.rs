@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use crate::models::LyricLine;
+
+/// Lyrics as found in a file's tags or a sidecar `.lrc` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileLyrics {
+    pub text: String,
+    /// Time-synced lines; empty for plain lyrics.
+    pub lines: Vec<LyricLine>,
+    pub language: Option<String>,
+}
+
+impl FileLyrics {
+    /// Plain text, or LRC when it carries timestamps.
+    pub fn from_text(text: &str, language: Option<String>) -> Option<Self> {
+        let lines = parse_lrc(text);
+        let text = if lines.is_empty() {
+            text.trim().to_string()
+        } else {
+            plain_text(&lines)
+        };
+        if text.is_empty() && lines.is_empty() {
+            return None;
+        }
+        Some(FileLyrics {
+            text,
+            lines,
+            language,
+        })
+    }
+}
+
+/// Parse LRC lyrics into lines ordered by time.
+///
+/// Lines may carry several timestamps ("[00:12.00][01:30.50]Chorus"); `[offset:ms]` shifts every
+/// line, and other `[tag:value]` headers are skipped. Text without timestamps yields no lines.
+pub fn parse_lrc(content: &str) -> Vec<LyricLine> {
+    let mut offset_ms = 0.0;
+    let mut lines = Vec::new();
+
+    for raw in content.lines() {
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(end) = tag.find(']') else {
+                break;
+            };
+            let (inner, after) = (&tag[..end], &tag[end + 1..]);
+            if let Some(time) = parse_timestamp(inner) {
+                times.push(time);
+            } else if let Some(value) = inner.strip_prefix("offset:") {
+                offset_ms = value.trim().parse().unwrap_or(0.0);
+            }
+            rest = after;
+        }
+
+        let text = rest.trim();
+        for time in times {
+            lines.push(LyricLine {
+                time,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    // A positive offset makes the lyrics appear sooner.
+    for line in &mut lines {
+        line.time = (line.time - offset_ms / 1000.0).max(0.0);
+    }
+    lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+    lines
+}
+
+/// "mm:ss", "mm:ss.xx" or "mm:ss:xx" in seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let (minutes, seconds) = value.split_once(':')?;
+    let minutes: u32 = minutes.trim().parse().ok()?;
+    let seconds: f64 = match seconds.split_once(':') {
+        Some((s, fraction)) => format!("{}.{}", s, fraction).parse().ok()?,
+        None => seconds.trim().parse().ok()?,
+    };
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some(minutes as f64 * 60.0 + seconds)
+}
+
+/// Format lines as LRC ("[mm:ss.xx]text").
+pub fn to_lrc(lines: &[LyricLine]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let centis = (line.time.max(0.0) * 100.0).round() as u64;
+            format!(
+                "[{:02}:{:02}.{:02}]{}",
+                centis / 6000,
+                centis / 100 % 60,
+                centis % 100,
+                line.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The text of synced lines, one per line.
+pub fn plain_text(lines: &[LyricLine]) -> String {
+    lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The `.lrc` file next to a song with the same stem, if there is one.
+pub fn find_sidecar_lrc(song_path: &str) -> Option<PathBuf> {
+    let path = Path::new(song_path);
+    ["lrc", "LRC"]
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|p| p.is_file())
+}
+
+/// Shift every line by `offset` seconds, then move individual lines (`(index, time)` pairs).
+/// Lines are re-sorted by time afterwards.
+pub fn retime(lines: &mut [LyricLine], offset: f64, moves: &[(usize, f64)]) {
+    for line in lines.iter_mut() {
+        line.time = (line.time + offset).max(0.0);
+    }
+    for &(index, time) in moves {
+        if let Some(line) = lines.get_mut(index) {
+            line.time = time.max(0.0);
+        }
+    }
+    lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(time: f64, text: &str) -> LyricLine {
+        LyricLine {
+            time,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_lrc() {
+        let lrc = "[ar:Someone]\n[offset:500]\n[00:12.00][01:30.50]Chorus\n[00:05.25]First line\nno timestamp\n[00:20:10]Third";
+        assert_eq!(
+            parse_lrc(lrc),
+            vec![
+                line(4.75, "First line"),
+                line(11.5, "Chorus"),
+                line(19.6, "Third"),
+                line(90.0, "Chorus"),
+            ]
+        );
+        assert!(parse_lrc("Just some words\n[chorus]").is_empty());
+    }
+
+    #[test]
+    fn test_lrc_round_trip_and_retime() {
+        let mut lines = vec![line(1.5, "a"), line(65.25, "b")];
+        assert_eq!(to_lrc(&lines), "[00:01.50]a\n[01:05.25]b");
+        assert_eq!(parse_lrc(&to_lrc(&lines)), lines);
+
+        retime(&mut lines, -1.0, &[(0, 70.0)]);
+        assert_eq!(lines, vec![line(64.25, "b"), line(70.0, "a")]);
+    }
+}
@@ -11,9 +11,18 @@ pub struct Song {
     pub filename: String,
     pub metadata: SongMetadata,
     pub available: bool,
+    pub range: Option<TrackRange>, // set for cue sheet tracks
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// The section of its file a cue sheet track plays, in seconds; `end` is `None` for a track
+/// that plays to the end of the file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackRange {
+    pub start: f64,
+    pub end: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
 pub struct SongMetadata {
     pub title: String,
     pub album: String,
@@ -29,9 +38,81 @@ pub struct SongMetadata {
     pub tags: Vec<String>,
     pub file_exists: bool,
     pub times_played: i32,
+    #[serde(default)]
+    pub album_artist: Option<String>,
+    #[serde(default)]
+    pub disc: Option<i32>,
+    #[serde(default)]
+    pub disc_total: Option<i32>,
+    #[serde(default)]
+    pub track_total: Option<i32>,
+    #[serde(default)]
+    pub compilation: bool,
+    #[serde(default)]
+    pub album_id: Option<String>, // assigned by the database
+    #[serde(default)]
+    pub rating: Option<f32>, // 0-5 stars in halves; None = unrated
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub extended: ExtendedTags,
+}
+
+/// Credits, identifiers and sort names read from and written to the file tags.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(default)]
+pub struct ExtendedTags {
+    pub composer: Option<String>,
+    pub conductor: Option<String>,
+    pub label: Option<String>,
+    pub isrc: Option<String>,
+    pub catalog_number: Option<String>,
+    pub original_year: Option<i32>,
+    pub grouping: Option<String>,
+    pub initial_key: Option<String>, // musical key as tagged ("Am", "8A")
+    pub title_sort: Option<String>,
+    pub artist_sort: Option<String>,
+    pub album_sort: Option<String>,
+    pub album_artist_sort: Option<String>,
+    pub composer_sort: Option<String>,
+    pub musicbrainz_recording_id: Option<String>,
+    pub musicbrainz_track_id: Option<String>, // release track
+    pub musicbrainz_album_id: Option<String>,
+    pub musicbrainz_artist_id: Option<String>,
+    pub musicbrainz_album_artist_id: Option<String>,
+    pub musicbrainz_release_group_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Album {
+    pub id: String,
+    pub title: String,
+    pub album_artist: String,
+    pub compilation: bool,
+    pub year: Option<i32>,
+    pub disc_count: Option<i32>,
+    pub track_count: Option<i32>,
+    pub image: Option<String>, // cover cache id
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artist {
+    pub id: String,
+    pub name: String,
+    pub sort_name: String,
+    pub aliases: Vec<String>,
+    pub song_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genre {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub aliases: Vec<String>,
+    pub song_count: i64, // songs tagged with this genre itself, not its descendants
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct Playlist {
     pub id: String,
     pub name: String,
@@ -39,7 +120,7 @@ pub struct Playlist {
     pub total_duration: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Marker {
     pub id: String,
     pub song: String,
@@ -47,6 +128,234 @@ pub struct Marker {
     pub end: Option<f64>,
     pub comment: Option<String>,
     pub color: Option<String>,
+    #[serde(default)]
+    pub kind: MarkerKind,
+    #[serde(default)]
+    pub section: Option<SectionLabel>, // what part of the song a section marker is
+    #[serde(default)]
+    pub hot_cue: Option<u8>, // DJ hot cue slot, 0-7
+    #[serde(default)]
+    pub position: i32, // order among the song's markers
+}
+
+/// What a marker marks. Points are cues unless said otherwise, ranges loops.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MarkerKind {
+    #[default]
+    Cue,
+    Loop,
+    Section,
+    Chapter,
+    Note,
+}
+
+impl MarkerKind {
+    pub const ALL: [MarkerKind; 5] = [
+        MarkerKind::Cue,
+        MarkerKind::Loop,
+        MarkerKind::Section,
+        MarkerKind::Chapter,
+        MarkerKind::Note,
+    ];
+
+    /// The kind of a marker that says nothing else: a loop if it has an end, else a cue.
+    pub fn for_range(end: Option<f64>) -> Self {
+        match end {
+            Some(_) => MarkerKind::Loop,
+            None => MarkerKind::Cue,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MarkerKind::Cue => "cue",
+            MarkerKind::Loop => "loop",
+            MarkerKind::Section => "section",
+            MarkerKind::Chapter => "chapter",
+            MarkerKind::Note => "note",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SectionLabel {
+    Intro,
+    Verse,
+    Chorus,
+    Bridge,
+    Breakdown,
+    Drop,
+    Outro,
+}
+
+impl SectionLabel {
+    pub const ALL: [SectionLabel; 7] = [
+        SectionLabel::Intro,
+        SectionLabel::Verse,
+        SectionLabel::Chorus,
+        SectionLabel::Bridge,
+        SectionLabel::Breakdown,
+        SectionLabel::Drop,
+        SectionLabel::Outro,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SectionLabel::Intro => "intro",
+            SectionLabel::Verse => "verse",
+            SectionLabel::Chorus => "chorus",
+            SectionLabel::Bridge => "bridge",
+            SectionLabel::Breakdown => "breakdown",
+            SectionLabel::Drop => "drop",
+            SectionLabel::Outro => "outro",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|label| label.as_str() == name)
+    }
+}
+
+/// A frame (ID3) or item (other tag formats) of a file's tag, as text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagFrame {
+    pub id: String, // frame id ("TIT2") or the format's item key ("TITLE")
+    pub value: String,
+}
+
+/// Everything in a file's tag, including what `SongMetadata` doesn't model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSnapshot {
+    pub format: String, // "ID3v2.4", or the tag type for other formats ("VorbisComments")
+    pub frames: Vec<TagFrame>,
+    #[serde(skip)]
+    pub raw: Option<Vec<u8>>, // the encoded ID3v2 tag, for restoring MP3 tags exactly
+}
+
+/// A song's tags as they were when it was first imported.
+#[derive(Debug, Clone, Serialize)]
+pub struct OriginalTags {
+    pub song_id: String,
+    #[serde(flatten)]
+    pub tags: TagSnapshot,
+    pub metadata: SongMetadata, // what was read from the tags
+    pub captured_at: DateTime<Utc>,
+}
+
+/// A metadata field that no longer has the value read on import.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetadataDiff {
+    pub field: String,
+    pub original: serde_json::Value,
+    pub current: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevertToOriginalPayload {
+    pub song_id: String,
+    pub fields: Option<Vec<String>>, // None reverts every tag field
+    pub update_id3: Option<bool>,
+}
+
+/// A tag field whose value in the library differs from the one in the song's file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldDrift {
+    pub field: String,
+    pub database: serde_json::Value,
+    pub file: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SongDrift {
+    pub song_id: String,
+    pub url: String,
+    pub fields: Vec<FieldDrift>,
+}
+
+/// Which side wins when the library and a file's tags disagree. `Merge` fills empty values
+/// from the other side and combines lists; where both have a value the library's is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DriftResolution {
+    File,
+    Database,
+    Merge,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveDriftPayload {
+    pub song_ids: Vec<String>,
+    pub resolution: DriftResolution,
+    pub fields: Option<Vec<String>>, // None resolves every drifted field
+}
+
+/// A playlist and its songs in order, as stored in the edit journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistSnapshot {
+    pub playlist: Playlist,
+    pub song_ids: Vec<String>,
+}
+
+/// The state of one thing an edit changed, before and after it. `None` means it didn't exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum JournalChange {
+    #[serde(rename_all = "camelCase")]
+    Song {
+        id: String,
+        before: Box<SongMetadata>,
+        after: Box<SongMetadata>,
+        #[serde(default)]
+        tags_written: bool, // the file's tags were rewritten too, so undo rewrites them
+    },
+    Playlist {
+        id: String,
+        before: Option<PlaylistSnapshot>,
+        after: Option<PlaylistSnapshot>,
+    },
+    Marker {
+        id: String,
+        before: Option<Marker>,
+        after: Option<Marker>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: i64,
+    pub operation: String, // the command that made the edit, e.g. "bulk_update_songs"
+    pub changes: Vec<JournalChange>,
+    pub undone: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResponse {
+    pub entry: Option<JournalEntry>, // None when there was nothing to undo or redo
+    pub tag_failures: Vec<String>,   // files whose tags couldn't be restored
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lyrics {
+    pub song_id: String,
+    pub text: String,
+    pub lines: Vec<LyricLine>, // time-synced lines; empty for plain lyrics
+    pub language: Option<String>,
+    pub source: String, // "tag", "lrc" or "user"
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LyricLine {
+    pub time: f64, // seconds
+    pub text: String,
 }
 
 // Database Models (for SQLite storage)
@@ -73,6 +382,50 @@ pub struct DbSong {
     pub available: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub album_id: Option<String>,
+    pub album_artist: Option<String>,
+    pub disc: Option<i32>,
+    pub disc_total: Option<i32>,
+    pub track_total: Option<i32>,
+    pub compilation: bool,
+    pub rating: Option<f32>,
+    pub track_start: Option<f64>,
+    pub track_end: Option<f64>,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub extended: ExtendedTags,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbAlbum {
+    pub id: String,
+    pub title: String,
+    pub album_artist: String,
+    pub compilation: bool,
+    pub year: Option<i32>,
+    pub disc_count: Option<i32>,
+    pub track_count: Option<i32>,
+    pub image: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbArtist {
+    pub id: String,
+    pub name: String,
+    pub sort_name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DbGenre {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -103,6 +456,40 @@ pub struct DbMarker {
     pub end: Option<f64>,
     pub comment: Option<String>,
     pub color: Option<String>,
+    pub kind: String,
+    pub section: Option<String>,
+    pub hot_cue: Option<i64>,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DbOriginalTags {
+    pub song_id: String,
+    pub format: String,
+    pub frames: String,   // JSON array of TagFrame
+    pub raw: Option<Vec<u8>>,
+    pub metadata: String, // JSON SongMetadata
+    pub captured_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DbJournalEntry {
+    pub id: i64,
+    pub operation: String,
+    pub changes: String, // JSON array of JournalChange
+    pub undone: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DbLyrics {
+    pub song_id: String,
+    pub text: String,
+    pub lines: Option<String>, // JSON array of LyricLine
+    pub language: Option<String>,
+    pub source: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -138,6 +525,9 @@ pub struct SongGroupRequestItem {
     pub name: String,
     pub selected: serde_json::Value,
     pub sort_by: SongGroupSortBy,
+    /// Genre groups only: roll genres up to their ancestor at this depth (0 = top level).
+    #[serde(default)]
+    pub level: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -151,6 +541,10 @@ pub struct GetSongsGroupsQuery {
 pub struct SongGroupItemCount {
     pub name: String,
     pub count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>, // album id for album groups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>, // album artist for album groups
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -181,6 +575,81 @@ pub struct BulkUpdateSongsPayload {
     pub ids: Vec<String>,
     pub updates: serde_json::Value,
     pub update_id3: Option<bool>,
+    #[serde(default)]
+    pub on_tag_failure: TagFailurePolicy,
+}
+
+/// What a bulk update does with a song's database change when its file tags can't be written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagFailurePolicy {
+    #[default]
+    Keep,
+    Rollback,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUpdateSongsResponse {
+    pub updated_songs: usize,
+    pub results: Vec<SongUpdateResult>,
+}
+
+/// The outcome of one song in a bulk update.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SongUpdateResult {
+    pub id: String,
+    pub db_updated: bool,
+    pub tags_written: Option<bool>, // None when tags weren't requested or not attempted
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkEditTagsPayload {
+    pub ids: Vec<String>,
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+    pub update_id3: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkEditTagsResponse {
+    pub updated_songs: usize,
+    pub tag_failures: Vec<String>, // files whose tags couldn't be rewritten
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLyricsPayload {
+    pub song_id: String,
+    pub text: Option<String>,           // plain text or LRC
+    pub lines: Option<Vec<LyricLine>>, // takes precedence over text
+    pub language: Option<String>,
+    pub update_id3: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetimeLyricsPayload {
+    pub song_id: String,
+    pub offset: Option<f64>, // seconds added to every line
+    #[serde(default)]
+    pub lines: Vec<LyricLineTime>,
+    pub update_id3: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LyricLineTime {
+    pub index: usize,
+    pub time: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LyricsSearchResult {
+    pub song: Song,
+    pub snippet: String, // matched terms wrapped in [ ]
 }
 
 #[derive(Debug, Deserialize)]
@@ -206,6 +675,52 @@ pub struct GetPlaylistSongsQuery {
     pub sort: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPlaylistPayload {
+    pub playlist_id: String,
+    pub file_path: String,
+    pub relative_paths: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPlaylistPayload {
+    pub file_path: String,
+    pub name: Option<String>,      // defaults to the file's playlist name, then its file name
+    pub add_unknown: Option<bool>, // add files that aren't in the library yet
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPlaylistResponse {
+    pub playlist: Playlist,
+    pub song_count: usize,
+    pub added_songs: usize,      // songs added to the library for unknown files
+    pub unresolved: Vec<String>, // entries as written in the file
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportDjLibraryPayload {
+    pub playlist_ids: Vec<String>,
+    pub file_path: String, // .xml for Rekordbox, .nml for Traktor
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDjCuesPayload {
+    pub file_path: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDjCuesResponse {
+    pub matched_songs: usize,
+    pub added_markers: usize,
+    pub unresolved: Vec<String>, // files in the collection that aren't in the library
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -238,14 +753,49 @@ pub struct AddMarkerPayload {
     pub end: Option<f64>,
     pub comment: Option<String>,
     pub color: Option<String>,
+    pub kind: Option<MarkerKind>, // defaults to a loop for ranges, else a cue
+    pub section: Option<SectionLabel>,
+    pub hot_cue: Option<u8>,
 }
 
-#[allow(dead_code)]
+/// Changes to a marker; fields left out stay as they are. `end`, `comment`, `color`, `section`
+/// and `hot_cue` are cleared with null.
 #[derive(Debug, Deserialize)]
 pub struct UpdateMarkerPayload {
     pub id: String,
-    pub comment: Option<String>,
-    pub color: Option<String>,
+    pub start: Option<f64>,
+    #[serde(default, deserialize_with = "present")]
+    pub end: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub comment: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub color: Option<Option<String>>,
+    pub kind: Option<MarkerKind>,
+    #[serde(default, deserialize_with = "present")]
+    pub section: Option<Option<SectionLabel>>,
+    #[serde(default, deserialize_with = "present")]
+    pub hot_cue: Option<Option<u8>>,
+}
+
+/// Tell a field given as null (`Some(None)`) from one left out (`None`, via `default`).
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderMarkersPayload {
+    pub song_id: String,
+    pub marker_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkerLabelsPayload {
+    pub song_id: String,
+    pub file_path: String, // Audacity label track (.txt)
 }
 
 #[allow(dead_code)]
@@ -261,8 +811,336 @@ pub struct CalculateSimilarityResponse {
     pub distances: Vec<f32>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCandidate {
+    pub song: Song,
+    pub format: String,
+    pub bitrate: Option<u32>, // kbps, estimated from file size
+    pub tag_score: u32,
+    pub similarity: f32, // to the best copy
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub best_copy: String,
+    pub songs: Vec<DuplicateCandidate>, // best copy first
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateArtistPayload {
+    pub id: String,
+    pub sort_name: Option<String>,
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateGenrePayload {
+    pub id: String,
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistMergeSuggestion {
+    pub source: String, // the less used spelling
+    pub target: String,
+    pub similarity: f32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeArtistsResponse {
+    pub artist: Artist,
+    pub updated_songs: usize,
+    pub tag_failures: Vec<String>, // files whose tags couldn't be rewritten
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSongsResponse {
+    pub song: Song,
+    pub merged_count: usize,
+    pub trash_failures: Vec<String>, // paths that couldn't be moved to the trash
+}
+
+// Settings
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AppSettings {
+    pub artwork: ArtworkSettings,
+    pub artists: ArtistSettings,
+    pub tag_fields: TagFieldSettings,
+    pub ratings: RatingSettings,
+    pub multi_value: MultiValueSettings,
+    pub tag_writes: TagWriteSettings,
+    pub drift: DriftSettings,
+    pub serato: SeratoSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ArtworkSettings {
+    /// Fall back to an image file next to the song when it has no embedded picture.
+    pub use_sidecar: bool,
+    /// Case-insensitive filename patterns (`*` and `?` wildcards), in order of preference.
+    pub sidecar_patterns: Vec<String>,
+}
+
+impl Default for ArtworkSettings {
+    fn default() -> Self {
+        ArtworkSettings {
+            use_sidecar: true,
+            sidecar_patterns: vec![
+                "cover.*".to_string(),
+                "folder.*".to_string(),
+                "front.*".to_string(),
+                "album.*".to_string(),
+                "albumart*.*".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ArtistSettings {
+    /// Words that separate artists in a credit, matched case-insensitively as whole words.
+    pub split_separators: Vec<String>,
+    /// Credits that contain a separator but name a single artist.
+    pub keep_together: Vec<String>,
+}
+
+impl Default for ArtistSettings {
+    fn default() -> Self {
+        ArtistSettings {
+            split_separators: vec![
+                "feat.".to_string(),
+                "feat".to_string(),
+                "ft.".to_string(),
+                "featuring".to_string(),
+                "&".to_string(),
+                "x".to_string(),
+            ],
+            keep_together: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TagFieldSettings {
+    /// Field holding the instruments: a TXXX description in ID3, a comment name in Vorbis/APE.
+    pub instruments: String,
+    /// Field holding the user tags.
+    pub tags: String,
+}
+
+impl Default for TagFieldSettings {
+    fn default() -> Self {
+        TagFieldSettings {
+            instruments: "INSTRUMENTS".to_string(),
+            tags: "TAGS".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RatingSettings {
+    /// The POPM user (email) our ratings are read from and written under.
+    pub popm_email: String,
+    /// Read the play count from the POPM counter on import and write it back with the tags.
+    pub sync_play_count: bool,
+}
+
+impl Default for RatingSettings {
+    fn default() -> Self {
+        RatingSettings {
+            popm_email: "Windows Media Player 9 Series".to_string(),
+            sync_play_count: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MultiValueSettings {
+    /// Written between values in formats with no native multi-value support (ID3v2.3, RIFF INFO).
+    pub separator: String,
+    /// Single values read from a file are split on these, e.g. "Artist A; Artist B".
+    pub split_on: Vec<String>,
+}
+
+impl Default for MultiValueSettings {
+    fn default() -> Self {
+        MultiValueSettings {
+            separator: "; ".to_string(),
+            split_on: vec![";".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TagWriteSettings {
+    /// Copy each file's tag block aside before rewriting its tags.
+    pub backup: bool,
+    /// Where backups go; defaults to "tag-backups" in the app data directory.
+    pub backup_dir: Option<String>,
+    /// Read the tags back from the rewritten copy before it replaces the original.
+    pub verify: bool,
+}
+
+impl Default for TagWriteSettings {
+    fn default() -> Self {
+        TagWriteSettings {
+            backup: false,
+            backup_dir: None,
+            verify: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DriftSettings {
+    /// Reconcile drift between the library and file tags this way on every rescan.
+    pub auto_sync: Option<DriftResolution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SeratoSettings {
+    /// Add the cues and loops in a file's Serato frames as markers on import and rescan.
+    pub import: bool,
+    /// Write a song's markers back to its Serato frames whenever one is added.
+    pub write_back: bool,
+}
+
+impl Default for SeratoSettings {
+    fn default() -> Self {
+        SeratoSettings {
+            import: true,
+            write_back: false,
+        }
+    }
+}
+
 // Conversion functions
 
+impl ExtendedTags {
+    /// Names of the text fields, which are also their column and update payload keys.
+    pub const TEXT_FIELDS: [&'static str; 18] = [
+        "composer",
+        "conductor",
+        "label",
+        "isrc",
+        "catalog_number",
+        "grouping",
+        "initial_key",
+        "title_sort",
+        "artist_sort",
+        "album_sort",
+        "album_artist_sort",
+        "composer_sort",
+        "musicbrainz_recording_id",
+        "musicbrainz_track_id",
+        "musicbrainz_album_id",
+        "musicbrainz_artist_id",
+        "musicbrainz_album_artist_id",
+        "musicbrainz_release_group_id",
+    ];
+
+    pub fn text(&self, field: &str) -> Option<&str> {
+        let value = match field {
+            "composer" => &self.composer,
+            "conductor" => &self.conductor,
+            "label" => &self.label,
+            "isrc" => &self.isrc,
+            "catalog_number" => &self.catalog_number,
+            "grouping" => &self.grouping,
+            "initial_key" => &self.initial_key,
+            "title_sort" => &self.title_sort,
+            "artist_sort" => &self.artist_sort,
+            "album_sort" => &self.album_sort,
+            "album_artist_sort" => &self.album_artist_sort,
+            "composer_sort" => &self.composer_sort,
+            "musicbrainz_recording_id" => &self.musicbrainz_recording_id,
+            "musicbrainz_track_id" => &self.musicbrainz_track_id,
+            "musicbrainz_album_id" => &self.musicbrainz_album_id,
+            "musicbrainz_artist_id" => &self.musicbrainz_artist_id,
+            "musicbrainz_album_artist_id" => &self.musicbrainz_album_artist_id,
+            "musicbrainz_release_group_id" => &self.musicbrainz_release_group_id,
+            _ => return None,
+        };
+        value.as_deref()
+    }
+
+    /// Set a text field; blank values clear it. Unknown fields are ignored.
+    pub fn set_text(&mut self, field: &str, value: Option<&str>) {
+        let slot = match field {
+            "composer" => &mut self.composer,
+            "conductor" => &mut self.conductor,
+            "label" => &mut self.label,
+            "isrc" => &mut self.isrc,
+            "catalog_number" => &mut self.catalog_number,
+            "grouping" => &mut self.grouping,
+            "initial_key" => &mut self.initial_key,
+            "title_sort" => &mut self.title_sort,
+            "artist_sort" => &mut self.artist_sort,
+            "album_sort" => &mut self.album_sort,
+            "album_artist_sort" => &mut self.album_artist_sort,
+            "composer_sort" => &mut self.composer_sort,
+            "musicbrainz_recording_id" => &mut self.musicbrainz_recording_id,
+            "musicbrainz_track_id" => &mut self.musicbrainz_track_id,
+            "musicbrainz_album_id" => &mut self.musicbrainz_album_id,
+            "musicbrainz_artist_id" => &mut self.musicbrainz_artist_id,
+            "musicbrainz_album_artist_id" => &mut self.musicbrainz_album_artist_id,
+            "musicbrainz_release_group_id" => &mut self.musicbrainz_release_group_id,
+            _ => return,
+        };
+        *slot = value.map(str::trim).filter(|v| !v.is_empty()).map(|v| v.to_string());
+    }
+
+    /// The fields an update payload clears: present, but null or blank.
+    pub fn cleared_fields(updates: &serde_json::Map<String, serde_json::Value>) -> Vec<&'static str> {
+        let mut cleared: Vec<&'static str> = Self::TEXT_FIELDS
+            .into_iter()
+            .filter(|field| {
+                updates
+                    .get(*field)
+                    .is_some_and(|value| value.as_str().is_none_or(|v| v.trim().is_empty()))
+            })
+            .collect();
+        if updates.get("original_year").is_some_and(|value| !value.is_i64()) {
+            cleared.push("original_year");
+        }
+        cleared
+    }
+
+    /// Apply the fields present in an update payload (null clears a field).
+    /// Returns whether any field was present.
+    pub fn apply_updates(&mut self, updates: &serde_json::Map<String, serde_json::Value>) -> bool {
+        let mut changed = false;
+        for field in Self::TEXT_FIELDS {
+            if let Some(value) = updates.get(field) {
+                self.set_text(field, value.as_str());
+                changed = true;
+            }
+        }
+        if let Some(value) = updates.get("original_year") {
+            self.original_year = value.as_i64().and_then(|y| i32::try_from(y).ok());
+            changed = true;
+        }
+        changed
+    }
+}
+
 impl From<DbSong> for Song {
     fn from(db_song: DbSong) -> Self {
         Song {
@@ -286,8 +1164,35 @@ impl From<DbSong> for Song {
                 tags: serde_json::from_str(&db_song.tags).unwrap_or_default(),
                 file_exists: db_song.file_exists,
                 times_played: db_song.times_played,
+                album_artist: db_song.album_artist,
+                disc: db_song.disc,
+                disc_total: db_song.disc_total,
+                track_total: db_song.track_total,
+                compilation: db_song.compilation,
+                album_id: db_song.album_id,
+                rating: db_song.rating,
+                extended: db_song.extended,
             },
             available: db_song.available,
+            range: db_song.track_start.map(|start| TrackRange {
+                start,
+                end: db_song.track_end,
+            }),
+        }
+    }
+}
+
+impl From<DbAlbum> for Album {
+    fn from(db_album: DbAlbum) -> Self {
+        Album {
+            id: db_album.id,
+            title: db_album.title,
+            album_artist: db_album.album_artist,
+            compilation: db_album.compilation,
+            year: db_album.year,
+            disc_count: db_album.disc_count,
+            track_count: db_album.track_count,
+            image: db_album.image,
         }
     }
 }
@@ -312,6 +1217,37 @@ impl From<DbMarker> for Marker {
             end: db_marker.end,
             comment: db_marker.comment,
             color: db_marker.color,
+            kind: MarkerKind::from_name(&db_marker.kind).unwrap_or_default(),
+            section: db_marker.section.as_deref().and_then(SectionLabel::from_name),
+            hot_cue: db_marker.hot_cue.and_then(|slot| u8::try_from(slot).ok()),
+            position: db_marker.position as i32,
+        }
+    }
+}
+
+impl From<DbJournalEntry> for JournalEntry {
+    fn from(db_entry: DbJournalEntry) -> Self {
+        JournalEntry {
+            id: db_entry.id,
+            operation: db_entry.operation,
+            changes: serde_json::from_str(&db_entry.changes).unwrap_or_default(),
+            undone: db_entry.undone,
+            created_at: db_entry.created_at,
+        }
+    }
+}
+
+impl From<DbLyrics> for Lyrics {
+    fn from(db_lyrics: DbLyrics) -> Self {
+        Lyrics {
+            song_id: db_lyrics.song_id,
+            text: db_lyrics.text,
+            lines: db_lyrics
+                .lines
+                .and_then(|l| serde_json::from_str(&l).ok())
+                .unwrap_or_default(),
+            language: db_lyrics.language,
+            source: db_lyrics.source,
         }
     }
 }
@@ -331,18 +1267,15 @@ mod tests {
                 album: "Test Album".to_string(),
                 year: Some(2023),
                 track: Some(1),
-                image: None,
                 duration: 180.0,
                 artists: vec!["Test Artist".to_string()],
-                instruments: None,
                 bpm: Some(120.0),
                 genres: vec!["Rock".to_string()],
-                comment: None,
-                tags: vec![],
                 file_exists: true,
-                times_played: 0,
+                ..Default::default()
             },
             available: true,
+            range: None,
         };
 
         assert_eq!(song.id, "test-id");
@@ -373,6 +1306,7 @@ mod tests {
             end: Some(45.2),
             comment: Some("Chorus".to_string()),
             color: Some("#FF0000".to_string()),
+            ..Marker::default()
         };
 
         assert_eq!(marker.start, 30.5);
@@ -403,12 +1337,23 @@ mod tests {
             available: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            album_id: None,
+            album_artist: None,
+            disc: None,
+            disc_total: None,
+            track_total: None,
+            compilation: false,
+            rating: Some(4.5),
+            track_start: None,
+            track_end: None,
+            extended: ExtendedTags::default(),
         };
 
         let song: Song = db_song.into();
         assert_eq!(song.metadata.artists.len(), 2);
         assert_eq!(song.metadata.genres.len(), 2);
         assert_eq!(song.metadata.times_played, 5);
+        assert_eq!(song.metadata.rating, Some(4.5));
     }
 
     #[test]
@@ -437,6 +1382,10 @@ mod tests {
             end: Some(20.0),
             comment: Some("Verse".to_string()),
             color: Some("#00FF00".to_string()),
+            kind: "section".to_string(),
+            section: Some("verse".to_string()),
+            hot_cue: Some(3),
+            position: 1,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -445,6 +1394,9 @@ mod tests {
         assert_eq!(marker.song, "song-1");
         assert_eq!(marker.start, 10.0);
         assert_eq!(marker.comment, Some("Verse".to_string()));
+        assert_eq!(marker.kind, MarkerKind::Section);
+        assert_eq!(marker.section, Some(SectionLabel::Verse));
+        assert_eq!(marker.hot_cue, Some(3));
     }
 
     #[test]
@@ -454,16 +1406,12 @@ mod tests {
             album: "Album".to_string(),
             year: Some(2023),
             track: Some(1),
-            image: None,
             duration: 180.0,
             artists: vec!["Artist".to_string()],
-            instruments: None,
             bpm: Some(120.0),
             genres: vec!["Rock".to_string()],
-            comment: None,
-            tags: vec![],
             file_exists: true,
-            times_played: 0,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
@@ -0,0 +1,103 @@
+use serde_json::{Map, Value};
+
+use crate::models::{MetadataDiff, SongMetadata};
+
+/// Metadata fields that belong to the library rather than the file's tags.
+const LIBRARY_FIELDS: [&str; 4] = ["duration", "file_exists", "times_played", "album_id"];
+
+/// The tag-backed fields of a song's metadata, by name.
+pub(crate) fn tag_fields(metadata: &SongMetadata) -> Map<String, Value> {
+    let mut fields = match serde_json::to_value(metadata) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    for field in LIBRARY_FIELDS {
+        fields.remove(field);
+    }
+    fields
+}
+
+/// The fields whose current value differs from the one read on import.
+pub fn diff(original: &SongMetadata, current: &SongMetadata) -> Vec<MetadataDiff> {
+    let current = tag_fields(current);
+    tag_fields(original)
+        .into_iter()
+        .filter_map(|(field, original)| {
+            let current = current.get(&field).cloned().unwrap_or(Value::Null);
+            (original != current).then_some(MetadataDiff {
+                field,
+                original,
+                current,
+            })
+        })
+        .collect()
+}
+
+/// `current` with the given fields, or every tag field when `fields` is `None`, set back to
+/// their values on import.
+pub fn revert(
+    original: &SongMetadata,
+    current: &SongMetadata,
+    fields: Option<&[String]>,
+) -> Result<SongMetadata, String> {
+    let original = tag_fields(original);
+    let mut reverted = match serde_json::to_value(current) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return Err("Failed to read the song's metadata".to_string()),
+    };
+
+    match fields {
+        None => reverted.extend(original),
+        Some(fields) => {
+            for field in fields {
+                let value = original
+                    .get(field)
+                    .ok_or_else(|| format!("invalidInput: unknown tag field '{}'", field))?;
+                reverted.insert(field.clone(), value.clone());
+            }
+        }
+    }
+
+    serde_json::from_value(Value::Object(reverted)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> SongMetadata {
+        SongMetadata {
+            title: "Song".to_string(),
+            album: "Album".to_string(),
+            year: Some(1999),
+            duration: 180.0,
+            artists: vec!["Artist".to_string()],
+            genres: vec!["Rock".to_string()],
+            file_exists: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_and_revert() {
+        let original = metadata();
+        let mut current = metadata();
+        current.title = "Edited".to_string();
+        current.year = None;
+        current.extended.composer = Some("Composer".to_string());
+        current.times_played = 12;
+
+        let fields: Vec<String> = diff(&original, &current).into_iter().map(|d| d.field).collect();
+        assert_eq!(fields, ["composer", "title", "year"]);
+
+        let reverted = revert(&original, &current, Some(&["year".to_string()])).unwrap();
+        assert_eq!(reverted.year, Some(1999));
+        assert_eq!(reverted.title, "Edited");
+
+        let reverted = revert(&original, &current, None).unwrap();
+        assert!(diff(&original, &reverted).is_empty());
+        assert_eq!(reverted.times_played, 12);
+
+        assert!(revert(&original, &current, Some(&["times_played".to_string()])).is_err());
+    }
+}
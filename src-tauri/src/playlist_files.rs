@@ -0,0 +1,561 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use quick_xml::escape::{escape, resolve_predefined_entity};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::models::Song;
+
+/// The playlist file formats we read and write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaylistFormat {
+    M3u,
+    Xspf,
+    Pls,
+}
+
+impl PlaylistFormat {
+    /// The format of a playlist file, by extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            "pls" => Some(PlaylistFormat::Pls),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of a playlist file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistEntry {
+    /// The entry as written in the file.
+    pub location: String,
+    /// The local file it points to; `None` for URLs and unreadable locations.
+    pub path: Option<String>,
+    /// Where playback starts in the file, in seconds, for cue sheet tracks.
+    pub start: Option<f64>,
+    pub duration: Option<f64>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+impl PlaylistEntry {
+    /// How the entry is shown when it can't be matched: its location, or its artist and title
+    /// for XSPF tracks that have none.
+    pub fn label(&self) -> String {
+        if !self.location.is_empty() {
+            return self.location.clone();
+        }
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+            (None, Some(title)) => title.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// A playlist read from a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistFile {
+    pub name: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// Read a playlist file as UTF-8 (with or without a BOM), falling back to Latin-1 as used by
+/// older `.m3u` files.
+pub fn read_text(path: &Path) -> Result<String, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read playlist '{}': {}", path.display(), e))?;
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    };
+    Ok(content.trim_start_matches('\u{feff}').to_string())
+}
+
+/// Parse a playlist file. Relative locations are resolved against `base_dir`.
+pub fn parse(
+    format: PlaylistFormat,
+    content: &str,
+    base_dir: &Path,
+) -> Result<PlaylistFile, String> {
+    match format {
+        PlaylistFormat::M3u => Ok(parse_m3u(content, base_dir)),
+        PlaylistFormat::Xspf => parse_xspf(content, base_dir),
+        PlaylistFormat::Pls => Ok(parse_pls(content, base_dir)),
+    }
+}
+
+/// Write a playlist file. With `relative_to`, paths are written relative to that directory where
+/// they can be.
+pub fn render(
+    format: PlaylistFormat,
+    name: &str,
+    songs: &[Song],
+    relative_to: Option<&Path>,
+) -> String {
+    match format {
+        PlaylistFormat::M3u => render_m3u(name, songs, relative_to),
+        PlaylistFormat::Xspf => render_xspf(name, songs, relative_to),
+        PlaylistFormat::Pls => render_pls(songs, relative_to),
+    }
+}
+
+/// Parse an M3U or extended M3U playlist. Relative locations are resolved against `base_dir`.
+pub fn parse_m3u(content: &str, base_dir: &Path) -> PlaylistFile {
+    let mut playlist = PlaylistFile::default();
+    let mut next = PlaylistEntry::default();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (duration, title) = info.split_once(',').unwrap_or((info, ""));
+            // Attributes (`tvg-id="..."`) may follow the duration.
+            let duration = duration.split_whitespace().next().unwrap_or("");
+            next.duration = duration.parse().ok().filter(|d: &f64| *d >= 0.0);
+            next.title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+        } else if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            playlist.name = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+        } else if let Some(start) = line.strip_prefix("#EXTVLCOPT:start-time=") {
+            next.start = start.trim().parse().ok();
+        } else if !line.starts_with('#') {
+            next.location = line.to_string();
+            next.path = resolve_location(line, base_dir);
+            playlist.entries.push(std::mem::take(&mut next));
+        }
+    }
+    playlist
+}
+
+/// Write an extended M3U playlist. Cue sheet tracks get VLC's start and stop options, which
+/// other players ignore.
+pub fn render_m3u(name: &str, songs: &[Song], relative_to: Option<&Path>) -> String {
+    let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", single_line(name));
+    for song in songs {
+        out.push_str(&format!(
+            "#EXTINF:{},{}\n",
+            song.metadata.duration.round() as i64,
+            display_title(song)
+        ));
+        if let Some(range) = &song.range {
+            out.push_str(&format!("#EXTVLCOPT:start-time={}\n", range.start));
+            if let Some(end) = range.end {
+                out.push_str(&format!("#EXTVLCOPT:stop-time={}\n", end));
+            }
+        }
+        out.push_str(&location(&song.url, relative_to));
+        out.push('\n');
+    }
+    out
+}
+
+/// Parse an XSPF playlist. Tracks without a location are kept so they can be matched on their
+/// creator and title.
+pub fn parse_xspf(content: &str, base_dir: &Path) -> Result<PlaylistFile, String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("invalidInput: malformed XSPF: {}", e);
+    let mut reader = Reader::from_str(content);
+    let mut playlist = PlaylistFile::default();
+    let mut open: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut track: Option<PlaylistEntry> = None;
+
+    loop {
+        match reader.read_event().map_err(|e| invalid(&e))? {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if name == "track" && open.last().is_some_and(|p| p == "trackList") {
+                    track = Some(PlaylistEntry::default());
+                }
+                open.push(name);
+                text.clear();
+            }
+            Event::Text(t) => text.push_str(&t.decode().map_err(|e| invalid(&e))?),
+            Event::CData(t) => text.push_str(&t.decode().map_err(|e| invalid(&e))?),
+            Event::GeneralRef(r) => {
+                if let Some(c) = r.resolve_char_ref().map_err(|e| invalid(&e))? {
+                    text.push(c);
+                } else if let Some(value) =
+                    resolve_predefined_entity(&r.decode().map_err(|e| invalid(&e))?)
+                {
+                    text.push_str(value);
+                }
+            }
+            Event::End(_) => {
+                let name = open.pop().unwrap_or_default();
+                let value = std::mem::take(&mut text).trim().to_string();
+                let parent = open.last().map(String::as_str);
+                match (parent, name.as_str(), track.as_mut()) {
+                    (Some("playlist"), "title", _) => playlist.name = non_empty(value),
+                    (Some("trackList"), "track", _) => playlist.entries.extend(track.take()),
+                    // Only the first location is used; later ones are alternatives.
+                    (Some("track"), "location", Some(entry)) if entry.location.is_empty() => {
+                        entry.path = resolve_uri(&value, base_dir);
+                        entry.location = value;
+                    }
+                    (Some("track"), "title", Some(entry)) => entry.title = non_empty(value),
+                    (Some("track"), "creator", Some(entry)) => entry.artist = non_empty(value),
+                    (Some("track"), "album", Some(entry)) => entry.album = non_empty(value),
+                    (Some("track"), "duration", Some(entry)) => {
+                        entry.duration = value.parse::<f64>().ok().map(|ms| ms / 1000.0);
+                    }
+                    (Some("extension"), "option", Some(entry)) => {
+                        if let Some(start) = value.strip_prefix("start-time=") {
+                            entry.start = start.parse().ok();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(playlist)
+}
+
+/// Write an XSPF playlist with file URIs. Cue sheet tracks get VLC's start and stop options.
+pub fn render_xspf(name: &str, songs: &[Song], relative_to: Option<&Path>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\" xmlns:vlc=\"http://www.videolan.org/vlc/playlist/ns/0/\">\n");
+    out.push_str(&format!("  <title>{}</title>\n  <trackList>\n", escape(name)));
+    for song in songs {
+        let metadata = &song.metadata;
+        out.push_str("    <track>\n");
+        push_element(&mut out, "location", &uri(&song.url, relative_to));
+        push_element(&mut out, "title", &metadata.title);
+        push_element(&mut out, "creator", &metadata.artists.join(", "));
+        push_element(&mut out, "album", &metadata.album);
+        push_element(
+            &mut out,
+            "duration",
+            &((metadata.duration * 1000.0).round() as i64).to_string(),
+        );
+        if let Some(range) = &song.range {
+            out.push_str("      <extension application=\"http://www.videolan.org/vlc/playlist/0\">\n");
+            out.push_str(&format!("        <vlc:option>start-time={}</vlc:option>\n", range.start));
+            if let Some(end) = range.end {
+                out.push_str(&format!("        <vlc:option>stop-time={}</vlc:option>\n", end));
+            }
+            out.push_str("      </extension>\n");
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// Parse a PLS playlist. Entries keep the order of their numbers.
+pub fn parse_pls(content: &str, base_dir: &Path) -> PlaylistFile {
+    let mut entries: BTreeMap<usize, PlaylistEntry> = BTreeMap::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        let Some(digits) = key.find(|c: char| c.is_ascii_digit()) else {
+            continue;
+        };
+        let Ok(number) = key[digits..].parse::<usize>() else {
+            continue;
+        };
+        let entry = entries.entry(number).or_default();
+        match &key[..digits] {
+            "file" => {
+                entry.location = value.to_string();
+                entry.path = resolve_location(value, base_dir);
+            }
+            "title" => entry.title = non_empty(value.to_string()),
+            "length" => entry.duration = value.parse().ok().filter(|d: &f64| *d >= 0.0),
+            _ => {}
+        }
+    }
+
+    PlaylistFile {
+        name: None,
+        entries: entries
+            .into_values()
+            .filter(|e| !e.location.is_empty())
+            .collect(),
+    }
+}
+
+/// Write a PLS playlist. The format has no playlist name and no way to start inside a file, so
+/// cue sheet tracks point at the whole file.
+pub fn render_pls(songs: &[Song], relative_to: Option<&Path>) -> String {
+    let mut out = String::from("[playlist]\n");
+    for (i, song) in songs.iter().enumerate() {
+        let number = i + 1;
+        out.push_str(&format!("File{}={}\n", number, location(&song.url, relative_to)));
+        out.push_str(&format!("Title{}={}\n", number, display_title(song)));
+        out.push_str(&format!(
+            "Length{}={}\n",
+            number,
+            song.metadata.duration.round() as i64
+        ));
+    }
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", songs.len()));
+    out
+}
+
+/// `Artists - Title`, as M3U and PLS players show it.
+fn display_title(song: &Song) -> String {
+    let artists = song.metadata.artists.join(", ");
+    let title = if artists.is_empty() {
+        song.metadata.title.clone()
+    } else {
+        format!("{} - {}", artists, song.metadata.title)
+    };
+    single_line(&title)
+}
+
+fn push_element(out: &mut String, name: &str, value: &str) {
+    if !value.is_empty() {
+        out.push_str(&format!("      <{0}>{1}</{0}>\n", name, escape(value)));
+    }
+}
+
+/// The local path a playlist location points to: an absolute or relative path, or a `file://`
+/// URL.
+pub(crate) fn resolve_location(location: &str, base_dir: &Path) -> Option<String> {
+    let path = match location.strip_prefix("file://") {
+        Some(url) => {
+            let path = percent_decode(url.strip_prefix("localhost").unwrap_or(url))?;
+            // "file:///C:/Music/a.mp3"
+            match path.as_bytes().get(2) {
+                Some(b':') => PathBuf::from(&path[1..]),
+                _ => PathBuf::from(path),
+            }
+        }
+        None if location.contains("://") => return None,
+        None => base_dir.join(location.replace('\\', "/")),
+    };
+    Some(normalize(&path).to_string_lossy().to_string())
+}
+
+/// The local path an XSPF location points to: a `file://` URL, or a percent-encoded URI
+/// reference relative to the playlist.
+fn resolve_uri(location: &str, base_dir: &Path) -> Option<String> {
+    if location.contains("://") {
+        return resolve_location(location, base_dir);
+    }
+    resolve_location(&percent_decode(location)?, base_dir)
+}
+
+/// `path` as a `file://` URL, or as a relative URI reference when `relative_to` applies.
+fn uri(path: &str, relative_to: Option<&Path>) -> String {
+    let location = location(path, relative_to).replace('\\', "/");
+    let drive = location.as_bytes().get(1) == Some(&b':');
+    if !Path::new(&location).is_absolute() && !drive {
+        return percent_encode(&location);
+    }
+    let location = if drive {
+        format!("/{}", location)
+    } else {
+        location
+    };
+    format!("file://{}", percent_encode(&location))
+}
+
+/// `path` relative to `dir`, or as it is when they share no root.
+fn location(path: &str, relative_to: Option<&Path>) -> String {
+    let Some(dir) = relative_to else {
+        return path.to_string();
+    };
+    let path = normalize(Path::new(path));
+    let dir = normalize(dir);
+    let common = path
+        .components()
+        .zip(dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_string_lossy().to_string();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in dir.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(path.components().skip(common));
+    relative.to_string_lossy().to_string()
+}
+
+/// Drop `.` components and fold `..` ones into their parent.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.is_empty())
+}
+
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SongMetadata, TrackRange};
+
+    fn song(url: &str, title: &str, range: Option<TrackRange>) -> Song {
+        Song {
+            id: title.to_string(),
+            url: url.to_string(),
+            filename: title.to_string(),
+            metadata: SongMetadata {
+                title: title.to_string(),
+                album: "Album & Co".to_string(),
+                duration: 180.5,
+                artists: vec!["Band".to_string()],
+                file_exists: true,
+                ..Default::default()
+            },
+            available: true,
+            range,
+        }
+    }
+
+    #[test]
+    fn test_parse_m3u() {
+        let content = "#EXTM3U\n#PLAYLIST:Road Trip\n#EXTINF:215,Band - Song\nMusic/song.mp3\n\n\
+            #EXTINF:-1,Radio\nhttp://radio.example/stream\n\
+            #EXTVLCOPT:start-time=180\n/abs/Album.flac\n\
+            file:///abs/My%20Song.mp3\n";
+        let playlist = parse_m3u(content, Path::new("/home/me/lists"));
+
+        assert_eq!(playlist.name.as_deref(), Some("Road Trip"));
+        let paths: Vec<Option<&str>> = playlist.entries.iter().map(|e| e.path.as_deref()).collect();
+        assert_eq!(
+            paths,
+            [
+                Some("/home/me/lists/Music/song.mp3"),
+                None,
+                Some("/abs/Album.flac"),
+                Some("/abs/My Song.mp3"),
+            ]
+        );
+        assert_eq!(playlist.entries[0].duration, Some(215.0));
+        assert_eq!(playlist.entries[0].title.as_deref(), Some("Band - Song"));
+        assert_eq!(playlist.entries[1].duration, None);
+        assert_eq!(playlist.entries[2].start, Some(180.0));
+        assert_eq!(playlist.entries[3].start, None);
+    }
+
+    #[test]
+    fn test_relative_locations() {
+        let dir = Some(Path::new("/music/lists"));
+        assert_eq!(location("/music/rock/a.mp3", dir), "../rock/a.mp3");
+        assert_eq!(location("/music/lists/a.mp3", dir), "a.mp3");
+        assert_eq!(location("/music/rock/a.mp3", None), "/music/rock/a.mp3");
+        assert_eq!(
+            resolve_location("../rock/a.mp3", Path::new("/music/lists")).as_deref(),
+            Some("/music/rock/a.mp3")
+        );
+    }
+
+    #[test]
+    fn test_xspf_round_trip() {
+        let songs = [
+            song("/music/rock/My Song.mp3", "Song <1>", None),
+            song(
+                "/music/Album.flac",
+                "Two",
+                Some(TrackRange {
+                    start: 180.0,
+                    end: Some(360.0),
+                }),
+            ),
+        ];
+        let content = render_xspf("Mix", &songs, Some(Path::new("/music/lists")));
+        assert!(content.contains("<location>../rock/My%20Song.mp3</location>"));
+        assert!(content.contains("<title>Song &lt;1&gt;</title>"));
+        assert!(content.contains("<duration>180500</duration>"));
+
+        let playlist = parse_xspf(&content, Path::new("/music/lists")).unwrap();
+        assert_eq!(playlist.name.as_deref(), Some("Mix"));
+        let entry = &playlist.entries[0];
+        assert_eq!(entry.path.as_deref(), Some("/music/rock/My Song.mp3"));
+        assert_eq!(entry.title.as_deref(), Some("Song <1>"));
+        assert_eq!(entry.artist.as_deref(), Some("Band"));
+        assert_eq!(entry.album.as_deref(), Some("Album & Co"));
+        assert_eq!(entry.duration, Some(180.5));
+        assert_eq!(playlist.entries[1].start, Some(180.0));
+
+        let absolute = render_xspf("Mix", &songs, None);
+        assert!(absolute.contains("<location>file:///music/rock/My%20Song.mp3</location>"));
+
+        let bare = "<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\"><trackList>\
+            <track><title>Lost</title><creator>Band</creator></track></trackList></playlist>";
+        let playlist = parse_xspf(bare, Path::new("/")).unwrap();
+        assert_eq!(playlist.entries[0].path, None);
+        assert_eq!(playlist.entries[0].label(), "Band - Lost");
+        assert!(parse_xspf("<playlist><trackList></playlist>", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_pls_round_trip() {
+        let songs = [song("/music/a.mp3", "One", None)];
+        let content = render_pls(&songs, None);
+        assert_eq!(
+            content,
+            "[playlist]\nFile1=/music/a.mp3\nTitle1=Band - One\nLength1=181\nNumberOfEntries=1\nVersion=2\n"
+        );
+
+        let content = "[playlist]\nFile2=b.mp3\nfile1=/music/a.mp3\nLength1=-1\nNumberOfEntries=2\n";
+        let playlist = parse_pls(content, Path::new("/music"));
+        let paths: Vec<Option<&str>> = playlist.entries.iter().map(|e| e.path.as_deref()).collect();
+        assert_eq!(paths, [Some("/music/a.mp3"), Some("/music/b.mp3")]);
+        assert_eq!(playlist.entries[0].duration, None);
+    }
+}
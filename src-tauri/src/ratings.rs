@@ -0,0 +1,104 @@
+/// POPM bytes written for each half star of a 0-5 star rating, as used by Windows Media
+/// Player and MusicBee.
+const POPM_BYTES: [(f32, u8); 11] = [
+    (0.0, 0),
+    (0.5, 13),
+    (1.0, 1),
+    (1.5, 54),
+    (2.0, 64),
+    (2.5, 118),
+    (3.0, 128),
+    (3.5, 186),
+    (4.0, 196),
+    (4.5, 242),
+    (5.0, 255),
+];
+
+/// Clamp a rating to 0-5 and round it to the nearest half star. Non-finite values are dropped.
+pub fn normalize_rating(rating: f64) -> Option<f32> {
+    if !rating.is_finite() {
+        return None;
+    }
+    Some(((rating.clamp(0.0, 5.0) * 2.0).round() / 2.0) as f32)
+}
+
+/// The POPM byte for a rating.
+pub fn stars_to_popm(stars: f32) -> u8 {
+    let stars = normalize_rating(stars as f64).unwrap_or(0.0);
+    POPM_BYTES
+        .iter()
+        .find(|(s, _)| *s == stars)
+        .map(|(_, byte)| *byte)
+        .unwrap_or(0)
+}
+
+/// The rating for a POPM byte; 0 means unrated.
+///
+/// Bytes we write map back exactly; anything else falls into the Windows Media Player
+/// bands (1-31 is one star, 32-95 two, ...).
+pub fn popm_to_stars(byte: u8) -> Option<f32> {
+    if byte == 0 {
+        return None;
+    }
+    if let Some((stars, _)) = POPM_BYTES.iter().find(|(_, b)| *b == byte) {
+        return Some(*stars);
+    }
+    Some(match byte {
+        1..=31 => 1.0,
+        32..=95 => 2.0,
+        96..=159 => 3.0,
+        160..=223 => 4.0,
+        _ => 5.0,
+    })
+}
+
+/// The value written to a Vorbis/APE/MP4 RATING field (0-100).
+pub fn stars_to_percent(stars: f32) -> u32 {
+    (normalize_rating(stars as f64).unwrap_or(0.0) * 20.0).round() as u32
+}
+
+/// The rating for a RATING field value. Values up to 5 are read as stars, up to 10 as
+/// half stars and anything larger as a percentage.
+pub fn rating_field_to_stars(value: &str) -> Option<f32> {
+    let value: f64 = value.trim().parse().ok()?;
+    if value <= 0.0 {
+        return None;
+    }
+    let stars = if value <= 5.0 {
+        value
+    } else if value <= 10.0 {
+        value / 2.0
+    } else {
+        value / 20.0
+    };
+    normalize_rating(stars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_popm_round_trip() {
+        for half_stars in 1..=10 {
+            let stars = half_stars as f32 / 2.0;
+            assert_eq!(popm_to_stars(stars_to_popm(stars)), Some(stars));
+        }
+        assert_eq!(popm_to_stars(0), None);
+        assert_eq!(popm_to_stars(20), Some(1.0));
+        assert_eq!(popm_to_stars(100), Some(3.0));
+        assert_eq!(popm_to_stars(230), Some(5.0));
+    }
+
+    #[test]
+    fn test_rating_field_scales() {
+        assert_eq!(rating_field_to_stars("4"), Some(4.0));
+        assert_eq!(rating_field_to_stars("7"), Some(3.5));
+        assert_eq!(rating_field_to_stars("80"), Some(4.0));
+        assert_eq!(rating_field_to_stars("0"), None);
+        assert_eq!(rating_field_to_stars("n/a"), None);
+        assert_eq!(stars_to_percent(3.5), 70);
+        assert_eq!(normalize_rating(4.3), Some(4.5));
+        assert_eq!(normalize_rating(9.0), Some(5.0));
+    }
+}
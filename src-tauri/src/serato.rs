@@ -0,0 +1,328 @@
+use base64::alphabet::STANDARD;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+
+use crate::dj_library::{hex, rgb, DjCue};
+
+/// GEOB description of the frame holding cues, loops and the track color.
+pub const MARKERS2: &str = "Serato Markers2";
+/// GEOB description of the frame holding the beatgrid.
+pub const BEATGRID: &str = "Serato BeatGrid";
+
+/// Cue and loop slots Serato has.
+pub const SLOTS: usize = 8;
+/// Serato pads the Markers2 frame to at least this many bytes.
+const MARKERS2_MIN_LEN: usize = 470;
+/// Color Serato gives new cues.
+const DEFAULT_CUE_COLOR: (u8, u8, u8) = (0xcc, 0x00, 0x00);
+/// Color Serato gives every loop.
+const LOOP_COLOR: (u8, u8, u8) = (0x27, 0xaa, 0xe1);
+
+/// Serato writes its base64 without padding, sometimes with a stray character at the end.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// What Serato keeps in a file's tags.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeratoData {
+    /// Cues and loops, in seconds from the start of the file.
+    pub cues: Vec<DjCue>,
+    /// The tempo of the beatgrid's last section.
+    pub bpm: Option<f32>,
+}
+
+/// Read the cues and loops of a `Serato Markers2` frame. The track color and other entries are
+/// skipped.
+pub fn parse_markers2(data: &[u8]) -> Result<Vec<DjCue>, String> {
+    let mut cues = Vec::new();
+    for (name, payload) in markers2_entries(data)? {
+        let cue = match name.as_str() {
+            "CUE" => parse_cue(&payload),
+            "LOOP" => parse_loop(&payload),
+            _ => continue,
+        };
+        cues.push(cue.ok_or_else(|| format!("malformed Serato {} entry", name))?);
+    }
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(cues)
+}
+
+/// The tempo of a `Serato BeatGrid` frame, from its terminal marker.
+pub fn parse_beatgrid(data: &[u8]) -> Option<f32> {
+    let count = u32::from_be_bytes(data.get(2..6)?.try_into().ok()?) as usize;
+    let last = 6 + count.checked_sub(1)? * 8;
+    let bpm = f32::from_be_bytes(data.get(last + 4..last + 8)?.try_into().ok()?);
+    Some(bpm).filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+}
+
+/// A `Serato Markers2` frame holding `cues`: points become cues in their hot cue slot, or else
+/// the first free one by position, and ranges loops in their first eight slots by position. The
+/// track color and other entries of `existing` are kept.
+pub fn render_markers2(cues: &[DjCue], existing: Option<&[u8]>) -> Vec<u8> {
+    let kept: Vec<(String, Vec<u8>)> = existing
+        .and_then(|data| markers2_entries(data).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(name, _)| name != "CUE" && name != "LOOP")
+        .collect();
+
+    let mut sorted: Vec<&DjCue> = cues.iter().collect();
+    sorted.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let points = cue_slots(sorted.iter().copied().filter(|c| c.end.is_none()));
+    let loops = sorted.iter().filter(|c| c.end.is_some()).take(SLOTS);
+
+    let mut entries = Vec::new();
+    match kept.iter().find(|(name, _)| name == "COLOR") {
+        Some(color) => entries.push(color.clone()),
+        None => entries.push(("COLOR".to_string(), vec![0x00, 0xff, 0xff, 0xff])),
+    }
+    for (slot, cue) in points {
+        entries.push(("CUE".to_string(), render_cue(slot, cue)));
+    }
+    for (slot, cue) in loops.enumerate() {
+        entries.push(("LOOP".to_string(), render_loop(slot, cue)));
+    }
+    entries.extend(kept.iter().filter(|(name, _)| name != "COLOR").cloned());
+    if !entries.iter().any(|(name, _)| name == "BPMLOCK") {
+        entries.push(("BPMLOCK".to_string(), vec![0x00]));
+    }
+
+    let mut body = vec![0x01, 0x01];
+    for (name, payload) in &entries {
+        body.extend_from_slice(name.as_bytes());
+        body.push(0);
+        body.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        body.extend_from_slice(payload);
+    }
+    body.push(0);
+
+    let encoded = BASE64.encode(&body);
+    let mut data = vec![0x01, 0x01];
+    for (i, line) in encoded.as_bytes().chunks(72).enumerate() {
+        if i > 0 {
+            data.push(b'\n');
+        }
+        data.extend_from_slice(line);
+    }
+    if data.len() < MARKERS2_MIN_LEN {
+        data.resize(MARKERS2_MIN_LEN, 0);
+    }
+    data
+}
+
+/// Cues in slot order, each in its hot cue slot when set and not taken by an earlier cue, the
+/// rest in the free slots by position. Cues past the last free slot are left out.
+fn cue_slots<'a>(cues: impl Iterator<Item = &'a DjCue>) -> Vec<(usize, &'a DjCue)> {
+    let cues: Vec<&DjCue> = cues.collect();
+    let mut taken = [false; SLOTS];
+    let mut slots: Vec<Option<usize>> = cues
+        .iter()
+        .map(|cue| {
+            let slot = cue.hot_cue.filter(|slot| *slot < SLOTS && !taken[*slot])?;
+            taken[slot] = true;
+            Some(slot)
+        })
+        .collect();
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = (0..SLOTS).find(|free| !taken[*free]);
+        if let Some(free) = *slot {
+            taken[free] = true;
+        }
+    }
+
+    let mut slotted: Vec<(usize, &DjCue)> = slots
+        .into_iter()
+        .zip(cues)
+        .filter_map(|(slot, cue)| Some((slot?, cue)))
+        .collect();
+    slotted.sort_by_key(|(slot, _)| *slot);
+    slotted
+}
+
+/// The named entries of a `Serato Markers2` frame: a two-byte version, then base64 text
+/// (wrapped, and padded with nulls) which decodes to the version again and the entries.
+fn markers2_entries(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let text: Vec<u8> = data
+        .get(2..)
+        .unwrap_or_default()
+        .iter()
+        .copied()
+        .filter(|b| !matches!(b, b'\n' | b'\r' | 0))
+        .collect();
+    let text = match text.len() % 4 {
+        1 => &text[..text.len() - 1],
+        _ => &text[..],
+    };
+    let body = BASE64
+        .decode(text)
+        .map_err(|e| format!("malformed Serato Markers2 frame: {}", e))?;
+
+    let mut entries = Vec::new();
+    let mut rest = body.get(2..).unwrap_or_default();
+    while let Some(end) = rest.iter().position(|b| *b == 0).filter(|end| *end > 0) {
+        let name = String::from_utf8_lossy(&rest[..end]).to_string();
+        let length = rest
+            .get(end + 1..end + 5)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or("truncated Serato Markers2 frame")?;
+        let payload = rest
+            .get(end + 5..end + 5 + length)
+            .ok_or("truncated Serato Markers2 frame")?;
+        entries.push((name, payload.to_vec()));
+        rest = &rest[end + 5 + length..];
+    }
+    Ok(entries)
+}
+
+// CUE: 0x00, slot, position (ms), 0x00, RGB, 0x0000, name\0
+fn parse_cue(payload: &[u8]) -> Option<DjCue> {
+    let position = u32::from_be_bytes(payload.get(2..6)?.try_into().ok()?);
+    let color = payload.get(7..10)?;
+    Some(DjCue {
+        start: position as f64 / 1000.0,
+        end: None,
+        name: label(payload.get(12..)?),
+        color: Some(hex(color[0], color[1], color[2])),
+        hot_cue: Some(*payload.get(1)? as usize),
+    })
+}
+
+fn render_cue(slot: usize, cue: &DjCue) -> Vec<u8> {
+    let (r, g, b) = cue.color.as_deref().and_then(rgb).unwrap_or(DEFAULT_CUE_COLOR);
+    let mut payload = vec![0x00, slot as u8];
+    payload.extend_from_slice(&millis(cue.start).to_be_bytes());
+    payload.extend_from_slice(&[0x00, r, g, b, 0x00, 0x00]);
+    payload.extend_from_slice(cue.name.as_deref().unwrap_or("").as_bytes());
+    payload.push(0);
+    payload
+}
+
+// LOOP: 0x00, slot, start (ms), end (ms), 0xffffffff, 0x00 + RGB, 0x00, locked, name\0
+fn parse_loop(payload: &[u8]) -> Option<DjCue> {
+    let start = u32::from_be_bytes(payload.get(2..6)?.try_into().ok()?);
+    let end = u32::from_be_bytes(payload.get(6..10)?.try_into().ok()?);
+    let color = payload.get(15..18)?;
+    Some(DjCue {
+        start: start as f64 / 1000.0,
+        end: Some(end as f64 / 1000.0),
+        name: label(payload.get(20..)?),
+        color: Some(hex(color[0], color[1], color[2])),
+        hot_cue: None,
+    })
+}
+
+fn render_loop(slot: usize, cue: &DjCue) -> Vec<u8> {
+    let (r, g, b) = cue.color.as_deref().and_then(rgb).unwrap_or(LOOP_COLOR);
+    let mut payload = vec![0x00, slot as u8];
+    payload.extend_from_slice(&millis(cue.start).to_be_bytes());
+    payload.extend_from_slice(&millis(cue.end.unwrap_or(cue.start)).to_be_bytes());
+    payload.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x00, r, g, b, 0x00, 0x00]);
+    payload.extend_from_slice(cue.name.as_deref().unwrap_or("").as_bytes());
+    payload.push(0);
+    payload
+}
+
+/// A null-terminated label; empty ones are none.
+fn label(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    Some(String::from_utf8_lossy(&bytes[..end]).trim().to_string()).filter(|l| !l.is_empty())
+}
+
+fn millis(seconds: f64) -> u32 {
+    (seconds.max(0.0) * 1000.0).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start: f64, end: Option<f64>, name: Option<&str>, color: Option<&str>) -> DjCue {
+        DjCue {
+            start,
+            end,
+            name: name.map(str::to_string),
+            color: color.map(str::to_string),
+            hot_cue: None,
+        }
+    }
+
+    #[test]
+    fn test_markers2_round_trip() {
+        let cues = vec![
+            cue(64.5, None, Some("Drop"), Some("#00ff00")),
+            cue(16.0, Some(24.0), Some("Intro loop"), None),
+            cue(1.25, None, None, None),
+        ];
+        let data = render_markers2(&cues, None);
+        assert_eq!(&data[..2], &[0x01, 0x01]);
+        assert!(data.len() >= MARKERS2_MIN_LEN);
+
+        let read = parse_markers2(&data).unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(read[0].start, 1.25);
+        assert_eq!(read[0].hot_cue, Some(0));
+        assert_eq!(read[0].color.as_deref(), Some("#cc0000"));
+        assert_eq!((read[1].start, read[1].end), (16.0, Some(24.0)));
+        assert_eq!(read[1].color.as_deref(), Some("#27aae1"));
+        assert_eq!(read[2].name.as_deref(), Some("Drop"));
+        assert_eq!(read[2].color.as_deref(), Some("#00ff00"));
+        assert_eq!(read[2].hot_cue, Some(1));
+    }
+
+    #[test]
+    fn test_render_markers2_keeps_other_entries() {
+        let mut body = vec![0x01, 0x01];
+        for (name, payload) in [
+            ("COLOR", vec![0x00, 0x99, 0xff, 0x99]),
+            ("CUE", render_cue(0, &cue(3.0, None, Some("Old"), None))),
+            ("BPMLOCK", vec![0x01]),
+        ] {
+            body.extend_from_slice(name.as_bytes());
+            body.push(0);
+            body.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            body.extend_from_slice(&payload);
+        }
+        body.push(0);
+        let mut existing = vec![0x01, 0x01];
+        existing.extend_from_slice(BASE64.encode(&body).as_bytes());
+
+        let data = render_markers2(&[cue(8.0, None, Some("New"), None)], Some(&existing));
+        let entries = markers2_entries(&data).unwrap();
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["COLOR", "CUE", "BPMLOCK"]);
+        assert_eq!(entries[0].1, [0x00, 0x99, 0xff, 0x99]);
+        assert_eq!(entries[2].1, [0x01]);
+        assert_eq!(parse_markers2(&data).unwrap()[0].name.as_deref(), Some("New"));
+    }
+
+    #[test]
+    fn test_render_markers2_keeps_hot_cue_slots() {
+        let mut drop = cue(64.5, None, Some("Drop"), None);
+        drop.hot_cue = Some(3);
+        let mut intro = cue(1.0, None, Some("Intro"), None);
+        intro.hot_cue = Some(3);
+        let cues = [cue(0.5, None, None, None), drop, intro];
+
+        let read = parse_markers2(&render_markers2(&cues, None)).unwrap();
+        let slots: Vec<(f64, Option<usize>)> = read.iter().map(|c| (c.start, c.hot_cue)).collect();
+        assert_eq!(slots, [(0.5, Some(0)), (1.0, Some(3)), (64.5, Some(1))]);
+    }
+
+    #[test]
+    fn test_parse_beatgrid() {
+        let mut data = vec![0x01, 0x00];
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&0.05f32.to_be_bytes());
+        data.extend_from_slice(&64u32.to_be_bytes());
+        data.extend_from_slice(&30.1f32.to_be_bytes());
+        data.extend_from_slice(&126.0f32.to_be_bytes());
+        data.push(0x00);
+        assert_eq!(parse_beatgrid(&data), Some(126.0));
+        assert_eq!(parse_beatgrid(&[0x01, 0x00, 0, 0, 0, 0]), None);
+    }
+}
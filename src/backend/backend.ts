@@ -54,6 +54,19 @@ export interface BulkUpdateSongsPayload {
   ids: string[];
   updates: Partial<SongMetadata>;
   update_id3?: boolean;
+  on_tag_failure?: 'keep' | 'rollback';
+}
+
+export interface SongUpdateResult {
+  id: string;
+  dbUpdated: boolean;
+  tagsWritten: boolean | null;
+  error: string | null;
+}
+
+export interface BulkUpdateSongsResponse {
+  updatedSongs: number;
+  results: SongUpdateResult[];
 }
 
 export interface GetPlaylistsQuery {
@@ -121,7 +134,9 @@ export interface BackendService {
   updateSong(payload: UpdateSongPayload): Promise<Song | null>;
   deleteSong(songId: string): Promise<boolean>;
   getSongBpm(songId: string): Promise<number | null>;
-  bulkUpdateSongs(payload: BulkUpdateSongsPayload): Promise<number>;
+  /** The song's cover art as a data URL, scaled to fit `size` pixels when given. */
+  getCover(songId: string, size?: number): Promise<string | null>;
+  bulkUpdateSongs(payload: BulkUpdateSongsPayload): Promise<BulkUpdateSongsResponse>;
   getPlaylists(query: GetPlaylistsQuery): Promise<Playlist[]>;
   createPlaylist(name: string): Promise<Playlist>;
   deletePlaylist(playlistId: string): Promise<void>;
@@ -3,6 +3,7 @@ import {
   AddSongToPlaylistPayload,
   BackendService,
   BulkUpdateSongsPayload,
+  BulkUpdateSongsResponse,
   GetSongsGroupsQuery,
   GetSongsGroupsResponse,
   GetPlaylistSongsQuery,
@@ -77,7 +78,11 @@ export default class TauriBackendService implements BackendService {
     return await invoke<number | null>('get_song_bpm', { songId });
   }
 
-  async bulkUpdateSongs(payload: BulkUpdateSongsPayload): Promise<number> {
-    return await invoke<number>('bulk_update_songs', { payload });
+  getCover(songId: string, size?: number): Promise<string | null> {
+    return invoke<string | null>('get_cover', { songId, size });
+  }
+
+  async bulkUpdateSongs(payload: BulkUpdateSongsPayload): Promise<BulkUpdateSongsResponse> {
+    return await invoke<BulkUpdateSongsResponse>('bulk_update_songs', { payload });
   }
 }
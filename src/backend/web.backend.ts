@@ -2,6 +2,7 @@ import {
   AddSongToPlaylistPayload,
   BackendService,
   BulkUpdateSongsPayload,
+  BulkUpdateSongsResponse,
   GetSongsGroupsQuery,
   GetSongsGroupsResponse,
   GetPlaylistSongsQuery,
@@ -20,6 +21,11 @@ export default class WebBackendService implements BackendService {
     return Promise.resolve(null);
   }
 
+  getCover(songId: string, size?: number): Promise<string | null> {
+    console.error('Method not implemented.', songId, size);
+    return Promise.resolve(null);
+  }
+
   getSongsGroups(query: GetSongsGroupsQuery): Promise<GetSongsGroupsResponse> {
     console.error('getSongsGroups not implemented in WebBackendService', query);
     return Promise.resolve({ groups: [] });
@@ -86,8 +92,8 @@ export default class WebBackendService implements BackendService {
     return Promise.resolve(false);
   }
 
-  bulkUpdateSongs(payload: BulkUpdateSongsPayload): Promise<number> {
+  bulkUpdateSongs(payload: BulkUpdateSongsPayload): Promise<BulkUpdateSongsResponse> {
     console.error('Method not implemented.', payload);
-    return Promise.resolve(0);
+    return Promise.resolve({ updatedSongs: 0, results: [] });
   }
 }
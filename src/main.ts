@@ -80,7 +80,7 @@ export async function initApp() {
   state.addListener('mode', async (mode: string) => container.setAttribute('data-mode', mode));
 
   initNav(state);
-  document.getElementById('player-container')!.appendChild(Player(state));
+  document.getElementById('player-container')!.appendChild(Player(state, backendService));
   initNotifications(state, document.getElementById('notifications') as HTMLUListElement);
 
   await initFileDragAndDrop(state);
@@ -12,10 +12,10 @@ export function setPlayToggleState(elms: PlayerElements, isPlaying: boolean): vo
   }
 }
 
-export function renderTrackMetadata(elms: PlayerElements, data: SongMetadata): void {
+export function renderTrackMetadata(elms: PlayerElements, data: SongMetadata, cover: string | null): void {
   elms.title.textContent = data.title;
   elms.artist.textContent = Array.isArray(data.artists) ? data.artists.join(', ') : data.artists || 'Unknown Artist';
-  elms.cover.src = data.image || '';
+  elms.cover.src = cover || '';
 
   elms.position.value = '00:00';
   elms.position.max = data.duration ? Math.floor(data.duration).toString() : '0';
@@ -9,6 +9,7 @@ export function wirePlayerState(
     audio: PlayerAudio;
     elms: PlayerElements;
     loadFile: (path: string) => Promise<File>;
+    getCover: (song: Song) => Promise<string | null>;
     onEnded: () => void;
     setDuration: (durationSeconds: number) => void;
   }
@@ -29,7 +30,7 @@ export function wirePlayerState(
 
     deps.setDuration(song.metadata.duration);
     deps.audio.setSourceFromFile(await deps.loadFile(song.url));
-    renderTrackMetadata(deps.elms, song.metadata);
+    renderTrackMetadata(deps.elms, song.metadata, await deps.getCover(song));
     if (isPlaying) {
       await deps.audio.play();
     }
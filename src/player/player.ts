@@ -1,6 +1,7 @@
 import PlayerUi from './PlayerUi.js';
 
 import { State } from '../types.ts';
+import type { BackendService } from '../backend/backend.ts';
 import loadFile from '../files/load-file.ts';
 
 import { getPlayerElements } from './player-elements.ts';
@@ -13,7 +14,10 @@ import type { Song } from '../types.ts';
 import RepeatControl from '../queue/RepeatControl.tsx';
 import Knob from '../ui-components/knob/Knob.tsx';
 
-export default function Player(state: State) {
+/** Size the cover art is fetched at for the player. */
+const COVER_SIZE = 320;
+
+export default function Player(state: State, backendService: BackendService) {
   const form: HTMLFormElement = PlayerUi(
     RepeatControl(state),
     Knob('Playback Rate', state.playbackRate, 50, 200, 5, elm => state.bidi('playbackRate', elm, 'value', 'input')),
@@ -62,6 +66,13 @@ export default function Player(state: State) {
     audio,
     elms,
     loadFile,
+    getCover: song =>
+      song.metadata.image
+        ? backendService.getCover(song.id, COVER_SIZE).catch(error => {
+            console.error('Failed to load cover:', error);
+            return null;
+          })
+        : Promise.resolve(null),
     onEnded,
     setDuration: d => {
       currentDuration = d;
@@ -22,7 +22,8 @@ export default (
   columns: string[],
   onToggleSong: (song: Song, checked: boolean) => void,
   onPlaySong: (song: Song) => void,
-  onSongCheckboxClick: (song: Song, checked: boolean, shiftKey: boolean, visibleSongs: Song[]) => void
+  onSongCheckboxClick: (song: Song, checked: boolean, shiftKey: boolean, visibleSongs: Song[]) => void,
+  getCover: (songId: string) => Promise<string | null>
 ) => {
   const tableBody = (
     <tbody>
@@ -52,16 +53,27 @@ export default (
                       />
                     </td>
                   );
-                case 'artwork':
+                case 'artwork': {
+                  if (!song.metadata.image) {
+                    return (
+                      <td onclick={play} class="artwork-cell">
+                        N/A
+                      </td>
+                    );
+                  }
+                  // metadata.image is a cover id; the thumbnail is loaded from the cover cache.
+                  const thumbnail = (<img alt="Artwork" class="artwork-thumbnail" />) as HTMLImageElement;
+                  getCover(song.id)
+                    .then(src => {
+                      if (src) thumbnail.src = src;
+                    })
+                    .catch(error => console.error('Failed to load cover:', error));
                   return (
                     <td onclick={play} class="artwork-cell">
-                      {song.metadata.image ? (
-                        <img src={song.metadata.image} alt="Artwork" class="artwork-thumbnail" />
-                      ) : (
-                        'N/A'
-                      )}
+                      {thumbnail}
                     </td>
                   );
+                }
                 case 'title':
                   return (
                     <td onclick={play} class="text-cell" title={song.metadata.title}>
@@ -11,6 +11,9 @@ import getOnEvent from '../utils/on-event.ts';
 import { getSongsGenres } from './analyze-genres.ts';
 import { getSongsBPMs } from './analyze-bpm.ts';
 
+/** Size the tag editor's cover preview is fetched at. */
+const COVER_PREVIEW_SIZE = 320;
+
 export type GetCurrentGroupBy = () => SongMetadataAttribute[];
 
 export type SongDatabaseActionHandlerDeps = {
@@ -70,7 +73,8 @@ export function createSongDatabaseActionHandler({
           songs,
           (songId: string) => backendService.getSongBpm(songId),
           getSongGenres,
-          notifier
+          notifier,
+          (songId: string) => backendService.getCover(songId, COVER_PREVIEW_SIZE)
         )) as Id3TagEditorResult | null | undefined;
 
         if (editResult) {
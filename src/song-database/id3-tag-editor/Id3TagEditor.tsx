@@ -12,6 +12,7 @@ import { getClipboardImageFile, getDroppedImageFile, normalizeCoverImageToDataUr
 
 type GetSongBpm = (songId: string) => Promise<number | null>;
 type GetSongGenres = (songId: string) => Promise<string[] | null>;
+type GetCover = (songId: string) => Promise<string | null>;
 
 function toggleField(e: Event) {
   const checkbox = e.target as HTMLInputElement;
@@ -25,7 +26,8 @@ export default function Id3TagEditor(
   handleSubmit: (e: SubmitEvent) => void,
   getSongBpm?: GetSongBpm,
   getSongGenres?: GetSongGenres,
-  notifier?: Notifier
+  notifier?: Notifier,
+  getCover?: GetCover
 ): HTMLFormElement {
   const notify = getNotify(notifier);
 
@@ -502,7 +504,7 @@ export default function Id3TagEditor(
           />
           <label for="tag-image">Cover:</label>
           <div class="cover-controls">
-            <img id="cover-preview" class="cover-preview" alt="Cover preview" src={commonTags.image || 'data:,'} />
+            <img id="cover-preview" class="cover-preview" alt="Cover preview" src="data:," />
             <div
               id="cover-dropzone"
               class="cover-dropzone"
@@ -666,6 +668,17 @@ export default function Id3TagEditor(
     </form>
   ) as HTMLFormElement;
 
+  // The common cover is a cover id; preview it from the cover cache unless another was chosen meanwhile.
+  if (commonTags.image && getCover) {
+    const preview = form.querySelector('#cover-preview') as HTMLImageElement;
+    const hidden = form.querySelector('#tag-image-data') as HTMLInputElement;
+    getCover(songs[0].id)
+      .then(src => {
+        if (src && hidden.value === commonTags.image) preview.src = src;
+      })
+      .catch(error => console.error('Failed to load cover:', error));
+  }
+
   // Ensure paste works even when focus isn't on the dropzone.
   form.addEventListener('paste', onCoverPaste as unknown as EventListener);
 
@@ -5,6 +5,7 @@ import Id3TagEditorUI from './Id3TagEditor.tsx';
 
 type GetSongBpm = (songId: string) => Promise<number | null>;
 type GetSongGenres = (songId: string) => Promise<string[] | null>;
+type GetCover = (songId: string) => Promise<string | null>;
 
 export type Id3TagEditorResult = {
   updatedTags: Partial<SongMetadata>;
@@ -17,6 +18,7 @@ interface Id3TagEditorProps {
   getSongBpm: GetSongBpm;
   getSongGenres?: GetSongGenres;
   notifier?: Notifier;
+  getCover?: GetCover;
   onSubmit: (result: Id3TagEditorResult | null) => void;
 }
 
@@ -129,15 +131,23 @@ function handleSubmit(e: SubmitEvent, onSubmit: (result: Id3TagEditorResult | nu
   onSubmit({ updatedTags, analyzedBpms, analyzedGenres });
 }
 
-function Id3TagEditor({ songs, getSongBpm, getSongGenres, notifier, onSubmit }: Id3TagEditorProps) {
-  return Id3TagEditorUI(songs, (e: SubmitEvent) => handleSubmit(e, onSubmit), getSongBpm, getSongGenres, notifier);
+function Id3TagEditor({ songs, getSongBpm, getSongGenres, notifier, getCover, onSubmit }: Id3TagEditorProps) {
+  return Id3TagEditorUI(
+    songs,
+    (e: SubmitEvent) => handleSubmit(e, onSubmit),
+    getSongBpm,
+    getSongGenres,
+    notifier,
+    getCover
+  );
 }
 
 export default async function editId3Tags(
   songs: Song[],
   getSongBpm: GetSongBpm,
   getSongGenres?: GetSongGenres,
-  notifier?: Notifier
+  notifier?: Notifier,
+  getCover?: GetCover
 ): Promise<Id3TagEditorResult | null> {
-  return openInModal(Id3TagEditor, { songs, getSongBpm, getSongGenres, notifier, onSubmit: () => {} });
+  return openInModal(Id3TagEditor, { songs, getSongBpm, getSongGenres, notifier, getCover, onSubmit: () => {} });
 }
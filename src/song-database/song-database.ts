@@ -24,6 +24,9 @@ import getOnEvent from '../utils/on-event.ts';
 import { getSongsGenres } from './analyze-genres.ts';
 import { getSongsBPMs } from './analyze-bpm.ts';
 
+/** Cover thumbnails are fetched at this size, enough for the artwork column on HiDPI screens. */
+const THUMBNAIL_SIZE = 80;
+
 // const allColumns = ['select', 'artwork', 'title', 'artists', 'album', 'genre', 'year', 'bpm', 'duration', 'comment'];
 
 export default function SongDatabase(state: State, backendService: BackendService) {
@@ -45,6 +48,8 @@ export default function SongDatabase(state: State, backendService: BackendServic
       onChangeSort,
     });
 
+  const getThumbnail = (songId: string) => backendService.getCover(songId, THUMBNAIL_SIZE);
+
   const getBody = () => {
     const selectedSongIds = new Set(Array.from(selectedSongs).map(s => s.id));
     const newContent = SongDatabaseTableBody(
@@ -53,7 +58,8 @@ export default function SongDatabase(state: State, backendService: BackendServic
       getColumns(),
       onToggleSong,
       onSongSelected,
-      onSongCheckboxClick
+      onSongCheckboxClick,
+      getThumbnail
     );
     newContent.onsubmit = onFormSubmitted;
     return newContent;
@@ -199,7 +205,8 @@ export default function SongDatabase(state: State, backendService: BackendServic
     columns,
     onToggleSong,
     onSongSelected,
-    onSongCheckboxClick
+    onSongCheckboxClick,
+    getThumbnail
   );
   let header = SongDatabaseTableHeader({
     columns,
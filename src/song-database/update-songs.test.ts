@@ -62,7 +62,7 @@ describe('updateSongs', () => {
 
   it('bulk updates multiple songs by merging metadata locally', async () => {
     const backendService = {
-      bulkUpdateSongs: vi.fn(async () => 2),
+      bulkUpdateSongs: vi.fn(async () => ({ updatedSongs: 2, results: [] })),
     } as unknown as BackendService;
 
     const db = [
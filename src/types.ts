@@ -50,6 +50,8 @@ export interface Song {
   filename: string;
   metadata: SongMetadata;
   available: boolean;
+  /** Section of the file a cue sheet track plays, in seconds. */
+  range?: { start: number; end: number | null } | null;
 }
 
 export interface Playlist {